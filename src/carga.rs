@@ -0,0 +1,716 @@
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::cuadro_contable::{masa, AsientoNuevo, Cuadro, LibroDiario, LibroDiarioError};
+use crate::cuadro_contable::apunte::Lado;
+use crate::cuadro_contable::formato::{formato_importe, leer_importe};
+use crate::cuadro_contable::asiento::TipoAsiento;
+use crate::cuadro_contable::masa::Naturaleza;
+use crate::cuadro_contable::moneda::Moneda;
+
+/// Nombre del archivo del cuadro de cuentas dentro del directorio de trabajo
+pub const ARCHIVO_CUADRO: &str = "cuadro.txt";
+/// Nombre del archivo del balance inicial dentro del directorio de trabajo
+pub const ARCHIVO_BALANCE_INICIAL: &str = "balance_inicial.txt";
+
+/// Pares de código de cuenta e importe, tal y como los recibe `LibroDiario::insertar_asiento`
+type Anotaciones<'a> = Vec<(&'a str, Moneda)>;
+
+/// Manejo de posibles errores de la carga de archivos
+#[derive(Debug, PartialEq)]
+pub enum CargaError {
+    /// No se ha podido leer un archivo: su ruta y el motivo
+    ArchivoIlegible(String, String),
+    /// No se ha podido listar la carpeta del diario: su ruta y el motivo
+    CarpetaIlegible(String, String),
+    /// El asiento de apertura del balance inicial no se ha podido anotar
+    AperturaNoAnotada(LibroDiarioError),
+}
+
+impl Display for CargaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CargaError::ArchivoIlegible(ruta, motivo) => write!(f, "Ha habido un error al leer el archivo '{}': {}", ruta, motivo),
+            CargaError::CarpetaIlegible(ruta, motivo) => write!(f, "Imposible listar el directorio '{}': {}", ruta, motivo),
+            CargaError::AperturaNoAnotada(e) => write!(f, "No se ha podido anotar el asiento de apertura: {}", e),
+        }
+    }
+}
+
+/// Carga el cuadro de cuentas, el PGC, el balance inicial que haya en `directorio`, y el libro diario de `path_diario`.
+/// Las cuentas propias del cuadro se cargan antes y mandan sobre las del PGC con el mismo código.
+/// Ningún paso es imprescindible: los que fallan se anotan en los avisos del resumen y la carga sigue
+pub fn cargar(cuadro: &mut Cuadro, libro_diario: &mut LibroDiario, directorio: &Path, path_diario: &Path) -> ResumenCarga {
+
+    let mut avisos = vec![];
+
+    let lineas_ignoradas = cargar_cuadro(cuadro, &directorio.join(ARCHIVO_CUADRO))
+        .unwrap_or_else(|e| {
+            avisos.push(e.to_string());
+            vec![]
+        });
+
+    cuadro.cargar_pgc_complementario();
+    avisos.extend(Cuadro::cuentas_pgc_no_clasificables()
+        .into_iter()
+        .filter(|c| cuadro.buscar_cuenta(c).is_none())
+        .map(|c| format!("Código del PGC sin masa, no se ha cargado: {c}")));
+
+    let apertura = fecha_apertura(cuadro, path_diario);
+    match leer_balance_inicial(cuadro, libro_diario, &directorio.join(ARCHIVO_BALANCE_INICIAL), apertura) {
+        Ok(lineas_ignoradas) => avisos.extend(lineas_ignoradas),
+        Err(e) => avisos.push(e.to_string()),
+    }
+    cuadro.fijar_saldos_apertura();
+
+    let mut resumen = cargar_diario(cuadro, libro_diario, path_diario)
+        .unwrap_or_else(|e| {
+            avisos.push(e.to_string());
+            ResumenCarga::default()
+        });
+    resumen.lineas_cuadro_ignoradas = lineas_ignoradas;
+    resumen.avisos = avisos;
+    resumen
+}
+
+/// Fecha del asiento de apertura: el primer día del ejercicio del cuadro o, si no tiene ejercicio,
+/// la del asiento más antiguo del diario de `path_diario`, contando sus líneas `FECHA`.
+/// Sin ejercicio ni asientos que leer, `None`, y la apertura queda con la fecha de hoy
+fn fecha_apertura(cuadro: &Cuadro, path_diario: &Path) -> Option<NaiveDate> {
+
+    if let Some(ejercicio) = cuadro.ejercicio() {
+        return Some(ejercicio.inicio())
+    }
+
+    fs::read_dir(path_diario).ok()?
+        .flatten()
+        .filter_map(|archivo| {
+            let fecha_archivo = validar_archivo(&archivo).ok()?;
+            let leido = fs::read_to_string(archivo.path()).ok()?.replace("\r\n", "\n");
+            separar_asientos(&leido)
+                .iter()
+                .filter_map(|bloque| leer_fecha(bloque, fecha_archivo).ok().map(|(fecha, _)| fecha))
+                .min()
+        })
+        .min()
+}
+
+/// Error de lectura de un archivo, con la ruta que lo ha provocado
+fn archivo_ilegible(path: &Path, e: std::io::Error) -> CargaError {
+    CargaError::ArchivoIlegible(path.display().to_string(), e.to_string())
+}
+
+/// Lee un archivo de cuadro de cuentas para recuperar las cuentas.
+/// Devuelve las líneas que no se han podido convertir en cuentas, o el error si no se puede leer
+pub fn cargar_cuadro(cuadro: &mut Cuadro, path: &Path) -> Result<Vec<(usize, String)>, CargaError> {
+    let contenido = fs::read_to_string(path).map_err(|e| archivo_ilegible(path, e))?;
+    Ok(procesar_cadena(contenido, cuadro))
+}
+
+/// Toma una serie leída y procesa cada línea escrita en formato <CÓDIGO> <NOMBRE> como una cuenta.
+/// Las líneas en blanco y las que empiezan por '#' (comentarios) se saltan.
+/// Devuelve el número y el texto de las líneas no reconocidas: las que no siguen el formato
+/// y las de cuentas que no se han podido clasificar o crear.
+fn procesar_cadena(cadena: String, cuadro: &mut Cuadro) -> Vec<(usize, String)> {
+
+    let mut no_reconocidas = vec![];
+
+    let re_codigo: regex::Regex = regex::Regex::new(r"^(?P<codigo>[0-9]+)\s+(?P<nombre>.+?)\s*$").unwrap();
+
+    for (numero, linea) in cadena.lines().enumerate() {
+        let linea_limpia = linea.trim();
+        if linea_limpia.is_empty() || linea_limpia.starts_with('#') {
+            continue;
+        }
+
+        let Some(c) = re_codigo.captures(linea_limpia) else {
+            no_reconocidas.push((numero + 1, linea_limpia.to_string()));
+            continue;
+        };
+
+        match masa::interpretar_codigo(&c["codigo"]) {
+            Some(m) => {
+                // Por ejemplo, porque el código o el nombre ya salen en una línea anterior
+                if cuadro.crear_cuenta(&c["nombre"], &c["codigo"], m).is_err() {
+                    no_reconocidas.push((numero + 1, linea_limpia.to_string()));
+                }
+            },
+            None => no_reconocidas.push((numero + 1, linea_limpia.to_string())),
+        }
+    }
+
+    no_reconocidas
+}
+
+/// Resumen de la carga del libro diario
+#[derive(Debug, Default)]
+pub struct ResumenCarga {
+    /// Archivos con nombre válido que se han leído
+    pub archivos_leidos: usize,
+    /// Asientos insertados en el libro diario
+    pub asientos_insertados: usize,
+    /// Archivos descartados y el motivo
+    pub archivos_ignorados: Vec<(String, String)>,
+    /// Suma del debe de los asientos insertados
+    pub total_debe: Moneda,
+    /// Suma del haber de los asientos insertados
+    pub total_haber: Moneda,
+    /// Líneas de 'cuadro.txt' que no se han convertido en cuentas, con su número
+    pub lineas_cuadro_ignoradas: Vec<(usize, String)>,
+    /// Pasos de la carga que han fallado sin detenerla, como la falta de 'cuadro.txt'
+    pub avisos: Vec<String>,
+}
+
+impl Display for ResumenCarga {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Archivos leídos: {}", self.archivos_leidos)?;
+        writeln!(f, "Asientos insertados: {}", self.asientos_insertados)?;
+        writeln!(f, "Total debe: {}", formato_importe(self.total_debe))?;
+        writeln!(f, "Total haber: {}", formato_importe(self.total_haber))?;
+        writeln!(f, "Archivos ignorados: {}", self.archivos_ignorados.len())?;
+        for (archivo, motivo) in &self.archivos_ignorados {
+            writeln!(f, "  {}: {}", archivo, motivo)?;
+        }
+        if !self.lineas_cuadro_ignoradas.is_empty() {
+            writeln!(f, "Líneas no reconocidas en 'cuadro.txt': {}", self.lineas_cuadro_ignoradas.len())?;
+            for (numero, linea) in &self.lineas_cuadro_ignoradas {
+                writeln!(f, "  línea {}: '{}'", numero, linea)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Procesa una carpeta y procesa los posibles archivos de asientos, que deben tener formato <YYYYMMDD>[Nº].data.
+/// Los archivos rechazados se anotan en el resumen con su motivo; solo falla si la carpeta no se puede listar
+pub fn cargar_diario(cuadro: &mut Cuadro, libro_diario: &mut LibroDiario, path: &Path) -> Result<ResumenCarga, CargaError> {
+
+    let mut resumen = ResumenCarga::default();
+
+    let carpeta = fs::read_dir(path)
+        .map_err(|e| CargaError::CarpetaIlegible(path.display().to_string(), e.to_string()))?;
+
+    for archivo in carpeta.flatten() {
+        let nombre_archivo = archivo.file_name().to_string_lossy().to_string();
+
+        let fecha = match validar_archivo(&archivo) {
+            Ok(f) => f,
+            Err(motivo) => {
+                resumen.archivos_ignorados.push((nombre_archivo, motivo));
+                continue
+            }
+        };
+
+        resumen.archivos_leidos += 1;
+
+        match leer_asientos(&archivo.path(), fecha, cuadro, libro_diario) {
+            Ok((asientos, debe, haber)) => {
+                resumen.asientos_insertados += asientos;
+                resumen.total_debe += debe;
+                resumen.total_haber += haber;
+            },
+            Err(motivo) => resumen.archivos_ignorados.push((nombre_archivo, motivo)),
+        }
+    }
+
+    Ok(resumen)
+}
+
+/// Valida que la ruta y archivo son correctos. Devuelve la fecha si lo ha leído bien, o el motivo por el que no.
+fn validar_archivo(ruta: &fs::DirEntry) -> Result<NaiveDate, String> {
+    match ruta.file_name().into_string() {
+        Ok(nombre) => validar_nombre_archivo(&nombre),
+        Err(_e) => Err("el nombre no es una cadena válida".to_string()),
+    }
+}
+
+/// Valida el nombre de un archivo de asiento: `<YYYYMMDD>.data` o `<YYYYMMDD><Nº>.data`,
+/// donde el número de orden del día es opcional. Devuelve la fecha o el motivo del rechazo.
+fn validar_nombre_archivo(nombre: &str) -> Result<NaiveDate, String> {
+
+    let Some(base) = nombre.strip_suffix(".data") else {
+        return Err("la extensión no es '.data'".to_string());
+    };
+
+    let formato_archivo = regex::Regex::new(r"^(?P<fecha>[0-9]{8})[0-9]*$").unwrap();
+    let cap = formato_archivo.captures(base)
+        .ok_or("el nombre no sigue el formato <YYYYMMDD>[Nº].data".to_string())?;
+
+    NaiveDate::parse_from_str(&cap["fecha"], "%Y%m%d")
+        .map_err(|_| format!("la fecha '{}' no es válida", &cap["fecha"]))
+}
+
+/// Lee los asientos del archivo `path` y los inserta en el libro diario con la fecha dada, todos o ninguno.
+/// Cada asiento termina en una línea `///`, la del último es opcional. Si un asiento empieza con una línea
+/// `FECHA YYYY-MM-DD`, esa fecha tiene prioridad sobre la del nombre del archivo.
+/// Devuelve el número de asientos y los totales del debe y del haber insertados, o el motivo por el que no se ha cargado.
+pub fn leer_asientos(path: &Path, fecha: NaiveDate, cuadro: &mut Cuadro, libro_diario: &mut LibroDiario) -> Result<(usize, Moneda, Moneda), String> {
+
+    // Los saltos de línea de Windows se normalizan para que el concepto conserve sus líneas
+    let leido = fs::read_to_string(path)
+        .map_err(|e| format!("imposible leer el archivo: {e}"))?
+        .replace("\r\n", "\n");
+
+    let bloques = separar_asientos(&leido);
+    if bloques.is_empty() {
+        return Err("no contiene un asiento bien formado".to_string());
+    }
+
+    // Si hay más de un asiento, los errores indican cuál falla
+    let motivo = |posicion: Option<usize>, motivo: String| match posicion {
+        Some(n) if bloques.len() > 1 => format!("asiento {n}: {motivo}"),
+        _ => motivo,
+    };
+
+    let mut asientos = vec![];
+    for (i, bloque) in bloques.iter().enumerate() {
+        asientos.push(leer_bloque(bloque, fecha).map_err(|m| motivo(Some(i + 1), m))?);
+    }
+
+    let total = |lineas: &[(&str, Moneda)]| lineas.iter().map(|(_, i)| *i).sum::<Moneda>();
+    let numero = asientos.len();
+    let total_debe: Moneda = asientos.iter().map(|a| total(&a.debe)).sum();
+    let total_haber: Moneda = asientos.iter().map(|a| total(&a.haber)).sum();
+
+    libro_diario.insertar_varios(asientos, cuadro)
+        .map_err(|(posicion, e)| motivo(posicion, e.to_string()))?;
+
+    Ok((numero, total_debe, total_haber))
+}
+
+/// Separa el texto de un archivo `.data` en los bloques de cada asiento, que terminan en una línea `///`.
+/// Los bloques vacíos, como el que queda tras el último `///`, se descartan
+fn separar_asientos(texto: &str) -> Vec<String> {
+    let mut bloques = vec![String::new()];
+    for linea in texto.lines() {
+        match linea.trim() {
+            "///" => bloques.push(String::new()),
+            _ => if let Some(bloque) = bloques.last_mut() {
+                bloque.push_str(linea);
+                bloque.push('\n');
+            },
+        }
+    }
+    bloques.into_iter()
+        .map(|b| b.trim_matches(|c: char| c.is_whitespace()).to_string())
+        .filter(|b| !b.is_empty())
+        .collect()
+}
+
+/// Interpreta el bloque de un asiento: la línea `FECHA` opcional, el concepto y los bloques DEBE y HABER
+fn leer_bloque(bloque: &str, fecha_archivo: NaiveDate) -> Result<AsientoNuevo<'_>, String> {
+
+    // La línea FECHA, si la hay, manda sobre la fecha del nombre del archivo
+    let (fecha, resto) = leer_fecha(bloque, fecha_archivo)?;
+    let (tipo, resto) = leer_tipo(resto)?;
+
+    let concepto_expr = regex::Regex::new(r"^(?s)(?P<concepto>.+?)\n\nDEBE\n(?P<debe>.+?)\n\nHABER\n(?P<haber>.+?)\s*\z").unwrap();
+
+    let cap = concepto_expr.captures(resto)
+        .ok_or("no contiene un asiento bien formado".to_string())?;
+
+    Ok(AsientoNuevo {
+        // Concepto del asiento, que puede ocupar varias líneas
+        concepto: leer_concepto(cap.name("concepto").map_or("", |m| m.as_str())),
+        fecha: Some(fecha),
+        tipo,
+        // Movimientos del debe y del haber
+        debe: leer_movimientos(cap.name("debe").map_or("", |m| m.as_str()), Lado::Debe)?,
+        haber: leer_movimientos(cap.name("haber").map_or("", |m| m.as_str()), Lado::Haber)?,
+    })
+}
+
+/// Limpia el concepto de un asiento conservando sus líneas, que el `Display` del asiento muestra una a una:
+/// quita los espacios de los extremos de cada línea y las líneas en blanco
+fn leer_concepto(bloque: &str) -> String {
+    bloque
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Separa la línea `FECHA YYYY-MM-DD` del principio de un asiento, si la hay.
+/// Devuelve la fecha que corresponde al asiento y el resto del texto; si no hay línea de fecha, la del archivo.
+fn leer_fecha(asiento: &str, fecha_archivo: NaiveDate) -> Result<(NaiveDate, &str), String> {
+
+    let Some(resto) = asiento.strip_prefix("FECHA ") else {
+        return Ok((fecha_archivo, asiento))
+    };
+
+    let (fecha, resto) = resto.split_once('\n').unwrap_or((resto, ""));
+    let fecha = fecha.trim();
+
+    NaiveDate::parse_from_str(fecha, "%Y-%m-%d")
+        .map(|f| (f, resto))
+        .map_err(|_| format!("la fecha '{fecha}' no es válida"))
+}
+
+/// Separa la línea `TIPO <tipo>` que sigue a la fecha, si la hay, y devuelve el tipo y el resto del texto.
+/// Sin esa línea, el asiento es normal
+fn leer_tipo(asiento: &str) -> Result<(TipoAsiento, &str), String> {
+
+    let Some(resto) = asiento.strip_prefix("TIPO ") else {
+        return Ok((TipoAsiento::Normal, asiento))
+    };
+
+    let (tipo, resto) = resto.split_once('\n').unwrap_or((resto, ""));
+
+    tipo.parse::<TipoAsiento>()
+        .map(|t| (t, resto))
+        .map_err(|tipo| format!("el tipo de asiento '{tipo}' no existe"))
+}
+
+/// Interpreta un bloque de líneas <Código de cuenta> <Importe> como movimientos del lado indicado.
+/// Un importe que falta o no se puede leer, o cualquier texto de más tras el importe, es un error que señala
+/// la línea (desde 1) y el lado
+fn leer_movimientos(bloque: &str, lado: Lado) -> Result<Anotaciones<'_>, String> {
+    bloque
+        .split('\n')
+        .filter(|v| !v.trim().is_empty())
+        .enumerate()
+        .map(|(i, v)| {
+            let movimiento: Vec<&str> = v.split_whitespace().collect();
+            match movimiento[..] {
+                [codigo_cuenta] => Err(format!("falta el importe de la línea {} del {} ('{}')", i + 1, lado, codigo_cuenta)),
+                [codigo_cuenta, importe] => leer_importe(importe)
+                    .map(|importe| (codigo_cuenta, importe))
+                    .ok_or_else(|| format!("el importe '{}' de la línea {} del {} no es válido", importe, i + 1, lado)),
+                [_, _, ..] => Err(format!("la línea {} del {} tiene más campos que el código y el importe ('{}')", i + 1, lado, v.trim())),
+                [] => unreachable!("las líneas en blanco se han descartado"),
+            }
+        })
+        .collect()
+}
+
+/// Lee un archivo de balance inicial y anota el asiento de apertura en la fecha indicada, que debería ser
+/// la del inicio del ejercicio para que la apertura vaya antes que el resto de asientos (sin fecha, hoy).
+/// Devuelve un aviso por cada línea que no se ha entendido y se ha ignorado
+pub fn leer_balance_inicial(cuadro: &mut Cuadro, libro_diario: &mut LibroDiario, path: &Path, fecha: Option<NaiveDate>) -> Result<Vec<String>, CargaError> {
+
+    let archivo = fs::read_to_string(path).map_err(|e| archivo_ilegible(path, e))?;
+
+    let (vec_debe, vec_haber, avisos) = interpretar_balance_inicial(&archivo);
+
+    libro_diario.insertar_asiento("Asiento de apertura", fecha, vec_debe, vec_haber, cuadro)
+        .map_err(CargaError::AperturaNoAnotada)?;
+
+    let codigo = libro_diario.asientos().last().map(|a| a.codigo()).unwrap_or_default();
+    libro_diario.marcar_tipo(&codigo, TipoAsiento::Apertura).map_err(CargaError::AperturaNoAnotada)?;
+    Ok(avisos)
+}
+
+/// Indica si una línea es una cabecera de sección del balance inicial, sin distinguir mayúsculas
+/// ni espacios de más ("Pasivo  corriente " es lo mismo que "PASIVO CORRIENTE")
+fn es_cabecera(linea: &str) -> bool {
+
+    let normalizada = linea
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_uppercase();
+
+    matches!(
+        normalizada.as_str(),
+        "ACTIVO" | "ACTIVO CORRIENTE" | "ACTIVO NO CORRIENTE"
+            | "PASIVO" | "PASIVO CORRIENTE" | "PASIVO NO CORRIENTE"
+            | "PATRIMONIO" | "PATRIMONIO NETO"
+    )
+}
+
+/// Separa las líneas <Código de cuenta> <Importe> del balance inicial en debe y haber.
+/// El lado lo decide la masa real de la cuenta según el PGC: las de naturaleza deudora (activo) van al debe
+/// y las acreedoras (patrimonio neto y pasivo) al haber, igual que las correctoras del activo, como la amortización
+/// acumulada. Un saldo negativo es contrario a su naturaleza (un banco en descubierto) y va al otro lado,
+/// en positivo; los saldos a cero no se anotan. Las cabeceras solo sirven para organizar el archivo.
+/// Las líneas en blanco se saltan y las que no se entienden se ignoran, con un aviso para cada una.
+fn interpretar_balance_inicial(contenido: &str) -> (Anotaciones<'_>, Anotaciones<'_>, Vec<String>) {
+
+    let mut vec_debe: Anotaciones = vec![];
+    let mut vec_haber: Anotaciones = vec![];
+    let mut avisos = vec![];
+
+    for (numero, linea) in contenido.lines().enumerate() {
+        if linea.trim().is_empty() || es_cabecera(linea) {
+            continue;
+        }
+
+        let read: Vec<&str> = linea.split_whitespace().collect();
+
+        let [codigo_cuenta, importe] = read[..] else {
+            avisos.push(format!("Balance inicial, línea {}: no se reconoce '{}'", numero + 1, linea.trim()));
+            continue;
+        };
+
+        let Some(importe_parsed) = leer_importe(importe) else {
+            avisos.push(format!("Balance inicial, línea {}: el importe '{}' no es válido", numero + 1, importe));
+            continue;
+        };
+
+        let naturaleza = match masa::naturaleza_codigo(codigo_cuenta) {
+            Some(naturaleza) if importe_parsed < Moneda::CERO => naturaleza.contraria(),
+            Some(naturaleza) => naturaleza,
+            None => {
+                avisos.push(format!("Balance inicial, línea {}: no se ha podido clasificar la cuenta '{}'", numero + 1, codigo_cuenta));
+                continue;
+            },
+        };
+
+        if importe_parsed == Moneda::CERO {
+            continue;
+        }
+        match naturaleza {
+            Naturaleza::Deudora => vec_debe.push((codigo_cuenta, importe_parsed.abs())),
+            Naturaleza::Acreedora => vec_haber.push((codigo_cuenta, importe_parsed.abs())),
+        }
+    }
+
+    (vec_debe, vec_haber, avisos)
+}
+
+#[cfg(test)]
+mod carga_tests {
+
+    use super::*;
+
+    #[test]
+    fn validar_nombre_archivo_admite_el_numero_de_orden_opcional() {
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+
+        assert_eq!(validar_nombre_archivo("20230809.data"), Ok(fecha));
+        assert_eq!(validar_nombre_archivo("202308094.data"), Ok(fecha));
+        assert_eq!(validar_nombre_archivo("2023080912.data"), Ok(fecha));
+    }
+
+    #[test]
+    fn validar_nombre_archivo_explica_el_motivo_del_rechazo() {
+        assert_eq!(validar_nombre_archivo("20230809.txt"), Err("la extensión no es '.data'".to_string()));
+        assert_eq!(validar_nombre_archivo("2023089.data"), Err("el nombre no sigue el formato <YYYYMMDD>[Nº].data".to_string()));
+        assert_eq!(validar_nombre_archivo("asiento.data"), Err("el nombre no sigue el formato <YYYYMMDD>[Nº].data".to_string()));
+        assert_eq!(validar_nombre_archivo("20231345.data"), Err("la fecha '20231345' no es válida".to_string()));
+    }
+
+    #[test]
+    fn leer_movimientos_no_pone_a_cero_los_importes_con_coma() {
+        assert_eq!(leer_movimientos("600 1500,00\n472 315,5", Lado::Debe), Ok(vec![("600", Moneda::from_euros(1500.00)), ("472", Moneda::from_euros(315.50))]));
+    }
+
+    #[test]
+    fn leer_fecha_prioriza_la_linea_fecha_sobre_la_del_archivo() {
+        let fecha_archivo = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+
+        assert_eq!(
+            leer_fecha("FECHA 2023-08-15\nVenta\n\nDEBE\n572 10\n\nHABER\n700 10", fecha_archivo),
+            Ok((NaiveDate::from_ymd_opt(2023, 8, 15).unwrap(), "Venta\n\nDEBE\n572 10\n\nHABER\n700 10"))
+        );
+        assert_eq!(leer_fecha("Venta\n\nDEBE", fecha_archivo), Ok((fecha_archivo, "Venta\n\nDEBE")));
+        assert!(leer_fecha("FECHA 2023-13-01\nVenta", fecha_archivo).is_err());
+    }
+
+    #[test]
+    fn leer_asientos_inserta_cada_asiento_con_su_tipo() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc().unwrap();
+        let mut libro_diario = LibroDiario::new();
+        let path = std::env::temp_dir().join("presupuestos_tipo.data");
+        fs::write(&path, "FECHA 2024-01-20\nTIPO Rectificativo\nAbono\n\nDEBE\n430 -121\n\nHABER\n700 -100\n477 -21\n///\nVenta\n\nDEBE\n430 121\n\nHABER\n700 100\n477 21\n").unwrap();
+        let fecha = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        let lectura = leer_asientos(&path, fecha, &mut cuadro, &mut libro_diario);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lectura, Ok((2, Moneda::from_euros(0.0), Moneda::from_euros(0.0))));
+        let tipos: Vec<TipoAsiento> = libro_diario.asientos().map(|a| a.tipo()).collect();
+        assert_eq!(tipos, vec![TipoAsiento::Rectificativo, TipoAsiento::Normal]);
+        assert_eq!(cuadro.buscar_cuenta("430").unwrap().saldo(), Moneda::from_euros(0.0));
+    }
+
+    #[test]
+    fn leer_tipo_es_normal_sin_linea_tipo_y_rechaza_los_desconocidos() {
+        assert_eq!(leer_tipo("TIPO Regularización\nRegularización"), Ok((TipoAsiento::Regularizacion, "Regularización")));
+        assert_eq!(leer_tipo("Venta\n\nDEBE"), Ok((TipoAsiento::Normal, "Venta\n\nDEBE")));
+        assert_eq!(leer_tipo("TIPO Extraordinario\nVenta"), Err("el tipo de asiento 'Extraordinario' no existe".to_string()));
+    }
+
+    #[test]
+    fn interpretar_balance_inicial_tolera_cabeceras_y_lineas_en_blanco() {
+        let contenido = "activo corriente \n572 1000\n\n  PASIVO   CORRIENTE\n400 300,50\nPatrimonio neto\n100 abc\n100 699,50\n";
+
+        let (debe, haber, avisos) = interpretar_balance_inicial(contenido);
+
+        assert_eq!(debe, vec![("572", Moneda::from_euros(1000.0))]);
+        assert_eq!(haber, vec![("400", Moneda::from_euros(300.50)), ("100", Moneda::from_euros(699.50))]);
+        assert_eq!(avisos, vec!["Balance inicial, línea 7: el importe 'abc' no es válido".to_string()]);
+        assert!(es_cabecera("PASIVO CORRIENTE "));
+        assert!(!es_cabecera("572 1000"));
+    }
+
+    #[test]
+    fn interpretar_balance_inicial_lleva_las_correctoras_al_haber() {
+        let (debe, haber, _) = interpretar_balance_inicial("ACTIVO NO CORRIENTE\n213 10000\n2813 2500\nPATRIMONIO NETO\n100 7500\n");
+
+        assert_eq!(debe, vec![("213", Moneda::from_euros(10000.0))]);
+        assert_eq!(haber, vec![("2813", Moneda::from_euros(2500.0)), ("100", Moneda::from_euros(7500.0))]);
+    }
+
+    #[test]
+    fn interpretar_balance_inicial_decide_el_lado_por_la_masa_de_la_cuenta() {
+        // Aunque esté bajo la cabecera de pasivo, la 572 es de activo y va al debe
+        let (debe, haber, _) = interpretar_balance_inicial("PASIVO\n400 100\n572 100\n");
+
+        assert_eq!(debe, vec![("572", Moneda::from_euros(100.0))]);
+        assert_eq!(haber, vec![("400", Moneda::from_euros(100.0))]);
+    }
+
+    #[test]
+    fn interpretar_balance_inicial_lleva_los_saldos_negativos_al_otro_lado() {
+        // Un banco en descubierto y un proveedor con saldo a favor; la línea a cero no se anota
+        let (debe, haber, avisos) = interpretar_balance_inicial("572 -150,25
+400 -80
+430 0
+100 1000
+");
+
+        assert_eq!(debe, vec![("400", Moneda::from_euros(80.0))]);
+        assert_eq!(haber, vec![("572", Moneda::from_euros(150.25)), ("100", Moneda::from_euros(1000.0))]);
+        assert!(avisos.is_empty());
+    }
+
+    #[test]
+    fn procesar_cadena_salta_comentarios_y_lineas_en_blanco() {
+        let mut cuadro = Cuadro::new();
+        let contenido = "# Tesorería\n572 Bancos\n\n   \n# Compras\n600 Compras de mercaderías\n700 Ventas".to_string();
+
+        assert!(procesar_cadena(contenido, &mut cuadro).is_empty());
+
+        let codigos: Vec<String> = cuadro.cuentas().map(|c| c.codigo()).collect();
+        assert_eq!(codigos, vec!["572".to_string(), "600".to_string(), "700".to_string()]);
+    }
+
+    #[test]
+    fn procesar_cadena_devuelve_las_lineas_no_reconocidas() {
+        let mut cuadro = Cuadro::new();
+        let contenido = "572 Bancos\nBancos 572\n600\n700 Ventas".to_string();
+
+        let no_reconocidas = procesar_cadena(contenido, &mut cuadro);
+
+        assert_eq!(no_reconocidas, vec![(2, "Bancos 572".to_string()), (3, "600".to_string())]);
+        assert!(cuadro.buscar_cuenta("700").is_some());
+    }
+
+    #[test]
+    fn procesar_cadena_devuelve_las_cuentas_que_no_se_pueden_crear() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        let contenido = "572 Banco Uno\n5720 Banco Uno\n700 Ventas\n700 Ventas de mercaderías".to_string();
+
+        let no_reconocidas = procesar_cadena(contenido, &mut cuadro);
+
+        // La 572 ya existía y la 700 está repetida
+        assert_eq!(no_reconocidas, vec![(1, "572 Banco Uno".to_string()), (4, "700 Ventas de mercaderías".to_string())]);
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().nombre(), "Bancos");
+    }
+
+    #[test]
+    fn leer_concepto_conserva_las_lineas_hasta_la_pantalla() {
+        let concepto = leer_concepto("Compra de mercaderías  \n  a Suministros Pérez\n\nfactura 2023/118\n");
+        assert_eq!(concepto, "Compra de mercaderías\na Suministros Pérez\nfactura 2023/118");
+
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Compras", "600", masa::Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Proveedores", "400", masa::Masa::PasivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento(&concepto, None, vec![("600", Moneda::from_euros(10.0))], vec![("400", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+
+        let pantalla = libro_diario.to_string();
+        let lineas: Vec<&str> = pantalla.lines().map(|l| l.trim_matches(|c| c == '|' || c == ' ')).collect();
+        assert!(lineas.windows(3).any(|v| v == ["Compra de mercaderías", "a Suministros Pérez", "factura 2023/118"]));
+    }
+
+    #[test]
+    fn las_funciones_de_carga_devuelven_el_error_de_la_ruta() {
+        let mut cuadro = Cuadro::new();
+        let mut libro_diario = LibroDiario::new();
+        let inexistente = Path::new("no_existe");
+
+        assert!(matches!(cargar_cuadro(&mut cuadro, &inexistente.join(ARCHIVO_CUADRO)), Err(CargaError::ArchivoIlegible(..))));
+        assert!(matches!(leer_balance_inicial(&mut cuadro, &mut libro_diario, inexistente, None), Err(CargaError::ArchivoIlegible(..))));
+        assert!(matches!(cargar_diario(&mut cuadro, &mut libro_diario, inexistente), Err(CargaError::CarpetaIlegible(..))));
+
+        // La carga completa no se detiene: anota los avisos y sigue con el PGC
+        let resumen = cargar(&mut cuadro, &mut libro_diario, inexistente, inexistente);
+        assert_eq!(resumen.avisos.len(), 3);
+        assert!(resumen.avisos[2].starts_with("Imposible listar el directorio 'no_existe'"));
+        assert!(cuadro.buscar_cuenta("572").is_some());
+    }
+
+    #[test]
+    fn leer_asientos_inserta_el_asiento_de_un_archivo() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc().unwrap();
+        let mut libro_diario = LibroDiario::new();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/diario/202401150.data");
+        let fecha = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        assert_eq!(leer_asientos(&path, fecha, &mut cuadro, &mut libro_diario), Ok((1, Moneda::from_euros(2420.0), Moneda::from_euros(2420.0))));
+        assert_eq!(libro_diario.asientos().last().unwrap().concepto(), "Venta de mercaderías");
+        assert!(leer_asientos(Path::new("no_existe.data"), fecha, &mut cuadro, &mut libro_diario).is_err());
+    }
+
+    #[test]
+    fn leer_asientos_inserta_cada_bloque_del_archivo_con_su_fecha() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc().unwrap();
+        let mut libro_diario = LibroDiario::new();
+        let path = std::env::temp_dir().join("presupuestos_dos_asientos.data");
+        fs::write(&path, "FECHA 2024-01-16\nCompra\n\nDEBE\n600 100\n\nHABER\n400 100\n\n///\nVenta\n\nDEBE\n572 30\n\nHABER\n700 30\n///").unwrap();
+        let fecha = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        let lectura = leer_asientos(&path, fecha, &mut cuadro, &mut libro_diario);
+
+        assert_eq!(lectura, Ok((2, Moneda::from_euros(130.0), Moneda::from_euros(130.0))));
+        let asientos: Vec<_> = libro_diario.asientos().collect();
+        assert_eq!(asientos.iter().map(|a| a.concepto()).collect::<Vec<String>>(), vec!["Compra", "Venta"]);
+        assert_eq!(asientos[0].fecha(), NaiveDate::from_ymd_opt(2024, 1, 16).unwrap());
+        assert_eq!(asientos[1].fecha(), fecha);
+        assert_eq!(cuadro.buscar_cuenta("600").unwrap().saldo(), Moneda::from_euros(100.0));
+
+        // Si un bloque falla no se inserta ninguno, y el motivo dice cuál
+        fs::write(&path, "Compra\n\nDEBE\n600 100\n\nHABER\n400 100\n///\nVenta\n\nDEBE\n572 30\n\nHABER\n700 20\n///").unwrap();
+        let lectura = leer_asientos(&path, fecha, &mut cuadro, &mut libro_diario);
+        fs::remove_file(&path).unwrap();
+
+        assert!(lectura.unwrap_err().starts_with("asiento 2: el debe y el haber"));
+        assert_eq!(libro_diario.asientos().count(), 2);
+    }
+
+    #[test]
+    fn leer_asientos_rechaza_los_importes_ilegibles() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc().unwrap();
+        let mut libro_diario = LibroDiario::new();
+        let path = std::env::temp_dir().join("presupuestos_importe_ilegible.data");
+        fs::write(&path, "Venta\n\nDEBE\n572 10\n\nHABER\n700 diez\n").unwrap();
+        let fecha = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+
+        let lectura = leer_asientos(&path, fecha, &mut cuadro, &mut libro_diario);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(lectura, Err("el importe 'diez' de la línea 1 del haber no es válido".to_string()));
+        assert_eq!(libro_diario.asientos().count(), 0);
+    }
+
+    #[test]
+    fn leer_movimientos_senala_la_linea_sin_importe_o_con_importe_ilegible() {
+        assert_eq!(leer_movimientos("572 10\n700", Lado::Haber), Err("falta el importe de la línea 2 del haber ('700')".to_string()));
+        assert_eq!(leer_movimientos("572 10\n477 2l,10", Lado::Debe), Err("el importe '2l,10' de la línea 2 del debe no es válido".to_string()));
+        assert_eq!(
+            leer_movimientos("572 100,00 basura", Lado::Debe),
+            Err("la línea 1 del debe tiene más campos que el código y el importe ('572 100,00 basura')".to_string())
+        );
+    }
+}
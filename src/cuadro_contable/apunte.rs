@@ -0,0 +1,65 @@
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+
+use super::moneda::Moneda;
+
+/// Lado de la cuenta en el que se anota un importe
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lado {
+    Debe,
+    Haber,
+}
+
+impl Display for Lado {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Lado::Debe => write!(f, "debe"),
+            Lado::Haber => write!(f, "haber"),
+        }
+    }
+}
+
+/// Representa una anotación en el libro mayor de una cuenta.
+/// Se guarda en la propia cuenta al insertar el asiento, de modo que el mayor
+/// no requiere recorrer el libro diario.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Apunte {
+    fecha: NaiveDate,
+    importe: Moneda,
+    lado: Lado,
+    codigo_asiento: String,
+}
+
+impl Apunte {
+
+    /// Crea un apunte con la fecha y el código del asiento del que procede
+    pub fn new(fecha: NaiveDate, importe: Moneda, lado: Lado, codigo_asiento: &str) -> Apunte {
+        Apunte {
+            fecha,
+            importe,
+            lado,
+            codigo_asiento: codigo_asiento.to_string(),
+        }
+    }
+
+    /// Devuelve la fecha del apunte
+    pub fn fecha(&self) -> NaiveDate {
+        self.fecha
+    }
+
+    /// Devuelve el importe del apunte
+    pub fn importe(&self) -> Moneda {
+        self.importe
+    }
+
+    /// Devuelve el lado (debe o haber) en el que se anotó
+    pub fn lado(&self) -> Lado {
+        self.lado
+    }
+
+    /// Devuelve el código del asiento del que procede
+    pub fn codigo_asiento(&self) -> String {
+        self.codigo_asiento.clone()
+    }
+}
@@ -1,37 +1,123 @@
 use std::fmt::Display;
+use std::str::FromStr;
 
 use chrono::{NaiveDate, offset};
 
-use super::movimiento::Movimiento;
+use super::formato::{formato_importe, formato_importe_divisa};
+use super::moneda::Moneda;
+use super::movimiento::{Divisa, Movimiento};
+
+/// Papel del asiento en el ejercicio. El orden de las variantes es el orden formal del libro diario
+/// dentro de un mismo día: la apertura siempre primero, luego los asientos normales, las rectificativas,
+/// la regularización y el cierre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum TipoAsiento {
+    Apertura,
+    #[default]
+    Normal,
+    /// Corrige asientos anteriores y, por eso, admite importes negativos
+    Rectificativo,
+    Regularizacion,
+    Cierre,
+}
+
+impl Display for TipoAsiento {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TipoAsiento::Apertura => write!(f, "Apertura"),
+            TipoAsiento::Normal => write!(f, "Normal"),
+            TipoAsiento::Rectificativo => write!(f, "Rectificativo"),
+            TipoAsiento::Regularizacion => write!(f, "Regularización"),
+            TipoAsiento::Cierre => write!(f, "Cierre"),
+        }
+    }
+}
+
+impl FromStr for TipoAsiento {
+    type Err = String;
+
+    /// Interpreta el nombre que escribe `Display`. Si no existe, devuelve el texto recibido
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "Apertura" => Ok(TipoAsiento::Apertura),
+            "Normal" => Ok(TipoAsiento::Normal),
+            "Rectificativo" => Ok(TipoAsiento::Rectificativo),
+            "Regularización" => Ok(TipoAsiento::Regularizacion),
+            "Cierre" => Ok(TipoAsiento::Cierre),
+            otro => Err(otro.to_string()),
+        }
+    }
+}
 
 /// Representa un asiento contable.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub struct Asiento {
     debe: Vec<Movimiento>,
     haber: Vec<Movimiento>,
     concepto: String,
     fecha: NaiveDate,
     codigo: String, 
-    comprobacion: f64,
+    /// Proveedor, cliente u otro tercero al que se refiere el asiento, si lo hay
+    tercero: Option<String>,
+    /// Referencia al documento que respalda el asiento (nombre de archivo o URL), si la hay
+    justificante: Option<String>,
+    /// Apertura, cierre, regularización, rectificativo o asiento normal
+    tipo: TipoAsiento,
 }
 
 impl Display for Asiento {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 
-        let cod_fmt = format!("N.º {}", self.codigo);
+        let cod_fmt = match self.tipo {
+            TipoAsiento::Normal => format!("N.º {}", self.codigo),
+            tipo => format!("N.º {} ({})", self.codigo, tipo),
+        };
         let vec_concepto = self.concepto.split('\n');
-        let w = 120;
+        let col = Asiento::ANCHO_COLUMNA;
+        let w = Asiento::ANCHO;
 
-        write!(f, "+{:-^width$}+\n","", width=w - 2)?;
-        write!(f, "|{:^width$}|\n", cod_fmt, width=w - 2)?;
+        writeln!(f, "+{:-^width$}+", "", width=w - 2)?;
+        writeln!(f, "|{:^width$}|", cod_fmt, width=w - 2)?;
+        if let Some(tercero) = &self.tercero {
+            writeln!(f, "|{:^width$}|", format!("Tercero: {}", tercero), width=w - 2)?;
+        }
         for line in vec_concepto {
-            write!(f, "|{:^width$}|\n", line, width=w - 2)?;
+            writeln!(f, "|{:^width$}|", line, width=w - 2)?;
+        }
+        writeln!(f, "|{:^width$}|", &self.fecha.format("%Y-%m-%d"), width=w - 2)?;
+        if let Some(justificante) = &self.justificante {
+            writeln!(f, "|{:^width$}|", format!("Justificante: {}", justificante), width=w - 2)?;
         }
-        write!(f, "|{:^width$}|\n",&self.fecha.format("%Y-%m-%d"), width=w - 2)?;
-        write!(f, "+{:-^width$}+\n","", width=w - 2)?;
+        writeln!(f, "+{:-<col$}+{:-<col$}+", "", "")?;
+        writeln!(f, "|{:^col$}|{:^col$}|", "DEBE", "HABER")?;
+        writeln!(f, "+{:-<col$}+{:-<col$}+", "", "")?;
+
+        // Cada celda lleva la cuenta a la izquierda y el importe a la derecha, precedido del importe
+        // original si el movimiento se hizo en otra divisa; si no cabe todo, se recorta el nombre de la cuenta
+        let celda = |texto: String, importe: String| {
+            let texto: String = texto.chars().take(col - 4 - importe.chars().count()).collect();
+            format!(" {}{:>ancho$} ", texto, importe, ancho = col - 2 - texto.chars().count())
+        };
+        let importe = |m: &Movimiento| match m.divisa() {
+            Divisa::Eur => formato_importe(m.importe()),
+            divisa => format!("({}) {}", formato_importe_divisa(m.importe_divisa(), divisa), formato_importe(m.importe())),
+        };
+        let celdas = |movimientos: &[Movimiento]| movimientos
+            .iter()
+            .map(|m| celda(format!("({}) {}", m.codigo_cuenta(), m.nombre_cuenta()), importe(m)))
+            .collect::<Vec<String>>();
+        let debe = celdas(&self.debe);
+        let haber = celdas(&self.haber);
 
+        for i in 0..debe.len().max(haber.len()) {
+            let izquierda = debe.get(i).cloned().unwrap_or(" ".repeat(col));
+            let derecha = haber.get(i).cloned().unwrap_or(" ".repeat(col));
+            writeln!(f, "|{}|{}|", izquierda, derecha)?;
+        }
 
-        write!(f, "+{:-^width$}+\n","", width=w - 2)?;
+        writeln!(f, "+{:-<col$}+{:-<col$}+", "", "")?;
+        writeln!(f, "|{}|{}|", celda("Total".to_string(), formato_importe(self.total_debe())), celda("Total".to_string(), formato_importe(self.total_haber())))?;
+        writeln!(f, "+{:-^width$}+", "", width=w - 2)?;
 
         Ok(())
 
@@ -40,20 +126,8 @@ impl Display for Asiento {
 
 impl Asiento {
 
-    /// Crea un nuevo asiento a partir de un concepto
+    /// Crea un nuevo asiento a partir de un concepto.
     pub fn new(concepto: &str, fecha: Option<NaiveDate>, debe: Vec<Movimiento>, haber: Vec<Movimiento>) -> Asiento {
-        let saldo_debe = debe
-            .iter()
-            .map(|x| x.importe())
-            .reduce(|a, b| a + b)
-            .unwrap();
-    
-        let saldo_haber = haber
-            .iter()
-            .map(|x| x.importe())
-            .reduce(|a, b| a + b)
-            .unwrap();
-        
         Asiento {
             concepto: concepto.to_string(),
             fecha: match fecha {
@@ -63,14 +137,369 @@ impl Asiento {
             debe,
             haber,
             codigo: String::new(),
-            comprobacion: saldo_debe - saldo_haber,
+            tercero: None,
+            justificante: None,
+            tipo: TipoAsiento::Normal,
         }
     }
 
-    /// Valida el asiento: las anotaciones del debe han de sumar lo mismo que las del haber
+    /// Suma los importes de los movimientos del debe
+    pub fn total_debe(&self) -> Moneda {
+        suma(&self.debe)
+    }
+
+    /// Suma los importes de los movimientos del haber
+    pub fn total_haber(&self) -> Moneda {
+        suma(&self.haber)
+    }
+
+    /// Indica si otro asiento tiene el mismo concepto, la misma fecha y los mismos movimientos
+    /// (cuenta e importe, sin importar el orden) en el debe y en el haber. No compara el código.
+    pub fn es_duplicado_de(&self, otro: &Asiento) -> bool {
+        let claves = |movimientos: &[Movimiento]| {
+            let mut claves: Vec<(String, Moneda)> = movimientos
+                .iter()
+                .map(|m| (m.codigo_cuenta(), m.importe()))
+                .collect();
+            claves.sort();
+            claves
+        };
+
+        self.concepto == otro.concepto
+            && self.fecha == otro.fecha
+            && claves(&self.debe) == claves(&otro.debe)
+            && claves(&self.haber) == claves(&otro.haber)
+    }
+
+    /// Ancho de cada columna de movimientos (debe y haber) al imprimir el asiento
+    pub const ANCHO_COLUMNA: usize = 58;
+
+    /// Ancho total del asiento impreso: las dos columnas y sus tres bordes
+    pub const ANCHO: usize = Asiento::ANCHO_COLUMNA * 2 + 3;
+
+    /// Valida el asiento: las anotaciones del debe han de sumar lo mismo que las del haber.
+    /// Como los importes están en céntimos, la comparación es exacta.
+    /// Los importes negativos (abonos, rectificativas) restan de su lado, así que
+    /// -100 en el debe cuadra con -100 en el haber o con +100 en otra línea del debe.
     pub fn validar_saldos(&self) -> bool {
-        self.comprobacion == 0.00
+        self.comprobacion() == Moneda::CERO
+    }
+
+    /// Asigna al asiento su código, que lo identifica en el libro diario,
+    /// y enlaza con él todos sus movimientos
+    pub fn numerar(&mut self, codigo: &str) {
+        self.codigo = codigo.to_string();
+        for movimiento in self.debe.iter_mut().chain(self.haber.iter_mut()) {
+            movimiento.asignar_asiento(codigo);
+        }
+    }
+
+    /// Pone en lugar de los primeros movimientos de cada lado los indicados, que han de llevar los mismos
+    /// importes en euros, de modo que el asiento sigue cuadrado. Sirve para conservar la divisa original
+    /// de las líneas; los movimientos que sobran, como la línea de ajuste de redondeo, no cambian
+    pub(super) fn sustituir_movimientos(&mut self, debe: Vec<Movimiento>, haber: Vec<Movimiento>) {
+        for (actual, nuevo) in self.debe.iter_mut().zip(debe).chain(self.haber.iter_mut().zip(haber)) {
+            *actual = nuevo;
+        }
+    }
+
+    /// Asocia el asiento a un tercero (proveedor, cliente...)
+    pub fn fijar_tercero(&mut self, tercero: &str) {
+        self.tercero = Some(tercero.to_string());
+    }
+
+    /// Devuelve el tercero del asiento, si lo tiene
+    pub fn tercero(&self) -> Option<&str> {
+        self.tercero.as_deref()
+    }
+
+    /// Vincula el asiento con el justificante que lo respalda (nombre de archivo o URL)
+    pub fn fijar_justificante(&mut self, justificante: &str) {
+        self.justificante = Some(justificante.to_string());
+    }
+
+    /// Devuelve la referencia al justificante del asiento, si la hay
+    pub fn justificante(&self) -> Option<&str> {
+        self.justificante.as_deref()
+    }
+
+    /// Marca el asiento como de apertura, cierre, regularización o normal
+    pub fn fijar_tipo(&mut self, tipo: TipoAsiento) {
+        self.tipo = tipo;
+    }
+
+    /// Devuelve el tipo del asiento
+    pub fn tipo(&self) -> TipoAsiento {
+        self.tipo
+    }
+
+    /// Clave para ordenar el libro diario: por fecha y, dentro del mismo día, por tipo
+    /// (la apertura primero y el cierre al final)
+    pub fn clave_orden(&self) -> (NaiveDate, TipoAsiento) {
+        (self.fecha, self.tipo)
+    }
+
+    /// Devuelve el código del asiento
+    pub fn codigo(&self) -> String {
+        self.codigo.clone()
+    }
+
+    /// Devuelve el concepto del asiento
+    pub fn concepto(&self) -> String {
+        self.concepto.clone()
+    }
+
+    /// Devuelve los movimientos del debe
+    pub fn debe(&self) -> &[Movimiento] {
+        &self.debe
+    }
+
+    /// Devuelve los movimientos del haber
+    pub fn haber(&self) -> &[Movimiento] {
+        &self.haber
+    }
+
+    /// Devuelve la fecha del asiento
+    pub fn fecha(&self) -> NaiveDate {
+        self.fecha
+    }
+
+    /// Devuelve la diferencia entre el debe y el haber del asiento, calculada sobre sus movimientos actuales.
+    /// Se suma en céntimos para que el error de coma flotante no descuadre el asiento.
+    pub fn comprobacion(&self) -> Moneda {
+        suma(&self.debe) - suma(&self.haber)
+    }
+
+
+}
+
+/// Suma exacta, en céntimos, de los importes de unos movimientos
+fn suma(movimientos: &[Movimiento]) -> Moneda {
+    movimientos.iter().map(|m| m.importe()).sum()
+}
+
+#[cfg(test)]
+mod asiento_tests {
+
+    use super::*;
+    use crate::cuadro_contable::cuenta::Cuenta;
+    use crate::cuadro_contable::masa::Masa;
+
+    #[test]
+    fn total_debe_y_total_haber_suman_sus_movimientos() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let mut iva = Cuenta::new("IVA soportado", "472", Masa::ActivoCorriente);
+
+        let asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras), Movimiento::new(Moneda::from_euros(21.0), &mut iva)],
+            vec![Movimiento::new(Moneda::from_euros(120.0), &mut bancos)],
+        );
+
+        assert_eq!(asiento.total_debe(), Moneda::from_euros(121.0));
+        assert_eq!(asiento.total_haber(), Moneda::from_euros(120.0));
+        assert_eq!(asiento.comprobacion(), Moneda::from_euros(1.0));
+    }
+
+    #[test]
+    fn total_de_un_lado_vacio_es_cero() {
+        let asiento = Asiento::new("Vacío", None, vec![], vec![]);
+
+        assert_eq!(asiento.total_debe(), Moneda::from_euros(0.0));
+        assert_eq!(asiento.total_haber(), Moneda::from_euros(0.0));
     }
 
+    #[test]
+    fn comprobacion_no_descuadra_por_error_de_coma_flotante() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let mut iva = Cuenta::new("IVA soportado", "472", Masa::ActivoCorriente);
 
-}
\ No newline at end of file
+        // 0.1 + 0.2 != 0.3 en f64
+        let asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(0.1), &mut compras), Movimiento::new(Moneda::from_euros(0.2), &mut iva)],
+            vec![Movimiento::new(Moneda::from_euros(0.3), &mut bancos)],
+        );
+
+        assert_eq!(asiento.comprobacion(), Moneda::from_euros(0.0));
+        assert!(asiento.validar_saldos());
+    }
+
+    #[test]
+    fn validar_saldos_tolera_menos_de_medio_centimo() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+
+        // Los importes se guardan en céntimos: 100,004 € y 99,996 € son 100,00 €
+        for importe in [100.004, 99.996] {
+            let asiento = Asiento::new(
+                "Compra",
+                None,
+                vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras)],
+                vec![Movimiento::new(Moneda::from_euros(importe), &mut bancos)],
+            );
+
+            assert_eq!(asiento.comprobacion(), Moneda::from_euros(0.0));
+            assert!(asiento.validar_saldos());
+        }
+    }
+
+    #[test]
+    fn validar_saldos_admite_importes_negativos() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut clientes = Cuenta::new("Clientes", "430", Masa::ActivoCorriente);
+        let mut ventas = Cuenta::new("Ventas", "700", Masa::Ingreso);
+
+        // Rectificativa: la factura original con los importes en negativo
+        let rectificativa = Asiento::new(
+            "Abono",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(-121.0), &mut clientes)],
+            vec![Movimiento::new(Moneda::from_euros(-100.0), &mut ventas), Movimiento::new(Moneda::from_euros(-21.0), &mut bancos)],
+        );
+        assert!(rectificativa.validar_saldos());
+
+        // Un negativo en el debe equivale a un positivo en el haber
+        let compensado = Asiento::new(
+            "Cobro",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(50.0), &mut bancos), Movimiento::new(Moneda::from_euros(-50.0), &mut clientes)],
+            vec![],
+        );
+        assert!(compensado.validar_saldos());
+
+        let descuadrado = Asiento::new(
+            "Signos cruzados",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(-50.0), &mut bancos)],
+            vec![Movimiento::new(Moneda::from_euros(50.0), &mut clientes)],
+        );
+        assert!(!descuadrado.validar_saldos());
+        assert_eq!(descuadrado.comprobacion(), Moneda::from_euros(-100.0));
+    }
+
+    #[test]
+    fn validar_saldos_no_tolera_un_centimo_de_diferencia() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras)],
+            vec![Movimiento::new(Moneda::from_euros(100.01), &mut bancos)],
+        );
+
+        assert_eq!(asiento.comprobacion(), Moneda::from_euros(-0.01));
+        assert!(!asiento.validar_saldos());
+    }
+
+    #[test]
+    fn comprobacion_se_recalcula_si_cambian_los_movimientos() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let mut asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras)],
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut bancos)],
+        );
+        assert!(asiento.validar_saldos());
+
+        asiento.debe.push(Movimiento::new(Moneda::from_euros(5.0), &mut compras));
+
+        assert_eq!(asiento.comprobacion(), Moneda::from_euros(5.0));
+        assert!(!asiento.validar_saldos());
+    }
+
+    #[test]
+    fn display_muestra_el_tercero_en_la_cabecera_si_lo_hay() {
+        let mut asiento = Asiento::new("Compra", None, vec![], vec![]);
+        assert!(!asiento.to_string().contains("Tercero"));
+
+        asiento.fijar_tercero("Suministros Pérez");
+
+        assert_eq!(asiento.tercero(), Some("Suministros Pérez"));
+        assert!(asiento.to_string().lines().nth(2).unwrap().contains("Tercero: Suministros Pérez"));
+    }
+
+    #[test]
+    fn display_alinea_debe_y_haber_fila_a_fila() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let mut iva = Cuenta::new("H.P. IVA soportado", "472", Masa::ActivoCorriente);
+        let asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras), Movimiento::new(Moneda::from_euros(21.0), &mut iva)],
+            vec![Movimiento::new(Moneda::from_euros(121.0), &mut bancos)],
+        );
+
+        let texto = asiento.to_string();
+        let lineas: Vec<&str> = texto.lines().collect();
+        let fila = |inicio: &str| *lineas.iter().find(|l| l.starts_with(inicio)).unwrap();
+
+        assert!(lineas.iter().all(|l| l.chars().count() == lineas[0].chars().count()));
+        let primera = fila("| (600) Compras");
+        assert!(primera.contains("100,00 €") && primera.contains("(572) Bancos") && primera.contains("121,00 €"));
+        let segunda = fila("| (472) H.P. IVA soportado");
+        assert!(segunda.trim_end_matches('|').ends_with(&" ".repeat(58)));
+        let total = fila("| Total");
+        assert!(total.contains("121,00 €"));
+    }
+
+    #[test]
+    fn display_muestra_el_justificante_tras_la_fecha() {
+        let mut asiento = Asiento::new("Compra", NaiveDate::from_ymd_opt(2023, 8, 9), vec![], vec![]);
+        assert!(!asiento.to_string().contains("Justificante"));
+
+        asiento.fijar_justificante("facturas/2023/F-0042.pdf");
+
+        assert_eq!(asiento.justificante(), Some("facturas/2023/F-0042.pdf"));
+        let texto = asiento.to_string();
+        let lineas: Vec<&str> = texto.lines().collect();
+        let fecha = lineas.iter().position(|l| l.contains("2023-08-09")).unwrap();
+        assert!(lineas[fecha + 1].contains("Justificante: facturas/2023/F-0042.pdf"));
+    }
+
+    #[test]
+    fn display_indica_el_tipo_salvo_en_los_asientos_normales() {
+        let mut asiento = Asiento::new("Apertura", None, vec![], vec![]);
+        asiento.numerar("202301010");
+        assert!(asiento.to_string().lines().nth(1).unwrap().contains("N.º 202301010 "));
+
+        asiento.fijar_tipo(TipoAsiento::Apertura);
+
+        assert!(asiento.to_string().lines().nth(1).unwrap().contains("N.º 202301010 (Apertura)"));
+    }
+
+    #[test]
+    fn tipo_asiento_ordena_apertura_primero_y_cierre_al_final() {
+        let mut tipos = vec![TipoAsiento::Cierre, TipoAsiento::Normal, TipoAsiento::Regularizacion, TipoAsiento::Apertura];
+        tipos.sort();
+
+        assert_eq!(tipos, vec![TipoAsiento::Apertura, TipoAsiento::Normal, TipoAsiento::Regularizacion, TipoAsiento::Cierre]);
+        for tipo in tipos {
+            assert_eq!(tipo.to_string().parse::<TipoAsiento>(), Ok(tipo));
+        }
+    }
+
+    #[test]
+    fn numerar_enlaza_los_movimientos_con_el_asiento() {
+        let mut bancos = Cuenta::new("Bancos", "572", Masa::ActivoCorriente);
+        let mut compras = Cuenta::new("Compras", "600", Masa::Gasto);
+        let mut asiento = Asiento::new(
+            "Compra",
+            None,
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut compras)],
+            vec![Movimiento::new(Moneda::from_euros(100.0), &mut bancos)],
+        );
+
+        asiento.numerar("202308090");
+
+        assert!(asiento.debe().iter().chain(asiento.haber()).all(|m| m.codigo_asiento() == "202308090"));
+    }
+}
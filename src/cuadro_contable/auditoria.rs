@@ -0,0 +1,127 @@
+use std::fmt::Display;
+use std::fs;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use super::Cuadro;
+use super::masa::Masa;
+use super::moneda::Moneda;
+
+/// Una operación sobre el cuadro que queda registrada en su historial
+#[derive(Debug, PartialEq, Clone)]
+pub enum Operacion {
+    /// Se ha cargado el PGC, con el número de cuentas que se han creado
+    PgcCargado(usize),
+    CuentaCreada { codigo: String, nombre: String },
+    CuentaReclasificada { codigo: String, masa: Masa },
+    CuentaDesactivada(String),
+    CuentaActivada(String),
+    /// Se ha insertado un asiento, con su código
+    AsientoInsertado(String),
+    /// Se ha cuadrado un asiento llevando una diferencia de céntimos a la cuenta de redondeo
+    AjusteRedondeo { codigo: String, diferencia: Moneda },
+    /// Se han bloqueado los periodos hasta la fecha indicada
+    PeriodoBloqueado(NaiveDate),
+}
+
+impl Display for Operacion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operacion::PgcCargado(cuentas) => write!(f, "PGC cargado ({} cuentas)", cuentas),
+            Operacion::CuentaCreada { codigo, nombre } => write!(f, "Cuenta creada: ({}) {}", codigo, nombre),
+            Operacion::CuentaReclasificada { codigo, masa } => write!(f, "Cuenta reclasificada: {} pasa a {}", codigo, masa),
+            Operacion::CuentaDesactivada(codigo) => write!(f, "Cuenta desactivada: {}", codigo),
+            Operacion::CuentaActivada(codigo) => write!(f, "Cuenta activada: {}", codigo),
+            Operacion::AsientoInsertado(codigo) => write!(f, "Asiento insertado: {}", codigo),
+            Operacion::AjusteRedondeo { codigo, diferencia } => write!(f, "Ajuste de redondeo en el asiento {}: {}", codigo, diferencia),
+            Operacion::PeriodoBloqueado(fecha) => write!(f, "Periodo bloqueado hasta {}", fecha.format("%Y-%m-%d")),
+        }
+    }
+}
+
+/// Una entrada del historial: qué se hizo y cuándo
+#[derive(Debug, PartialEq, Clone)]
+pub struct EventoAuditoria {
+    pub momento: NaiveDateTime,
+    pub operacion: Operacion,
+}
+
+impl Display for EventoAuditoria {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.momento.format("%Y-%m-%d %H:%M:%S"), self.operacion)
+    }
+}
+
+impl Cuadro {
+
+    /// Anota una operación en el historial con la hora actual
+    pub(super) fn registrar(&mut self, operacion: Operacion) {
+        self.historial.push(EventoAuditoria {
+            momento: chrono::offset::Local::now().naive_local(),
+            operacion,
+        });
+    }
+
+    /// Devuelve las operaciones hechas sobre el cuadro (cuentas creadas, asientos insertados...)
+    /// en el orden en que se hicieron. A diferencia del libro diario, registra el uso del sistema,
+    /// no los hechos contables
+    pub fn historial(&self) -> &[EventoAuditoria] {
+        &self.historial
+    }
+
+    /// Exporta el historial a un archivo de log, con un evento por línea
+    pub fn exportar_historial(&self, path: &str) -> std::io::Result<()> {
+        let log: String = self.historial.iter().map(|e| format!("{}\n", e)).collect();
+        fs::write(path, log)
+    }
+}
+
+#[cfg(test)]
+mod auditoria_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+
+    #[test]
+    fn historial_registra_las_operaciones_en_orden() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Caja", "570", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Caja", "570", Masa::ActivoCorriente).unwrap_err();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha), vec![("572", Moneda::from_euros(10.0))], vec![("700", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        cuadro.desactivar_cuenta("570").unwrap();
+        cuadro.bloquear_hasta(fecha);
+
+        let operaciones: Vec<Operacion> = cuadro.historial().iter().map(|e| e.operacion.clone()).collect();
+
+        assert_eq!(operaciones, vec![
+            Operacion::CuentaCreada { codigo: "572".to_string(), nombre: "Bancos".to_string() },
+            Operacion::CuentaCreada { codigo: "700".to_string(), nombre: "Ventas".to_string() },
+            Operacion::CuentaCreada { codigo: "570".to_string(), nombre: "Caja".to_string() },
+            Operacion::AsientoInsertado("202308090".to_string()),
+            Operacion::CuentaDesactivada("570".to_string()),
+            Operacion::PeriodoBloqueado(fecha),
+        ]);
+        assert!(cuadro.historial().windows(2).all(|w| w[0].momento <= w[1].momento));
+    }
+
+    #[test]
+    fn exportar_historial_escribe_un_evento_por_linea() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc_filtrado(&[5]).unwrap();
+        cuadro.reclasificar_cuenta("572", Masa::ActivoNoCorriente).unwrap();
+        let path = std::env::temp_dir().join("presupuestos_historial.log");
+
+        cuadro.exportar_historial(path.to_str().unwrap()).unwrap();
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let lineas: Vec<&str> = log.lines().collect();
+        assert_eq!(lineas.len(), 2);
+        let cuentas_grupo_5 = cuadro.cuentas().count();
+        assert!(lineas[0].ends_with(&format!("PGC cargado ({} cuentas)", cuentas_grupo_5)));
+        assert!(lineas[1].ends_with("Cuenta reclasificada: 572 pasa a ActivoNoCorriente"));
+    }
+}
@@ -0,0 +1,316 @@
+use chrono::NaiveDate;
+
+use super::Cuadro;
+use super::formato::{formato_importe, LineaInforme};
+use super::masa::Masa;
+use super::moneda::Moneda;
+use super::pdf::{self, DocumentoPdf};
+
+impl Cuadro {
+
+    /// Genera las dos columnas del balance: el activo a la izquierda y el patrimonio neto y pasivo a la derecha.
+    /// Los importes del patrimonio neto y del pasivo se muestran en positivo cuando su saldo es acreedor.
+    /// Las cuentas correctoras (amortizaciones y deterioros) van al final de su masa de activo y restan de ella.
+    fn lineas_balance(&self) -> (Vec<LineaInforme>, Vec<LineaInforme>) {
+
+        let totales = self.totales_por_masa();
+        let total = |masa: Masa| totales.get(&masa).copied().unwrap_or(Moneda::CERO);
+
+        // El resultado aún no cerrado forma parte del patrimonio neto
+        let resultado = self.resultado_provisional();
+
+        let seccion = |lineas: &mut Vec<LineaInforme>, titulo: &str, masa: Masa| {
+            let signo = masa.naturaleza().signo();
+            lineas.push(LineaInforme::epigrafe(titulo, signo * total(masa)));
+            let mut cuentas: Vec<_> = self.cuentas.iter().filter(|c| c.masa() == masa && c.saldo() != Moneda::CERO).collect();
+            cuentas.sort_by_key(|c| c.es_correctora());
+            for cuenta in cuentas {
+                lineas.push(LineaInforme::cuenta(format!("({}) {}", cuenta.codigo(), cuenta.nombre()), signo * cuenta.saldo()));
+            }
+        };
+
+        let mut activo = vec![];
+        seccion(&mut activo, "ACTIVO NO CORRIENTE", Masa::ActivoNoCorriente);
+        seccion(&mut activo, "ACTIVO CORRIENTE", Masa::ActivoCorriente);
+        activo.push(LineaInforme::epigrafe(
+            "TOTAL ACTIVO",
+            total(Masa::ActivoNoCorriente) + total(Masa::ActivoCorriente),
+        ));
+
+        let mut pasivo = vec![];
+        seccion(&mut pasivo, "PATRIMONIO NETO", Masa::Patrimonio);
+        if let Some(linea) = pasivo.first_mut() {
+            linea.importe += resultado;
+        }
+        if resultado != Moneda::CERO {
+            pasivo.push(LineaInforme::cuenta("Resultado del ejercicio".to_string(), resultado));
+        }
+        seccion(&mut pasivo, "PASIVO NO CORRIENTE", Masa::PasivoNoCorriente);
+        seccion(&mut pasivo, "PASIVO CORRIENTE", Masa::PasivoCorriente);
+        pasivo.push(LineaInforme::epigrafe(
+            "TOTAL PATRIMONIO NETO Y PASIVO",
+            -(total(Masa::Patrimonio) + total(Masa::PasivoNoCorriente) + total(Masa::PasivoCorriente)) + resultado,
+        ));
+
+        (activo, pasivo)
+    }
+
+    /// Devuelve el balance de situación a fecha de hoy (o de cierre, si el ejercicio ya ha terminado) en dos columnas: activo y patrimonio neto y pasivo
+    pub fn balance_situacion(&self) -> String {
+
+        let fecha = self.fecha_informe();
+        let (activo, pasivo) = self.lineas_balance();
+        let w = 120;
+        let columna = (w - 3) / 2;
+
+        let mut salida = String::new();
+        salida.push_str(&format!("{:^w$}\n", "BALANCE DE SITUACIÓN"));
+        salida.push_str(&format!("{:^w$}\n", fecha.format("%Y-%m-%d").to_string()));
+        salida.push_str(&format!("{:-<w$}\n", ""));
+        salida.push_str(&format!("{:<columna$} | {:<columna$}\n", "ACTIVO", "PATRIMONIO NETO Y PASIVO"));
+        salida.push_str(&format!("{:-<w$}\n", ""));
+
+        for i in 0..activo.len().max(pasivo.len()) {
+            let izquierda = activo.get(i).map(|l| l.formatear(columna)).unwrap_or(" ".repeat(columna));
+            let derecha = pasivo.get(i).map(|l| l.formatear(columna)).unwrap_or_default();
+            salida.push_str(format!("{} | {}", izquierda, derecha).trim_end());
+            salida.push('\n');
+        }
+
+        salida
+    }
+
+    /// Compara el balance de situación en dos fechas: por cada masa de balance y cada una de sus cuentas,
+    /// ordenadas por código, el saldo en `fecha_a`, el saldo en `fecha_b` y la variación de la primera
+    /// a la segunda, en euros y en porcentaje (n/d si el saldo de partida es cero). Como en el balance,
+    /// el patrimonio neto y el pasivo se muestran en positivo y el resultado no cerrado va al patrimonio neto.
+    pub fn balance_comparativo(&self, fecha_a: NaiveDate, fecha_b: NaiveDate) -> String {
+
+        let w = 112;
+        let fila = |texto: &str, a: Moneda, b: Moneda| {
+            let variacion = b - a;
+            let porcentaje = if a == Moneda::CERO { "n/d".to_string() } else { format!("{:.2} %", variacion.as_euros() / a.abs().as_euros() * 100.0).replace('.', ",") };
+            let texto: String = texto.chars().take(42).collect();
+            format!("{:<42} {:>16} {:>16} {:>16} {:>16}\n", texto, formato_importe(a), formato_importe(b), formato_importe(variacion), porcentaje)
+        };
+
+        // Saldo de una masa a una fecha, con el signo de su naturaleza
+        let total_masa = |masa: Masa, fecha: NaiveDate| -> Moneda {
+            masa.naturaleza().signo() * self.cuentas.iter()
+                .filter(|c| c.masa() == masa)
+                .map(|c| c.saldo_a_fecha(fecha))
+                .sum::<Moneda>()
+        };
+        // Ingresos menos gastos a una fecha
+        let resultado = |fecha: NaiveDate| total_masa(Masa::Ingreso, fecha) - total_masa(Masa::Gasto, fecha);
+
+        let mut salida = String::new();
+        salida.push_str(&format!("{:^w$}\n", "BALANCE COMPARATIVO"));
+        salida.push_str(&format!("{:^w$}\n", format!("{} frente a {}", fecha_a.format("%Y-%m-%d"), fecha_b.format("%Y-%m-%d"))));
+        salida.push_str(&format!("{:-<w$}\n", ""));
+        salida.push_str(&format!(
+            "{:<42} {:>16} {:>16} {:>16} {:>16}\n",
+            "Cuenta", fecha_a.format("%Y-%m-%d").to_string(), fecha_b.format("%Y-%m-%d").to_string(), "Variación", "%"
+        ));
+        salida.push_str(&format!("{:-<w$}\n", ""));
+
+        let secciones = [
+            ("ACTIVO NO CORRIENTE", Masa::ActivoNoCorriente),
+            ("ACTIVO CORRIENTE", Masa::ActivoCorriente),
+            ("PATRIMONIO NETO", Masa::Patrimonio),
+            ("PASIVO NO CORRIENTE", Masa::PasivoNoCorriente),
+            ("PASIVO CORRIENTE", Masa::PasivoCorriente),
+        ];
+
+        for (titulo, masa) in secciones {
+            let mut total = (total_masa(masa, fecha_a), total_masa(masa, fecha_b));
+            if masa == Masa::Patrimonio {
+                total.0 += resultado(fecha_a);
+                total.1 += resultado(fecha_b);
+            }
+            salida.push_str(&fila(titulo, total.0, total.1));
+
+            let mut cuentas: Vec<_> = self.cuentas.iter().filter(|c| c.masa() == masa).collect();
+            cuentas.sort_by_key(|c| (c.codigo_numerico().map_or((1, 0), |n| (0, n)), c.codigo()));
+            let signo = masa.naturaleza().signo();
+            for cuenta in cuentas {
+                let (a, b) = (signo * cuenta.saldo_a_fecha(fecha_a), signo * cuenta.saldo_a_fecha(fecha_b));
+                if a != Moneda::CERO || b != Moneda::CERO {
+                    salida.push_str(&fila(&format!("  ({}) {}", cuenta.codigo(), cuenta.nombre()), a, b));
+                }
+            }
+            if masa == Masa::Patrimonio && (resultado(fecha_a) != Moneda::CERO || resultado(fecha_b) != Moneda::CERO) {
+                salida.push_str(&fila("  Resultado del ejercicio", resultado(fecha_a), resultado(fecha_b)));
+            }
+        }
+
+        salida.push_str(&format!("{:-<w$}\n", ""));
+        let activo = |fecha| total_masa(Masa::ActivoNoCorriente, fecha) + total_masa(Masa::ActivoCorriente, fecha);
+        salida.push_str(&fila("TOTAL ACTIVO", activo(fecha_a), activo(fecha_b)));
+        let pasivo = |fecha| total_masa(Masa::Patrimonio, fecha) + resultado(fecha)
+            + total_masa(Masa::PasivoNoCorriente, fecha) + total_masa(Masa::PasivoCorriente, fecha);
+        salida.push_str(&fila("TOTAL PATRIMONIO NETO Y PASIVO", pasivo(fecha_a), pasivo(fecha_b)));
+
+        salida
+    }
+
+    /// Genera el balance de situación en un PDF de dos columnas, con cabecera, fecha y totales
+    pub fn balance_situacion_pdf(&self, path: &str) -> std::io::Result<()> {
+        self.documento_balance(self.fecha_informe()).guardar(path)
+    }
+
+    /// Compone el documento PDF del balance a la fecha indicada
+    fn documento_balance(&self, fecha: NaiveDate) -> DocumentoPdf {
+
+        let (activo, pasivo) = self.lineas_balance();
+
+        // Courier a 7 puntos: cada carácter mide 4,2 puntos
+        let margen = 40.0;
+        let tamano = 7.0;
+        let interlinea = 10.0;
+        let ancho_columna = (pdf::ANCHO_PAGINA - margen * 2.0 - 20.0) / 2.0;
+        let caracteres = (ancho_columna / (tamano * 0.6)) as usize;
+        let x_derecha = margen + ancho_columna + 20.0;
+
+        let mut documento = DocumentoPdf::new("Balance de situación");
+
+        let cabecera = |documento: &mut DocumentoPdf| -> f64 {
+            let mut y = pdf::ALTO_PAGINA - margen;
+            documento.escribir(margen, y, 14.0, "BALANCE DE SITUACIÓN");
+            y -= 18.0;
+            documento.escribir(margen, y, 10.0, &format!("Fecha: {}", fecha.format("%d/%m/%Y")));
+            y -= 24.0;
+            documento.escribir(margen, y, 9.0, "ACTIVO");
+            documento.escribir(x_derecha, y, 9.0, "PATRIMONIO NETO Y PASIVO");
+            y - interlinea * 1.5
+        };
+
+        let mut y = cabecera(&mut documento);
+
+        for i in 0..activo.len().max(pasivo.len()) {
+            if y < margen {
+                documento.nueva_pagina();
+                y = cabecera(&mut documento);
+            }
+            if let Some(linea) = activo.get(i) {
+                documento.escribir(margen, y, tamano, &linea.formatear(caracteres));
+            }
+            if let Some(linea) = pasivo.get(i) {
+                documento.escribir(x_derecha, y, tamano, &linea.formatear(caracteres));
+            }
+            y -= interlinea;
+        }
+
+        documento
+    }
+}
+
+#[cfg(test)]
+mod balance_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(250.0))], vec![("700", Moneda::from_euros(250.0))], &mut cuadro).unwrap();
+
+        cuadro
+    }
+
+    #[test]
+    fn balance_situacion_incluye_cuentas_y_totales_cuadrados() {
+        let balance = setup_cuadro().balance_situacion();
+
+        assert!(balance.contains("BALANCE DE SITUACIÓN"));
+        assert!(balance.contains("(572) Bancos"));
+        assert!(balance.contains("Resultado del ejercicio"));
+        assert!(!balance.contains("(700) Ventas"));
+
+        let total_activo = balance.lines().find(|l| l.starts_with("TOTAL ACTIVO")).unwrap();
+        assert!(total_activo.contains("1.250,00 €"));
+        let total_pasivo = balance.lines().find(|l| l.contains("TOTAL PATRIMONIO NETO Y PASIVO")).unwrap();
+        assert!(total_pasivo.trim_end().ends_with("1.250,00 €"));
+    }
+
+    #[test]
+    fn balance_situacion_excluye_las_cuentas_de_orden() {
+        let mut cuadro = setup_cuadro();
+        cuadro.crear_cuenta("Avales concedidos", "010", Masa::Orden).unwrap();
+        cuadro.crear_cuenta("Avales, contrapartida", "011", Masa::Orden).unwrap();
+        LibroDiario::new().insertar_asiento("Aval", None, vec![("010", Moneda::from_euros(500.0))], vec![("011", Moneda::from_euros(500.0))], &mut cuadro).unwrap();
+
+        let balance = cuadro.balance_situacion();
+
+        assert!(!balance.contains("Avales"));
+        assert_eq!(cuadro.cuentas_de_orden().count(), 2);
+    }
+
+    #[test]
+    fn balance_comparativo_muestra_saldos_y_variaciones_por_masa() {
+        let fecha = |a, m, d| NaiveDate::from_ymd_opt(a, m, d).unwrap();
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Caja", "570", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", Some(fecha(2023, 1, 1)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(2024, 3, 1)), vec![("572", Moneda::from_euros(250.0))], vec![("700", Moneda::from_euros(250.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(2024, 3, 2)), vec![("570", Moneda::from_euros(50.0))], vec![("700", Moneda::from_euros(50.0))], &mut cuadro).unwrap();
+
+        let informe = cuadro.balance_comparativo(fecha(2023, 12, 31), fecha(2024, 12, 31));
+        let linea = |inicio: &str| informe.lines().find(|l| l.trim_start().starts_with(inicio)).unwrap().to_string();
+
+        assert!(linea("(572) Bancos").contains("1.000,00 €") && linea("(572) Bancos").contains("1.250,00 €"));
+        assert!(linea("(572) Bancos").trim_end().ends_with("25,00 %"));
+        assert!(linea("(570) Caja").trim_end().ends_with("n/d"));
+        assert!(informe.find("(570) Caja").unwrap() < informe.find("(572) Bancos").unwrap());
+        assert!(linea("Resultado del ejercicio").contains("300,00 €"));
+        assert!(linea("TOTAL ACTIVO").contains("1.300,00 €"));
+        assert!(linea("TOTAL PATRIMONIO NETO Y PASIVO").contains("1.300,00 €"));
+        assert!(!informe.contains("(700) Ventas"));
+    }
+
+    #[test]
+    fn balance_situacion_resta_las_correctoras_del_activo_que_corrigen() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Amortización acumulada de maquinaria", "2813", Masa::ActivoNoCorriente).unwrap();
+        cuadro.crear_cuenta("Maquinaria", "213", Masa::ActivoNoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Amortización del inmovilizado material", "681", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("213", Moneda::from_euros(10000.0))], vec![("100", Moneda::from_euros(10000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Amortización", None, vec![("681", Moneda::from_euros(2000.0))], vec![("2813", Moneda::from_euros(2000.0))], &mut cuadro).unwrap();
+
+        let balance = cuadro.balance_situacion();
+        let linea = |inicio: &str| balance.lines().find(|l| l.trim_start().starts_with(inicio)).unwrap().to_string();
+
+        assert!(linea("ACTIVO NO CORRIENTE").contains("8.000,00 €"));
+        assert!(linea("(2813) Amortización").contains("-2.000,00 €"));
+        assert!(balance.find("(213) Maquinaria").unwrap() < balance.find("(2813) Amortización").unwrap());
+        assert!(linea("TOTAL ACTIVO").contains("8.000,00 €"));
+        let total_pasivo = balance.lines().find(|l| l.contains("TOTAL PATRIMONIO NETO Y PASIVO")).unwrap();
+        assert!(total_pasivo.ends_with("8.000,00 €"));
+        assert_eq!(cuadro.verificar_ecuacion(), Ok(()));
+    }
+
+    #[test]
+    fn documento_balance_contiene_cabecera_fecha_y_totales() {
+        let fecha = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let documento = setup_cuadro().documento_balance(fecha);
+        let textos: Vec<&str> = documento.textos().collect();
+
+        assert!(textos.contains(&"Fecha: 31/12/2023"));
+        assert!(textos.iter().any(|t| t.starts_with("TOTAL ACTIVO") && t.ends_with("1.250,00 €")));
+        assert!(textos.iter().any(|t| t.starts_with("TOTAL PATRIMONIO NETO Y PASIVO")));
+        assert!(documento.bytes().unwrap().starts_with(b"%PDF"));
+    }
+}
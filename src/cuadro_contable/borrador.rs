@@ -0,0 +1,120 @@
+use chrono::NaiveDate;
+
+use super::{prestar_anotaciones, Cuadro, LibroDiario, LibroDiarioError};
+use super::moneda::Moneda;
+
+/// Un asiento en construcción, al que se le van añadiendo movimientos línea a línea
+/// (por ejemplo, según los teclea el usuario) hasta confirmarlo con `finalizar`
+#[derive(Debug, PartialEq, Clone)]
+pub struct BorradorAsiento {
+    concepto: String,
+    fecha: Option<NaiveDate>,
+    debe: Vec<(String, Moneda)>,
+    haber: Vec<(String, Moneda)>,
+}
+
+impl BorradorAsiento {
+
+    /// Crea un borrador sin movimientos. Sin fecha, el asiento se anotará con la de hoy
+    pub fn new(concepto: &str, fecha: Option<NaiveDate>) -> BorradorAsiento {
+        BorradorAsiento {
+            concepto: concepto.to_string(),
+            fecha,
+            debe: vec![],
+            haber: vec![],
+        }
+    }
+
+    /// Añade un movimiento al debe
+    pub fn anadir_debe(&mut self, codigo_cuenta: &str, importe: Moneda) -> &mut BorradorAsiento {
+        self.debe.push((codigo_cuenta.to_string(), importe));
+        self
+    }
+
+    /// Añade un movimiento al haber
+    pub fn anadir_haber(&mut self, codigo_cuenta: &str, importe: Moneda) -> &mut BorradorAsiento {
+        self.haber.push((codigo_cuenta.to_string(), importe));
+        self
+    }
+
+    /// Devuelve lo que falta para cuadrar el borrador: el debe menos el haber
+    pub fn diferencia(&self) -> Moneda {
+        let total = |lado: &[(String, Moneda)]| lado.iter().map(|(_, i)| *i).sum::<Moneda>();
+        total(&self.debe) - total(&self.haber)
+    }
+
+    /// Anota el asiento en el libro diario con las mismas comprobaciones que `insertar_asiento`, incluido
+    /// el ajuste de redondeo si el libro lo tiene activado. Si el borrador no cuadra o el asiento
+    /// no se puede insertar, no se toca nada y el borrador sigue disponible para corregirlo
+    pub fn finalizar(&self, libro_diario: &mut LibroDiario, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        libro_diario.insertar_asiento(
+            &self.concepto,
+            self.fecha,
+            prestar_anotaciones(&self.debe),
+            prestar_anotaciones(&self.haber),
+            cuadro,
+        )
+    }
+}
+
+#[cfg(test)]
+mod borrador_tests {
+
+    use super::*;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("IVA soportado", "472", Masa::ActivoCorriente).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn finalizar_inserta_el_asiento_construido_linea_a_linea() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let mut borrador = BorradorAsiento::new("Compra", NaiveDate::from_ymd_opt(2023, 8, 9));
+
+        borrador.anadir_debe("600", Moneda::from_euros(100.0)).anadir_debe("472", Moneda::from_euros(21.0));
+        assert_eq!(borrador.diferencia(), Moneda::from_euros(121.0));
+        borrador.anadir_haber("572", Moneda::from_euros(121.0));
+        assert_eq!(borrador.diferencia(), Moneda::from_euros(0.0));
+
+        borrador.finalizar(&mut libro_diario, &mut cuadro).unwrap();
+
+        let asiento = libro_diario.asientos().next().unwrap();
+        assert_eq!(asiento.debe().len(), 2);
+        assert_eq!(asiento.codigo(), "202308090");
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(-121.0));
+    }
+
+    #[test]
+    fn finalizar_rechaza_un_borrador_descuadrado_sin_tocar_nada() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let mut borrador = BorradorAsiento::new("Compra", None);
+        borrador.anadir_debe("600", Moneda::from_euros(100.0)).anadir_haber("572", Moneda::from_euros(90.0));
+
+        assert_eq!(borrador.finalizar(&mut libro_diario, &mut cuadro), Err(LibroDiarioError::AsientoDesequilibrado(Moneda::from_euros(10.0))));
+        assert_eq!(libro_diario.asientos().count(), 0);
+
+        borrador.anadir_haber("572", Moneda::from_euros(10.0));
+        assert_eq!(borrador.finalizar(&mut libro_diario, &mut cuadro), Ok(()));
+    }
+
+    #[test]
+    fn finalizar_aplica_el_ajuste_de_redondeo_del_libro_diario() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.activar_ajuste_redondeo(Moneda::from_euros(0.01), &mut cuadro).unwrap();
+        let mut borrador = BorradorAsiento::new("Compra", None);
+        borrador.anadir_debe("600", Moneda::from_euros(82.64)).anadir_debe("472", Moneda::from_euros(17.35)).anadir_haber("572", Moneda::from_euros(100.0));
+
+        assert_eq!(borrador.diferencia(), Moneda::from_euros(-0.01));
+        borrador.finalizar(&mut libro_diario, &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("669").unwrap().saldo(), Moneda::from_euros(0.01));
+    }
+}
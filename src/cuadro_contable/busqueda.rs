@@ -0,0 +1,159 @@
+use super::Cuadro;
+use super::cuenta::Cuenta;
+use super::formato::normalizar;
+
+/// Número mínimo de ediciones (inserciones, borrados o sustituciones de un carácter) para pasar de un texto a otro
+fn distancia_levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut anterior: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut actual = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let sustitucion = anterior[j] + usize::from(ca != *cb);
+            actual.push(sustitucion.min(anterior[j + 1] + 1).min(actual[j] + 1));
+        }
+        anterior = actual;
+    }
+
+    anterior[b.len()]
+}
+
+/// Indica si los caracteres de `consulta` aparecen en `texto` en el mismo orden, aunque no sean contiguos
+fn es_subsecuencia(consulta: &str, texto: &str) -> bool {
+    let mut caracteres = texto.chars();
+    consulta.chars().all(|c| caracteres.any(|t| t == c))
+}
+
+/// Puntúa lo bien que una cuenta responde a la consulta (ya normalizada): cuanto menor, mejor.
+/// Devuelve `None` si no se parece lo suficiente
+fn puntuar(cuenta: &Cuenta, consulta: &str) -> Option<(u8, usize)> {
+
+    let nombre = normalizar(&cuenta.nombre());
+    let palabras: Vec<&str> = nombre.split(|c: char| !c.is_alphanumeric()).filter(|p| !p.is_empty()).collect();
+
+    if cuenta.codigo().starts_with(consulta) || palabras.iter().any(|p| p.starts_with(consulta)) {
+        return Some((0, 0));
+    }
+    if nombre.contains(consulta) {
+        return Some((1, 0));
+    }
+
+    // Errores de tecleo: compara la consulta con el principio de cada palabra
+    let longitud = consulta.chars().count();
+    let tolerancia = (longitud / 3).max(1);
+    let distancia = palabras.iter()
+        .map(|p| distancia_levenshtein(consulta, &p.chars().take(longitud).collect::<String>()))
+        .min()
+        .filter(|d| *d <= tolerancia);
+    if let Some(distancia) = distancia {
+        return Some((2, distancia));
+    }
+
+    if es_subsecuencia(consulta, &nombre) {
+        return Some((3, 0));
+    }
+
+    None
+}
+
+impl Cuadro {
+
+    /// Busca una cuenta activa por su nombre completo, sin distinguir mayúsculas ni tildes
+    /// ("energia" encuentra "Energía")
+    pub fn buscar_cuenta_por_nombre(&self, nombre: &str) -> Option<&Cuenta> {
+        let nombre = normalizar(nombre.trim());
+        self.cuentas.iter().find(|c| c.activa() && normalizar(&c.nombre()) == nombre)
+    }
+
+    /// Sugiere hasta `limite` cuentas activas cuyo código o nombre se parezca a la consulta, de la más
+    /// a la menos parecida: primero las que tienen una palabra que empieza por la consulta ("banc"),
+    /// luego las que la contienen, las que se parecen salvo errores de tecleo y, por último, las que
+    /// contienen sus letras en orden. No distingue mayúsculas ni tildes.
+    pub fn sugerir_cuentas(&self, consulta: &str, limite: usize) -> Vec<&Cuenta> {
+
+        let consulta = normalizar(consulta.trim());
+        if consulta.is_empty() {
+            return vec![];
+        }
+
+        let mut candidatas: Vec<((u8, usize), &Cuenta)> = self.cuentas.iter()
+            .filter(|c| c.activa())
+            .filter_map(|c| puntuar(c, &consulta).map(|p| (p, c)))
+            .collect();
+        candidatas.sort_by_key(|(puntuacion, c)| (*puntuacion, c.codigo_numerico().unwrap_or(u64::MAX), c.codigo()));
+
+        candidatas.into_iter().take(limite).map(|(_, c)| c).collect()
+    }
+}
+
+#[cfg(test)]
+mod busqueda_tests {
+
+    use super::*;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Proveedores", "400", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("Proveedores, efectos comerciales a pagar", "401", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("Bancos e instituciones de crédito c/c vista, euros", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Suministros", "628", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Servicios bancarios y similares", "626", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Energía", "6280", Masa::Gasto).unwrap();
+        cuadro
+    }
+
+    fn codigos(cuentas: Vec<&Cuenta>) -> Vec<String> {
+        cuentas.iter().map(|c| c.codigo()).collect()
+    }
+
+    #[test]
+    fn sugerir_cuentas_prioriza_las_palabras_que_empiezan_por_la_consulta() {
+        let cuadro = setup_cuadro();
+
+        assert_eq!(codigos(cuadro.sugerir_cuentas("prov", 5)), vec!["400", "401"]);
+        assert_eq!(codigos(cuadro.sugerir_cuentas("banc", 5)), vec!["572", "626"]);
+        assert_eq!(codigos(cuadro.sugerir_cuentas("prov", 1)), vec!["400"]);
+        assert_eq!(codigos(cuadro.sugerir_cuentas("57", 5)), vec!["572"]);
+    }
+
+    #[test]
+    fn sugerir_cuentas_tolera_tildes_mayusculas_y_erratas() {
+        let cuadro = setup_cuadro();
+
+        assert_eq!(codigos(cuadro.sugerir_cuentas("ENERGIA", 5)), vec!["6280"]);
+        assert_eq!(codigos(cuadro.sugerir_cuentas("credito", 5)), vec!["572"]);
+        assert_eq!(codigos(cuadro.sugerir_cuentas("sumnistros", 5)), vec!["628"]);
+        assert!(cuadro.sugerir_cuentas("xyz", 5).is_empty());
+        assert!(cuadro.sugerir_cuentas("  ", 5).is_empty());
+    }
+
+    #[test]
+    fn sugerir_cuentas_omite_las_inactivas() {
+        let mut cuadro = setup_cuadro();
+        cuadro.desactivar_cuenta("401").unwrap();
+
+        assert_eq!(codigos(cuadro.sugerir_cuentas("prov", 5)), vec!["400"]);
+    }
+
+    #[test]
+    fn buscar_cuenta_por_nombre_ignora_tildes_y_mayusculas() {
+        let mut cuadro = setup_cuadro();
+        cuadro.crear_cuenta("Teléfono", "6290", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Préstamo de José", "5510", Masa::PasivoCorriente).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta_por_nombre("energia").unwrap().codigo(), "6280");
+        assert_eq!(cuadro.buscar_cuenta_por_nombre("TELEFONO").unwrap().codigo(), "6290");
+        assert_eq!(cuadro.buscar_cuenta_por_nombre(" préstamo de jose ").unwrap().codigo(), "5510");
+        assert!(cuadro.buscar_cuenta_por_nombre("prov").is_none());
+    }
+
+    #[test]
+    fn distancia_levenshtein_cuenta_las_ediciones() {
+        assert_eq!(distancia_levenshtein("banco", "banco"), 0);
+        assert_eq!(distancia_levenshtein("bnaco", "banco"), 2);
+        assert_eq!(distancia_levenshtein("suministro", "sumnistro"), 1);
+        assert_eq!(distancia_levenshtein("", "abc"), 3);
+    }
+}
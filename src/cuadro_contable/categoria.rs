@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use super::{Cuadro, CuadroError};
+use super::apunte::Lado;
+use super::masa::Masa;
+use super::moneda::Moneda;
+
+/// Categoría en la que se agrupan las cuentas a las que no se ha dado ninguna
+pub const SIN_CLASIFICAR: &str = "Sin clasificar";
+
+impl Cuadro {
+
+    /// Etiqueta una cuenta con una categoría propia (alimentación, ocio, vivienda...), o se la quita con `None`
+    pub fn categorizar_cuenta(&mut self, codigo: &str, categoria: Option<&str>) -> Result<(), CuadroError> {
+        match self.buscar_cuenta(codigo) {
+            Some(cuenta) => {
+                cuenta.fijar_categoria(categoria);
+                Ok(())
+            },
+            None => Err(CuadroError::CuentaInexistente(codigo.to_string())),
+        }
+    }
+
+    /// Suma los gastos entre `inicio` y `fin`, ambas incluidas, agrupados por la categoría de su cuenta.
+    /// Las cuentas sin categoría van a [`SIN_CLASIFICAR`] y las categorías sin movimientos no aparecen
+    pub fn gasto_por_categoria(&self, inicio: NaiveDate, fin: NaiveDate) -> HashMap<String, Moneda> {
+        self.por_categoria(Masa::Gasto, inicio, fin)
+    }
+
+    /// Suma los ingresos entre `inicio` y `fin`, ambas incluidas, agrupados por la categoría de su cuenta,
+    /// igual que [`Cuadro::gasto_por_categoria`]
+    pub fn ingreso_por_categoria(&self, inicio: NaiveDate, fin: NaiveDate) -> HashMap<String, Moneda> {
+        self.por_categoria(Masa::Ingreso, inicio, fin)
+    }
+
+    /// Neto de los apuntes del periodo en las cuentas de la masa, en positivo para su lado natural
+    fn por_categoria(&self, masa: Masa, inicio: NaiveDate, fin: NaiveDate) -> HashMap<String, Moneda> {
+
+        let mut categorias: HashMap<String, Moneda> = HashMap::new();
+
+        for cuenta in self.cuentas().filter(|c| c.masa() == masa) {
+            let apuntes: Vec<Moneda> = cuenta.apuntes().iter()
+                .filter(|a| a.fecha() >= inicio && a.fecha() <= fin)
+                .map(|a| match (a.lado(), masa) {
+                    (Lado::Debe, Masa::Gasto) | (Lado::Haber, Masa::Ingreso) => a.importe(),
+                    _ => -a.importe(),
+                })
+                .collect();
+            if apuntes.is_empty() {
+                continue;
+            }
+
+            let categoria = cuenta.categoria().unwrap_or(SIN_CLASIFICAR).to_string();
+            *categorias.entry(categoria).or_insert(Moneda::CERO) += apuntes.into_iter().sum();
+        }
+
+        categorias
+    }
+}
+
+#[cfg(test)]
+mod categoria_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+
+    fn fecha(dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, dia).unwrap()
+    }
+
+    fn setup() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Cuenta corriente", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Supermercado", "6001", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Mercado", "6002", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Alquiler", "621", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Cine", "629", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Nómina", "705", Masa::Ingreso).unwrap();
+        cuadro.categorizar_cuenta("6001", Some("Alimentación")).unwrap();
+        cuadro.categorizar_cuenta("6002", Some("Alimentación")).unwrap();
+        cuadro.categorizar_cuenta("621", Some("Vivienda")).unwrap();
+        cuadro.categorizar_cuenta("705", Some("Trabajo")).unwrap();
+
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Nómina", Some(fecha(1)), vec![("572", Moneda::from_euros(2000.0))], vec![("705", Moneda::from_euros(2000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Alquiler", Some(fecha(2)), vec![("621", Moneda::from_euros(800.0))], vec![("572", Moneda::from_euros(800.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", Some(fecha(3)), vec![("6001", Moneda::from_euros(120.50))], vec![("572", Moneda::from_euros(120.50))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Fruta", Some(fecha(10)), vec![("6002", Moneda::from_euros(30.25))], vec![("572", Moneda::from_euros(30.25))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Devolución", Some(fecha(11)), vec![("572", Moneda::from_euros(10.0))], vec![("6001", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Cine", Some(fecha(20)), vec![("629", Moneda::from_euros(15.0))], vec![("572", Moneda::from_euros(15.0))], &mut cuadro).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn gasto_por_categoria_agrupa_por_etiqueta_y_no_por_codigo() {
+        let cuadro = setup();
+
+        let gastos = cuadro.gasto_por_categoria(fecha(1), fecha(31));
+
+        assert_eq!(gastos.len(), 3);
+        assert_eq!(gastos["Alimentación"], Moneda::from_euros(140.75));
+        assert_eq!(gastos["Vivienda"], Moneda::from_euros(800.0));
+        assert_eq!(gastos[SIN_CLASIFICAR], Moneda::from_euros(15.0));
+        assert_eq!(cuadro.ingreso_por_categoria(fecha(1), fecha(31)), HashMap::from([("Trabajo".to_string(), Moneda::from_euros(2000.0))]));
+    }
+
+    #[test]
+    fn gasto_por_categoria_solo_cuenta_los_apuntes_del_periodo() {
+        let mut cuadro = setup();
+
+        let gastos = cuadro.gasto_por_categoria(fecha(3), fecha(10));
+
+        assert_eq!(gastos, HashMap::from([("Alimentación".to_string(), Moneda::from_euros(150.75))]));
+        assert_eq!(
+            cuadro.categorizar_cuenta("999", Some("Ocio")),
+            Err(CuadroError::CuentaInexistente("999".to_string()))
+        );
+    }
+}
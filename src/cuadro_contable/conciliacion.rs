@@ -0,0 +1,233 @@
+use chrono::NaiveDate;
+
+use super::{Cuadro, CuadroError};
+use super::apunte::{Apunte, Lado};
+use super::formato::leer_importe;
+use super::moneda::Moneda;
+
+/// Días de diferencia admitidos entre la fecha contable y la fecha valor del banco
+pub const DIAS_TOLERANCIA: i64 = 5;
+
+/// Un movimiento del extracto bancario. Los importes positivos son ingresos
+/// (debe de la cuenta de tesorería) y los negativos, pagos (haber).
+#[derive(Debug, PartialEq, Clone)]
+pub struct MovimientoBanco {
+    pub fecha: NaiveDate,
+    pub importe: Moneda,
+    pub concepto: String,
+}
+
+/// Resultado de conciliar una cuenta contra el extracto del banco
+#[derive(Debug, PartialEq)]
+pub struct Conciliacion {
+    /// Parejas de apunte contable y movimiento bancario que se corresponden
+    pub conciliados: Vec<(Apunte, MovimientoBanco)>,
+    /// Apuntes contables sin contrapartida en el banco
+    pub solo_contabilidad: Vec<Apunte>,
+    /// Movimientos del banco sin apunte contable
+    pub solo_banco: Vec<MovimientoBanco>,
+}
+
+impl Conciliacion {
+    /// Indica si todos los apuntes y movimientos han encontrado pareja
+    pub fn completa(&self) -> bool {
+        self.solo_contabilidad.is_empty() && self.solo_banco.is_empty()
+    }
+}
+
+/// Interpreta una fecha en formato ISO ("2023-08-09") o española ("09/08/2023")
+fn leer_fecha(texto: &str) -> Option<NaiveDate> {
+    let texto = texto.trim();
+    NaiveDate::parse_from_str(texto, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(texto, "%d/%m/%Y"))
+        .ok()
+}
+
+/// Lee un extracto bancario en CSV con las columnas fecha, importe y concepto.
+/// El separador, `;` o `,`, se detecta una sola vez en la primera línea (la cabecera, si la hay),
+/// y con `;` el importe puede llevar coma decimal. El concepto es la última columna y puede contener
+/// el separador. Los errores indican el número de línea, contando desde 1
+pub fn leer_extracto_banco_csv(path: &str) -> Result<Vec<MovimientoBanco>, CuadroError> {
+
+    let contenido = std::fs::read_to_string(path)
+        .map_err(|e| CuadroError::ArchivoIlegible(e.to_string()))?;
+
+    let mut lineas = contenido.lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l.trim()))
+        .filter(|(_, l)| !l.is_empty())
+        .peekable();
+
+    // Ni la cabecera ni una fecha contienen el separador, así que el primero que aparezca es el bueno
+    let separador = match lineas.peek() {
+        Some((numero, linea)) => linea.chars()
+            .find(|c| *c == ';' || *c == ',')
+            .ok_or_else(|| CuadroError::ExtractoNoValido(*numero, String::from("no se encuentra el separador ';' ni ','")))?,
+        None => return Ok(vec![]),
+    };
+
+    let mut movimientos = vec![];
+
+    for (numero, linea) in lineas {
+        let campos: Vec<&str> = linea.splitn(3, separador).collect();
+        if campos.len() < 3 {
+            return Err(CuadroError::ExtractoNoValido(numero, format!("'{}' no tiene tres columnas separadas por '{}'", linea, separador)));
+        }
+        if campos[0].trim().to_lowercase() == "fecha" {
+            continue;
+        }
+        let fecha = leer_fecha(campos[0])
+            .ok_or_else(|| CuadroError::ExtractoNoValido(numero, format!("fecha no válida: '{}'", campos[0].trim())))?;
+        let importe = leer_importe(campos[1].trim())
+            .ok_or_else(|| CuadroError::ExtractoNoValido(numero, format!("importe no válido: '{}'", campos[1].trim())))?;
+        movimientos.push(MovimientoBanco { fecha, importe, concepto: campos[2].trim().to_string() });
+    }
+
+    Ok(movimientos)
+}
+
+impl Cuadro {
+
+    /// Concilia los apuntes de una cuenta de tesorería con los movimientos del banco.
+    /// Cada apunte se empareja con el movimiento del mismo importe y signo cuya fecha
+    /// sea la más cercana, siempre que no se aleje más de `DIAS_TOLERANCIA` días.
+    pub fn conciliar(&self, codigo_cuenta: &str, movimientos_banco: &[MovimientoBanco]) -> Result<Conciliacion, CuadroError> {
+
+        let cuenta = self.cuentas()
+            .find(|c| c.codigo() == codigo_cuenta)
+            .ok_or_else(|| CuadroError::CuentaInexistente(codigo_cuenta.to_string()))?;
+
+        let mut pendientes: Vec<Option<&MovimientoBanco>> = movimientos_banco.iter().map(Some).collect();
+        let mut conciliados = vec![];
+        let mut solo_contabilidad = vec![];
+
+        for apunte in cuenta.apuntes() {
+            let importe = match apunte.lado() {
+                Lado::Debe => apunte.importe(),
+                Lado::Haber => -apunte.importe(),
+            };
+            let candidato = pendientes.iter()
+                .enumerate()
+                .filter_map(|(i, m)| m.map(|m| (i, m)))
+                .filter(|(_, m)| m.importe == importe)
+                .map(|(i, m)| (i, (m.fecha - apunte.fecha()).num_days().abs()))
+                .filter(|(_, dias)| *dias <= DIAS_TOLERANCIA)
+                .min_by_key(|(_, dias)| *dias);
+
+            match candidato {
+                Some((i, _)) => {
+                    let movimiento = pendientes[i].take().unwrap();
+                    conciliados.push((apunte.clone(), movimiento.clone()));
+                },
+                None => solo_contabilidad.push(apunte.clone()),
+            }
+        }
+
+        let solo_banco = pendientes.into_iter().flatten().cloned().collect();
+
+        Ok(Conciliacion { conciliados, solo_contabilidad, solo_banco })
+    }
+}
+
+#[cfg(test)]
+mod conciliacion_tests {
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn fecha(dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 8, dia).unwrap()
+    }
+
+    fn movimiento(dia: u32, importe: f64) -> MovimientoBanco {
+        MovimientoBanco { fecha: fecha(dia), importe: Moneda::from_euros(importe), concepto: String::from("banco") }
+    }
+
+    fn setup() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Clientes", "430", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Suministros", "628", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Cobro", Some(fecha(2)), vec![("572", Moneda::from_euros(100.0))], vec![("430", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Luz", Some(fecha(10)), vec![("628", Moneda::from_euros(45.5))], vec![("572", Moneda::from_euros(45.5))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Agua", Some(fecha(20)), vec![("628", Moneda::from_euros(30.0))], vec![("572", Moneda::from_euros(30.0))], &mut cuadro).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn conciliar_empareja_por_importe_y_fecha_aproximada() {
+        let cuadro = setup();
+        let banco = vec![movimiento(4, 100.0), movimiento(9, -45.5), movimiento(21, 12.0)];
+
+        let conciliacion = cuadro.conciliar("572", &banco).unwrap();
+
+        assert_eq!(conciliacion.conciliados.len(), 2);
+        assert_eq!(conciliacion.conciliados[0].1, banco[0]);
+        assert_eq!(conciliacion.conciliados[1].1, banco[1]);
+        assert_eq!(conciliacion.solo_contabilidad.len(), 1);
+        assert_eq!(conciliacion.solo_contabilidad[0].fecha(), fecha(20));
+        assert_eq!(conciliacion.solo_banco, vec![banco[2].clone()]);
+        assert!(!conciliacion.completa());
+    }
+
+    #[test]
+    fn conciliar_no_empareja_fuera_de_tolerancia_ni_con_signo_contrario() {
+        let cuadro = setup();
+        let banco = vec![movimiento(2, -100.0), movimiento(10 + DIAS_TOLERANCIA as u32 + 1, -45.5)];
+
+        let conciliacion = cuadro.conciliar("572", &banco).unwrap();
+
+        assert!(conciliacion.conciliados.is_empty());
+        assert_eq!(conciliacion.solo_contabilidad.len(), 3);
+        assert_eq!(conciliacion.solo_banco.len(), 2);
+    }
+
+    #[test]
+    fn conciliar_cuenta_inexistente_falla() {
+        assert_eq!(setup().conciliar("999", &[]), Err(CuadroError::CuentaInexistente(String::from("999"))));
+    }
+
+    fn leer_extracto(nombre: &str, contenido: &str) -> Result<Vec<MovimientoBanco>, CuadroError> {
+        let path = std::env::temp_dir().join(nombre);
+        std::fs::write(&path, contenido).unwrap();
+        leer_extracto_banco_csv(path.to_str().unwrap())
+    }
+
+    #[test]
+    fn leer_extracto_banco_csv_admite_ambos_separadores() {
+        let movimientos = leer_extracto("presupuestos_extracto_punto_y_coma.csv", "fecha;importe;concepto\n04/08/2023;1.000,50;Transferencia; cliente\n").unwrap();
+        assert_eq!(movimientos, vec![
+            MovimientoBanco { fecha: fecha(4), importe: Moneda::from_euros(1000.5), concepto: String::from("Transferencia; cliente") },
+        ]);
+
+        let movimientos = leer_extracto("presupuestos_extracto_coma.csv", "2023-08-09,-45.5,Recibo luz; agosto\n").unwrap();
+        assert_eq!(movimientos, vec![
+            MovimientoBanco { fecha: fecha(9), importe: Moneda::from_euros(-45.5), concepto: String::from("Recibo luz; agosto") },
+        ]);
+
+        assert!(matches!(leer_extracto_banco_csv("/no/existe.csv"), Err(CuadroError::ArchivoIlegible(_))));
+    }
+
+    #[test]
+    fn leer_extracto_banco_csv_usa_el_separador_de_la_cabecera_en_todas_las_lineas() {
+        let resultado = leer_extracto(
+            "presupuestos_extracto_mezclado.csv",
+            "fecha;importe;concepto\n04/08/2023;100;Cobro\n\n2023-08-09,-45.5,Recibo luz\n",
+        );
+
+        assert!(matches!(resultado, Err(CuadroError::ExtractoNoValido(4, _))));
+    }
+
+    #[test]
+    fn leer_extracto_banco_csv_indica_la_linea_con_la_fecha_o_el_importe_mal() {
+        assert_eq!(
+            leer_extracto("presupuestos_extracto_fecha.csv", "fecha;importe;concepto\n31/02/2023;100;Cobro\n"),
+            Err(CuadroError::ExtractoNoValido(2, String::from("fecha no válida: '31/02/2023'")))
+        );
+        assert_eq!(
+            leer_extracto("presupuestos_extracto_importe.csv", "04/08/2023;cien;Cobro\n"),
+            Err(CuadroError::ExtractoNoValido(1, String::from("importe no válido: 'cien'")))
+        );
+    }
+}
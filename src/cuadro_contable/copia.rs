@@ -0,0 +1,312 @@
+use std::fs;
+
+use chrono::NaiveDate;
+
+use super::{prestar_anotaciones, Cuadro, CuadroError, LibroDiario};
+use super::asiento::{Asiento, TipoAsiento};
+use super::cuenta::Cuenta;
+use super::ejercicio::Ejercicio;
+use super::json::{self, Json};
+use super::masa::Masa;
+use super::moneda::Moneda;
+use super::movimiento::Movimiento;
+
+/// Versión del formato de exportación. Si cambia, `cargar_ejercicio` ha de saber migrar las anteriores
+pub const VERSION_FORMATO: u32 = 1;
+
+fn fecha_json(fecha: NaiveDate) -> Json {
+    Json::Texto(fecha.format("%Y-%m-%d").to_string())
+}
+
+fn movimientos_json(movimientos: &[Movimiento]) -> Json {
+    Json::Lista(movimientos.iter().map(|m| Json::objeto(vec![
+        ("cuenta", Json::Texto(m.codigo_cuenta())),
+        ("importe", Json::Numero(m.importe().as_euros())),
+    ])).collect())
+}
+
+fn asiento_json(asiento: &Asiento) -> Json {
+    Json::objeto(vec![
+        ("codigo", Json::Texto(asiento.codigo())),
+        ("fecha", fecha_json(asiento.fecha())),
+        ("concepto", Json::Texto(asiento.concepto())),
+        ("tipo", Json::Texto(asiento.tipo().to_string())),
+        ("tercero", Json::texto_opcional(asiento.tercero())),
+        ("justificante", Json::texto_opcional(asiento.justificante())),
+        ("debe", movimientos_json(asiento.debe())),
+        ("haber", movimientos_json(asiento.haber())),
+    ])
+}
+
+fn cuenta_json(cuenta: &Cuenta) -> Json {
+    Json::objeto(vec![
+        ("codigo", Json::Texto(cuenta.codigo())),
+        ("nombre", Json::Texto(cuenta.nombre())),
+        ("masa", Json::Texto(cuenta.masa().to_string())),
+        ("activa", Json::Booleano(cuenta.activa())),
+        ("categoria", Json::texto_opcional(cuenta.categoria())),
+        ("saldo_apertura", Json::Numero(cuenta.saldo_apertura().as_euros())),
+        ("saldo", Json::Numero(cuenta.saldo().as_euros())),
+    ])
+}
+
+/// Error de formato al cargar un ejercicio, con el campo que falta o no es válido
+fn campo_invalido(campo: &str) -> CuadroError {
+    CuadroError::ArchivoIlegible(format!("falta el campo '{}' o no es válido", campo))
+}
+
+fn texto<'a>(valor: &'a Json, campo: &str) -> Result<&'a str, CuadroError> {
+    valor.campo(campo).and_then(Json::como_texto).ok_or_else(|| campo_invalido(campo))
+}
+
+fn numero(valor: &Json, campo: &str) -> Result<f64, CuadroError> {
+    valor.campo(campo).and_then(Json::como_numero).ok_or_else(|| campo_invalido(campo))
+}
+
+/// Importe en euros, redondeado a céntimos
+fn importe(valor: &Json, campo: &str) -> Result<Moneda, CuadroError> {
+    numero(valor, campo).map(Moneda::from_euros)
+}
+
+fn lista<'a>(valor: &'a Json, campo: &str) -> Result<&'a [Json], CuadroError> {
+    valor.campo(campo).and_then(Json::como_lista).ok_or_else(|| campo_invalido(campo))
+}
+
+/// Texto opcional: admite que el campo falte o sea nulo
+fn texto_opcional<'a>(valor: &'a Json, campo: &str) -> Result<Option<&'a str>, CuadroError> {
+    match valor.campo(campo) {
+        None | Some(Json::Nulo) => Ok(None),
+        Some(v) => v.como_texto().map(Some).ok_or_else(|| campo_invalido(campo)),
+    }
+}
+
+fn fecha(valor: &Json, campo: &str) -> Result<NaiveDate, CuadroError> {
+    NaiveDate::parse_from_str(texto(valor, campo)?, "%Y-%m-%d").map_err(|_| campo_invalido(campo))
+}
+
+fn fecha_opcional(valor: &Json, campo: &str) -> Result<Option<NaiveDate>, CuadroError> {
+    match valor.campo(campo) {
+        None | Some(Json::Nulo) => Ok(None),
+        Some(_) => fecha(valor, campo).map(Some),
+    }
+}
+
+/// Lee los pares de código de cuenta e importe de un lado del asiento
+fn anotaciones(asiento: &Json, lado: &str) -> Result<Vec<(String, Moneda)>, CuadroError> {
+    lista(asiento, lado)?.iter()
+        .map(|m| Ok((texto(m, "cuenta")?.to_string(), importe(m, "importe")?)))
+        .collect()
+}
+
+impl Cuadro {
+
+    /// Exporta el ejercicio completo a un único JSON, que es la copia de seguridad canónica: metadatos
+    /// (versión del formato, fecha de generación y fechas del ejercicio), las cuentas con sus saldos,
+    /// los asientos del libro diario con sus movimientos y el bloqueo de periodos.
+    /// Se restaura con [`Cuadro::cargar_ejercicio`].
+    pub fn exportar_ejercicio(&self, libro_diario: &LibroDiario, path: &str) -> std::io::Result<()> {
+
+        let ejercicio = self.ejercicio.map_or(Json::Nulo, |e| Json::objeto(vec![
+            ("inicio", fecha_json(e.inicio())),
+            ("fin", fecha_json(e.fin())),
+        ]));
+
+        let exportacion = Json::objeto(vec![
+            ("version", Json::Numero(VERSION_FORMATO as f64)),
+            ("generado", Json::Texto(chrono::offset::Local::now().naive_local().format("%Y-%m-%dT%H:%M:%S").to_string())),
+            ("ejercicio", ejercicio),
+            ("bloqueado_hasta", self.bloqueado_hasta.map_or(Json::Nulo, fecha_json)),
+            ("cuenta_tesoreria", Json::texto_opcional(self.cuenta_tesoreria.as_deref())),
+            ("cuentas", Json::Lista(self.cuentas.iter().map(cuenta_json).collect())),
+            ("asientos", Json::Lista(libro_diario.asientos().map(asiento_json).collect())),
+        ]);
+
+        fs::write(path, format!("{}\n", exportacion))
+    }
+
+    /// Restaura un ejercicio exportado con [`Cuadro::exportar_ejercicio`]. Crea las cuentas, vuelve a
+    /// insertar los asientos en su orden original y, al final, restaura los saldos de apertura y el
+    /// bloqueo de periodos. Comprueba que los saldos resultantes coinciden con los exportados.
+    pub fn cargar_ejercicio(path: &str) -> Result<(Cuadro, LibroDiario), CuadroError> {
+
+        let contenido = fs::read_to_string(path)
+            .map_err(|e| CuadroError::ArchivoIlegible(e.to_string()))?;
+        let datos = json::interpretar(&contenido).map_err(CuadroError::ArchivoIlegible)?;
+
+        let version = numero(&datos, "version")?;
+        if version != VERSION_FORMATO as f64 {
+            return Err(CuadroError::ArchivoIlegible(format!("versión de formato no admitida: {}", version)));
+        }
+
+        let mut cuadro = Cuadro::new();
+        let mut libro_diario = LibroDiario::new();
+
+        if let Some(ejercicio) = datos.campo("ejercicio").filter(|e| **e != Json::Nulo) {
+            let ejercicio = Ejercicio::new(fecha(ejercicio, "inicio")?, fecha(ejercicio, "fin")?)
+                .ok_or_else(|| campo_invalido("ejercicio"))?;
+            cuadro.establecer_ejercicio(ejercicio);
+        }
+
+        let cuentas = lista(&datos, "cuentas")?;
+        for cuenta in cuentas {
+            let codigo = texto(cuenta, "codigo")?;
+            if cuadro.cuentas.iter().any(|c| c.codigo() == codigo) {
+                return Err(CuadroError::CuentaDuplicada(codigo.to_string()));
+            }
+            let masa: Masa = texto(cuenta, "masa")?.parse().map_err(CuadroError::MasaDesconocida)?;
+            cuadro.cuentas.push(Cuenta::new(texto(cuenta, "nombre")?, codigo, masa));
+        }
+
+        for asiento in lista(&datos, "asientos")? {
+            let codigo = texto(asiento, "codigo")?;
+            let debe = anotaciones(asiento, "debe")?;
+            let haber = anotaciones(asiento, "haber")?;
+            // El tipo se fija al insertar, porque decide si el asiento admite importes negativos
+            let tipo = match texto_opcional(asiento, "tipo")? {
+                Some(tipo) => tipo.parse::<TipoAsiento>().map_err(|_| campo_invalido("tipo"))?,
+                None => TipoAsiento::Normal,
+            };
+            libro_diario.insertar(
+                texto(asiento, "concepto")?,
+                Some(fecha(asiento, "fecha")?),
+                prestar_anotaciones(&debe),
+                prestar_anotaciones(&haber),
+                tipo,
+                &mut cuadro,
+            ).map_err(|e| CuadroError::ArchivoIlegible(format!("el asiento {} no se puede insertar: {}", codigo, e)))?;
+
+            let insertado = libro_diario.asientos.last_mut().unwrap();
+            if let Some(tercero) = texto_opcional(asiento, "tercero")? {
+                insertado.fijar_tercero(tercero);
+            }
+            if let Some(justificante) = texto_opcional(asiento, "justificante")? {
+                insertado.fijar_justificante(justificante);
+            }
+        }
+
+        for (datos_cuenta, cuenta) in cuentas.iter().zip(cuadro.cuentas.iter_mut()) {
+            if cuenta.saldo() != importe(datos_cuenta, "saldo")? {
+                return Err(CuadroError::ArchivoIlegible(format!("el saldo de la cuenta {} no coincide con sus asientos", cuenta.codigo())));
+            }
+            cuenta.restaurar_saldo_apertura(importe(datos_cuenta, "saldo_apertura")?);
+            if datos_cuenta.campo("activa").and_then(Json::como_booleano) == Some(false) {
+                cuenta.desactivar();
+            }
+            cuenta.fijar_categoria(texto_opcional(datos_cuenta, "categoria")?);
+        }
+
+        if let Some(codigo) = texto_opcional(&datos, "cuenta_tesoreria")? {
+            cuadro.fijar_cuenta_tesoreria(codigo)?;
+        }
+        // El bloqueo se restaura al final: antes impediría volver a insertar los asientos de los periodos bloqueados
+        cuadro.bloqueado_hasta = fecha_opcional(&datos, "bloqueado_hasta")?;
+
+        Ok((cuadro, libro_diario))
+    }
+}
+
+#[cfg(test)]
+mod copia_tests {
+
+    use super::*;
+
+    fn fecha(anio: i32, mes: u32, dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(anio, mes, dia).unwrap()
+    }
+
+    fn setup() -> (Cuadro, LibroDiario) {
+        let mut cuadro = Cuadro::new();
+        cuadro.establecer_ejercicio(Ejercicio::natural(2023));
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Caja \"B\"", "570", Masa::ActivoCorriente).unwrap();
+        cuadro.fijar_cuenta_tesoreria("572").unwrap();
+
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", Some(fecha(2023, 1, 1)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.marcar_tipo("202301010", TipoAsiento::Apertura).unwrap();
+        cuadro.fijar_saldos_apertura();
+        libro_diario.insertar_asiento("Venta\nfactura 7", Some(fecha(2023, 2, 3)), vec![("572", Moneda::from_euros(99.99))], vec![("700", Moneda::from_euros(99.99))], &mut cuadro).unwrap();
+        libro_diario.asignar_tercero("202302030", "Cliente, S.L.").unwrap();
+        libro_diario.asignar_justificante("202302030", "F-7").unwrap();
+        cuadro.desactivar_cuenta("570").unwrap();
+        cuadro.categorizar_cuenta("700", Some("Trabajo")).unwrap();
+        cuadro.bloquear_hasta(fecha(2023, 3, 31));
+
+        (cuadro, libro_diario)
+    }
+
+    #[test]
+    fn cargar_ejercicio_restaura_lo_exportado() {
+        let (mut cuadro, libro_diario) = setup();
+        let path = std::env::temp_dir().join("presupuestos_ejercicio.json");
+
+        cuadro.exportar_ejercicio(&libro_diario, path.to_str().unwrap()).unwrap();
+        let (mut cargado, libro_cargado) = Cuadro::cargar_ejercicio(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(cargado.ejercicio(), cuadro.ejercicio());
+        assert_eq!(cargado.bloqueado_hasta(), Some(fecha(2023, 3, 31)));
+        assert_eq!(cargado.cuenta_tesoreria(), Some("572"));
+        for codigo in ["572", "100", "700"] {
+            let original = cuadro.buscar_cuenta(codigo).map(|c| (c.nombre(), c.saldo(), c.saldo_apertura()));
+            assert_eq!(cargado.buscar_cuenta(codigo).map(|c| (c.nombre(), c.saldo(), c.saldo_apertura())), original);
+        }
+        assert!(!cargado.buscar_cuenta("570").unwrap().activa());
+        assert_eq!(cargado.buscar_cuenta("570").unwrap().nombre(), "Caja \"B\"");
+        assert_eq!(cargado.buscar_cuenta("700").unwrap().categoria(), Some("Trabajo"));
+        assert_eq!(cargado.buscar_cuenta("572").unwrap().categoria(), None);
+
+        let asientos: Vec<&Asiento> = libro_cargado.asientos().collect();
+        assert_eq!(asientos.len(), 2);
+        assert_eq!(asientos[0].tipo(), TipoAsiento::Apertura);
+        assert_eq!(asientos[1].tipo(), TipoAsiento::Normal);
+        assert_eq!(asientos[1].codigo(), "202302030");
+        assert_eq!(asientos[1].concepto(), "Venta\nfactura 7");
+        assert_eq!(asientos[1].tercero(), Some("Cliente, S.L."));
+        assert_eq!(asientos[1].justificante(), Some("F-7"));
+    }
+
+    #[test]
+    fn cargar_ejercicio_vuelve_a_insertar_las_rectificativas_con_su_tipo() {
+        let (mut cuadro, mut libro_diario) = setup();
+        libro_diario.insertar_rectificativo("Abono factura 7", Some(fecha(2023, 4, 3)), vec![("572", Moneda::from_euros(-99.99))], vec![("700", Moneda::from_euros(-99.99))], &mut cuadro).unwrap();
+        let path = std::env::temp_dir().join("presupuestos_ejercicio_rectificativa.json");
+
+        cuadro.exportar_ejercicio(&libro_diario, path.to_str().unwrap()).unwrap();
+        let (mut cargado, libro_cargado) = Cuadro::cargar_ejercicio(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(libro_cargado.asientos().last().unwrap().tipo(), TipoAsiento::Rectificativo);
+        assert_eq!(cargado.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(0.0));
+    }
+
+    #[test]
+    fn exportar_ejercicio_incluye_version_y_metadatos() {
+        let (cuadro, libro_diario) = setup();
+        let path = std::env::temp_dir().join("presupuestos_ejercicio_metadatos.json");
+
+        cuadro.exportar_ejercicio(&libro_diario, path.to_str().unwrap()).unwrap();
+        let datos = json::interpretar(&fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(datos.campo("version").and_then(Json::como_numero), Some(1.0));
+        assert!(datos.campo("generado").and_then(Json::como_texto).is_some());
+        assert_eq!(datos.campo("ejercicio").and_then(|e| e.campo("fin")).and_then(Json::como_texto), Some("2023-12-31"));
+        assert_eq!(datos.campo("bloqueado_hasta").and_then(Json::como_texto), Some("2023-03-31"));
+    }
+
+    #[test]
+    fn cargar_ejercicio_rechaza_versiones_desconocidas_y_saldos_descuadrados() {
+        let path = std::env::temp_dir().join("presupuestos_ejercicio_invalido.json");
+
+        fs::write(&path, r#"{"version": 2, "cuentas": [], "asientos": []}"#).unwrap();
+        assert_eq!(
+            Cuadro::cargar_ejercicio(path.to_str().unwrap()).err(),
+            Some(CuadroError::ArchivoIlegible("versión de formato no admitida: 2".to_string()))
+        );
+
+        fs::write(&path, r#"{"version": 1, "cuentas": [
+            {"codigo": "572", "nombre": "Bancos", "masa": "ActivoCorriente", "activa": true, "saldo_apertura": 0, "saldo": 50}
+        ], "asientos": []}"#).unwrap();
+        assert!(matches!(Cuadro::cargar_ejercicio(path.to_str().unwrap()), Err(CuadroError::ArchivoIlegible(_))));
+    }
+}
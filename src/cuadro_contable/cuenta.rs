@@ -1,8 +1,10 @@
 use std::fmt::Display;
-use super::masa::Masa;
 
-#[derive(PartialEq, Debug)]
-pub struct CuentaError;
+use chrono::NaiveDate;
+
+use super::apunte::{Apunte, Lado};
+use super::masa::{self, Masa, Naturaleza};
+use super::moneda::Moneda;
 
 /// Representa una cuenta
 #[derive(PartialEq, Debug)]
@@ -11,16 +13,20 @@ pub struct Cuenta {
     nombre: String,
     /// El código de la cuenta, que debe ser único e informa también del grupo al que pertence.
     codigo: String,
-    /// Los importes del debe
-    debe: Vec<f64>,
-    /// Los importes del haber
-    haber: Vec<f64>,
+    /// Los apuntes del debe y del haber, en orden de anotación
+    apuntes: Vec<Apunte>,
     /// El saldo deudor
-    saldo_deudor: f64,
+    saldo_deudor: Moneda,
     /// El saldo acreedor
-    saldo_acreedor: f64,
+    saldo_acreedor: Moneda,
+    /// El saldo con el que se abrió el ejercicio
+    saldo_apertura: Moneda,
     /// Masa
-    masa: Masa
+    masa: Masa,
+    /// Las cuentas inactivas se conservan, pero no aparecen en los listados
+    activa: bool,
+    /// Categoría propia (alimentación, ocio, vivienda...) para los informes domésticos, si se le ha dado
+    categoria: Option<String>,
 
 }
 
@@ -32,21 +38,23 @@ impl Display for Cuenta {
         if let Some(w) = f.width() { // Si se le pasa ancho, rellena la fila completa
 
             // Cadena de saldo
-            let saldo_str = format!("{:.2} €", self.saldo_deudor - self.saldo_acreedor);
+            let saldo_str = self.saldo().to_string();
 
             // Cadena de código y nombre
             let codigo_nombre_str = format!("({}) {}", self.codigo, self.nombre);
 
-            // Si el ancho es suficiente, deja espacio a los puntos intermedios
-            if w >= codigo_nombre_str.len() + 1 {
-                write!(f,"{}{:.>width$}", codigo_nombre_str, saldo_str, width=w - codigo_nombre_str.len())?;
+            // Si el ancho es suficiente, deja espacio a los puntos intermedios.
+            // El ancho se cuenta en caracteres, no en bytes, para que las tildes no descuadren la fila
+            let largo = codigo_nombre_str.chars().count();
+            if w > largo {
+                write!(f,"{}{:.>width$}", codigo_nombre_str, saldo_str, width=w - largo)?;
             } else { // En caso contrario, imprime con espacio
                 write!(f, "{} {}", codigo_nombre_str, saldo_str)?;
             }
 
         } else {
             // Formato estándar
-            write!(f, "({}) {} ~ {:.2} €", self.codigo, self.nombre, self.saldo_deudor - self.saldo_acreedor)?;
+            write!(f, "({}) {} ~ {}", self.codigo, self.nombre, self.saldo())?;
         }
 
         Ok(())
@@ -59,24 +67,50 @@ impl Cuenta {
         Cuenta {
             nombre: String::from(nombre),
             codigo: String::from(codigo),
-            debe: vec![],
-            haber: vec![],
-            saldo_deudor: 0.00,
-            saldo_acreedor: 0.00,
+            apuntes: vec![],
+            saldo_deudor: Moneda::CERO,
+            saldo_acreedor: Moneda::CERO,
+            saldo_apertura: Moneda::CERO,
             masa,
+            activa: true,
+            categoria: None,
         }
     }
 
     /// Incrementa el saldo por el debe (carga la cuenta)
-    pub fn saldo_deudor(&mut self, importe: f64) {
+    pub fn saldo_deudor(&mut self, importe: Moneda) {
         self.saldo_deudor += importe;
     } 
 
     /// Reduce el saldo
-    pub fn saldo_acreedor(&mut self, importe: f64) {
+    pub fn saldo_acreedor(&mut self, importe: Moneda) {
         self.saldo_acreedor += importe;
     } 
 
+    /// Guarda un apunte en la cuenta y actualiza su saldo por el lado correspondiente.
+    /// Un importe negativo resta de las sumas de su lado, por lo que un abono de -X
+    /// en el debe deja el mismo saldo que un apunte de X en el haber.
+    pub fn anotar(&mut self, apunte: Apunte) {
+        match apunte.lado() {
+            Lado::Debe => self.saldo_deudor += apunte.importe(),
+            Lado::Haber => self.saldo_acreedor += apunte.importe(),
+        }
+        self.apuntes.push(apunte);
+    }
+
+    /// Pone a cero los saldos y borra los apuntes de la cuenta.
+    /// Conserva el saldo de apertura, que no depende de los apuntes sino del momento en que se fijó.
+    pub fn reiniciar(&mut self) {
+        self.saldo_deudor = Moneda::CERO;
+        self.saldo_acreedor = Moneda::CERO;
+        self.apuntes.clear();
+    }
+
+    /// Devuelve los apuntes de la cuenta, es decir, su libro mayor
+    pub fn apuntes(&self) -> &[Apunte] {
+        &self.apuntes
+    }
+
     /// Devuelve el nombre de la cuenta
     pub fn nombre(&self) -> String {
         self.nombre.clone()
@@ -87,11 +121,106 @@ impl Cuenta {
         self.codigo.clone()
     }
 
+    /// Devuelve el código como número, para comparar y ordenar cuentas ("20" < "100").
+    /// Devuelve `None` si el código no es puramente numérico, como en las cuentas auxiliares con letras
+    pub fn codigo_numerico(&self) -> Option<u64> {
+        if self.codigo.is_empty() || !self.codigo.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        self.codigo.parse().ok()
+    }
+
     /// Devuelve el saldo de la cuenta
-    pub fn saldo(&self) -> f64 {
+    pub fn saldo(&self) -> Moneda {
         self.saldo_deudor - self.saldo_acreedor
     }
 
+    /// Devuelve el saldo que tenía la cuenta al final del día indicado: la suma de los apuntes
+    /// con esa fecha o anterior
+    pub fn saldo_a_fecha(&self, fecha: NaiveDate) -> Moneda {
+        self.apuntes.iter()
+            .filter(|a| a.fecha() <= fecha)
+            .map(|a| match a.lado() {
+                Lado::Debe => a.importe(),
+                Lado::Haber => -a.importe(),
+            })
+            .sum()
+    }
+
+    /// Toma el saldo actual como saldo de apertura del ejercicio
+    pub fn fijar_saldo_apertura(&mut self) {
+        self.saldo_apertura = self.saldo();
+    }
+
+    /// Restaura un saldo de apertura guardado, al cargar un ejercicio exportado
+    pub(super) fn restaurar_saldo_apertura(&mut self, saldo: Moneda) {
+        self.saldo_apertura = saldo;
+    }
+
+    /// Devuelve el saldo con el que se abrió el ejercicio
+    pub fn saldo_apertura(&self) -> Moneda {
+        self.saldo_apertura
+    }
+
+    /// Devuelve la variación del saldo desde la apertura, es decir, el movimiento propio del ejercicio
+    pub fn saldo_ejercicio(&self) -> Moneda {
+        self.saldo() - self.saldo_apertura
+    }
+
+    /// Devuelve la masa a la que pertenece la cuenta
+    pub fn masa(&self) -> Masa {
+        self.masa
+    }
+
+    /// Indica si es una cuenta correctora de valor, como la amortización acumulada, que minora el activo al que acompaña
+    pub fn es_correctora(&self) -> bool {
+        masa::es_correctora(&self.codigo)
+    }
+
+    /// Devuelve la naturaleza de su saldo: la de su masa, o la contraria si es correctora
+    pub fn naturaleza(&self) -> Naturaleza {
+        if self.es_correctora() {
+            return self.masa.naturaleza().contraria();
+        }
+        self.masa.naturaleza()
+    }
+
+    /// Cambia la masa a la que pertenece la cuenta
+    pub fn cambiar_masa(&mut self, masa: Masa) {
+        self.masa = masa;
+    }
+
+    /// Devuelve la categoría propia de la cuenta, si tiene
+    pub fn categoria(&self) -> Option<&str> {
+        self.categoria.as_deref()
+    }
+
+    /// Etiqueta la cuenta con una categoría propia, independiente de su código contable.
+    /// Con `None` se le quita la categoría
+    pub fn fijar_categoria(&mut self, categoria: Option<&str>) {
+        self.categoria = categoria.map(|c| c.to_string());
+    }
+
+    /// Indica si la cuenta está activa, es decir, si aparece en los listados
+    pub fn activa(&self) -> bool {
+        self.activa
+    }
+
+    /// Vuelve a mostrar la cuenta en los listados
+    pub fn activar(&mut self) {
+        self.activa = true;
+    }
+
+    /// Oculta la cuenta de los listados sin borrarla.
+    /// Una cuenta con saldo no se puede desactivar: devuelve `false` y la deja activa
+    pub fn desactivar(&mut self) -> bool {
+        if self.saldo() != Moneda::CERO {
+            return false;
+        }
+        self.activa = false;
+        true
+    }
+
 }
 
 #[cfg(test)]
@@ -103,11 +232,13 @@ mod cuenta_tests {
         Cuenta {
             nombre: "test".to_string(),
             codigo: "0000".to_string(),
-            debe: vec![],
-            haber: vec![],
-            saldo_deudor: 0.00,
-            saldo_acreedor: 0.00,
+            apuntes: vec![],
+            saldo_deudor: Moneda::CERO,
+            saldo_acreedor: Moneda::CERO,
+            saldo_apertura: Moneda::CERO,
             masa: Masa::ActivoCorriente,
+            activa: true,
+            categoria: None,
         }
     }
 
@@ -118,11 +249,13 @@ mod cuenta_tests {
         assert_eq!(cuenta, Cuenta {
             nombre: "Cuenta 1".to_string(),
             codigo: "101".to_string(),
-            debe: vec![],
-            haber: vec![],
-            saldo_deudor: 0.00,
-            saldo_acreedor: 0.00,
-            masa: Masa::ActivoCorriente
+            apuntes: vec![],
+            saldo_deudor: Moneda::CERO,
+            saldo_acreedor: Moneda::CERO,
+            saldo_apertura: Moneda::CERO,
+            masa: Masa::ActivoCorriente,
+            activa: true,
+            categoria: None,
         })
     }
 
@@ -130,17 +263,80 @@ mod cuenta_tests {
     fn saldo_deudor() {
         let mut cuenta = setup_cuenta();
 
-        cuenta.saldo_deudor(20.05);
+        cuenta.saldo_deudor(Moneda::from_euros(20.05));
 
-        assert_eq!(cuenta.saldo(), 20.05);
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(20.05));
     }
 
     #[test]
     fn saldo_acreedor() {
         let mut cuenta = setup_cuenta();
 
-        cuenta.saldo_acreedor(20.05);
-        assert_eq!(cuenta.saldo(), -20.05);
+        cuenta.saldo_acreedor(Moneda::from_euros(20.05));
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(-20.05));
+    }
+
+    #[test]
+    fn saldo_no_acumula_error_de_coma_flotante() {
+        let mut cuenta = setup_cuenta();
+
+        for _ in 0..10 {
+            cuenta.saldo_deudor(Moneda::from_euros(0.1));
+        }
+        cuenta.saldo_acreedor(Moneda::from_euros(0.3));
+
+        assert_eq!(cuenta.saldo(), Moneda::from_centimos(70));
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(0.7));
+    }
+
+    #[test]
+    fn anotar_guarda_el_apunte_y_actualiza_el_saldo() {
+        let mut cuenta = setup_cuenta();
+        let fecha = chrono::NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+
+        cuenta.anotar(Apunte::new(fecha, Moneda::from_euros(30.0), Lado::Debe, "202308090"));
+        cuenta.anotar(Apunte::new(fecha, Moneda::from_euros(10.0), Lado::Haber, "202308091"));
+
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(20.0));
+        assert_eq!(cuenta.apuntes().len(), 2);
+        assert_eq!(cuenta.apuntes()[1], Apunte::new(fecha, Moneda::from_euros(10.0), Lado::Haber, "202308091"));
+    }
+
+    #[test]
+    fn reiniciar_pone_a_cero_saldos_y_apuntes() {
+        let mut cuenta = setup_cuenta();
+        let fecha = chrono::NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        cuenta.anotar(Apunte::new(fecha, Moneda::from_euros(30.0), Lado::Debe, "202308090"));
+
+        cuenta.reiniciar();
+
+        assert_eq!(cuenta, setup_cuenta());
+    }
+
+    #[test]
+    fn saldo_ejercicio_descuenta_el_saldo_de_apertura() {
+        let mut cuenta = setup_cuenta();
+        let fecha = chrono::NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        cuenta.anotar(Apunte::new(fecha, Moneda::from_euros(100.0), Lado::Debe, "202308090"));
+        cuenta.fijar_saldo_apertura();
+        cuenta.anotar(Apunte::new(fecha, Moneda::from_euros(30.0), Lado::Haber, "202308091"));
+
+        assert_eq!(cuenta.saldo_apertura(), Moneda::from_euros(100.0));
+        assert_eq!(cuenta.saldo_ejercicio(), Moneda::from_euros(-30.0));
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(70.0));
+    }
+
+    #[test]
+    fn saldo_a_fecha_solo_suma_los_apuntes_hasta_ese_dia() {
+        let mut cuenta = setup_cuenta();
+        let fecha = |d| NaiveDate::from_ymd_opt(2023, 8, d).unwrap();
+        cuenta.anotar(Apunte::new(fecha(9), Moneda::from_euros(100.0), Lado::Debe, "202308090"));
+        cuenta.anotar(Apunte::new(fecha(20), Moneda::from_euros(30.0), Lado::Haber, "202308200"));
+
+        assert_eq!(cuenta.saldo_a_fecha(fecha(1)), Moneda::from_euros(0.0));
+        assert_eq!(cuenta.saldo_a_fecha(fecha(9)), Moneda::from_euros(100.0));
+        assert_eq!(cuenta.saldo_a_fecha(fecha(19)), Moneda::from_euros(100.0));
+        assert_eq!(cuenta.saldo_a_fecha(fecha(20)), Moneda::from_euros(70.0));
     }
 
     #[test]
@@ -161,7 +357,7 @@ mod cuenta_tests {
     fn saldo_devuelve_saldo() {
         let cuenta = setup_cuenta();
 
-        assert_eq!(cuenta.saldo(), 0.00);
+        assert_eq!(cuenta.saldo(), Moneda::from_euros(0.00));
     }
 
     #[test]
@@ -172,6 +368,31 @@ mod cuenta_tests {
         assert_eq!(cuenta.to_string(), "(0000) test ~ 0.00 €");
     }
 
+    #[test]
+    fn codigo_numerico_solo_admite_cifras() {
+        let codigo = |codigo: &str| Cuenta::new("test", codigo, Masa::ActivoCorriente).codigo_numerico();
+
+        assert_eq!(codigo("5720001"), Some(5720001));
+        assert_eq!(codigo("0001"), Some(1));
+        assert_eq!(codigo("A01"), None);
+        assert_eq!(codigo("+572"), None);
+        assert_eq!(codigo(""), None);
+    }
+
+    #[test]
+    fn desactivar_no_admite_cuentas_con_saldo() {
+        let mut cuenta = setup_cuenta();
+        assert!(cuenta.activa());
+
+        assert!(cuenta.desactivar());
+        assert!(!cuenta.activa());
+        cuenta.activar();
+
+        cuenta.anotar(Apunte::new(chrono::NaiveDate::from_ymd_opt(2023, 8, 9).unwrap(), Moneda::from_euros(10.0), Lado::Debe, "202308090"));
+        assert!(!cuenta.desactivar());
+        assert!(cuenta.activa());
+    }
+
     #[test]
     fn display_con_ancho_ocupa_todo_el_ancho() {
 
@@ -179,6 +400,11 @@ mod cuenta_tests {
 
         assert_eq!(format!("{:width$}", cuenta, width=20), "(0000) test...0.00 €");
 
+        let tesoreria = Cuenta::new("Tesorería", "57", Masa::ActivoCorriente);
+        let fila = format!("{:width$}", tesoreria, width=30);
+        assert_eq!(fila, "(57) Tesorería..........0.00 €");
+        assert_eq!(fila.chars().count(), 30);
+
     }
 
     #[test]
@@ -1,4 +1,4 @@
-pub const CUENTAS_PGC: [(&str, &str); 899] = [
+pub static CUENTAS_PGC: [(&str, &str); 899] = [
     ("CAPITAL", "10"),
     ("Capital social", "100"),
     ("Fondo social", "101"),
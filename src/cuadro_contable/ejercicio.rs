@@ -0,0 +1,101 @@
+use std::fmt::Display;
+
+use chrono::{Datelike, Months, NaiveDate};
+
+/// Un ejercicio contable: el periodo entre dos fechas, ambas incluidas.
+/// No tiene por qué coincidir con el año natural (p. ej. de julio a junio).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ejercicio {
+    inicio: NaiveDate,
+    fin: NaiveDate,
+}
+
+impl Display for Ejercicio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {}", self.inicio.format("%d/%m/%Y"), self.fin.format("%d/%m/%Y"))
+    }
+}
+
+impl Ejercicio {
+
+    /// Crea un ejercicio entre dos fechas. Devuelve `None` si el fin es anterior al inicio
+    pub fn new(inicio: NaiveDate, fin: NaiveDate) -> Option<Ejercicio> {
+        if fin < inicio {
+            return None
+        }
+        Some(Ejercicio { inicio, fin })
+    }
+
+    /// Crea el ejercicio que coincide con el año natural indicado
+    pub fn natural(anio: i32) -> Ejercicio {
+        Ejercicio {
+            inicio: NaiveDate::from_ymd_opt(anio, 1, 1).unwrap(),
+            fin: NaiveDate::from_ymd_opt(anio, 12, 31).unwrap(),
+        }
+    }
+
+    /// Devuelve la fecha de inicio del ejercicio
+    pub fn inicio(&self) -> NaiveDate {
+        self.inicio
+    }
+
+    /// Devuelve la fecha de fin del ejercicio
+    pub fn fin(&self) -> NaiveDate {
+        self.fin
+    }
+
+    /// Indica si una fecha cae dentro del ejercicio
+    pub fn contiene(&self, fecha: NaiveDate) -> bool {
+        self.inicio <= fecha && fecha <= self.fin
+    }
+
+    /// Devuelve el ejercicio siguiente: empieza el día después del cierre de este y dura un año
+    pub fn siguiente(&self) -> Ejercicio {
+        let inicio = self.fin.succ_opt().unwrap();
+        let fin = inicio
+            .checked_add_months(Months::new(12))
+            .and_then(|f| f.pred_opt())
+            .unwrap();
+        Ejercicio { inicio, fin }
+    }
+
+    /// Devuelve el año en que cierra el ejercicio, que es el que suele darle nombre
+    pub fn anio(&self) -> i32 {
+        self.fin.year()
+    }
+}
+
+#[cfg(test)]
+mod ejercicio_tests {
+
+    use super::*;
+
+    fn fecha(anio: i32, mes: u32, dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(anio, mes, dia).unwrap()
+    }
+
+    #[test]
+    fn new_rechaza_un_fin_anterior_al_inicio() {
+        assert_eq!(Ejercicio::new(fecha(2023, 7, 1), fecha(2023, 6, 30)), None);
+        assert!(Ejercicio::new(fecha(2023, 7, 1), fecha(2024, 6, 30)).is_some());
+    }
+
+    #[test]
+    fn contiene_incluye_ambos_extremos() {
+        let ejercicio = Ejercicio::new(fecha(2023, 7, 1), fecha(2024, 6, 30)).unwrap();
+
+        assert!(ejercicio.contiene(fecha(2023, 7, 1)));
+        assert!(ejercicio.contiene(fecha(2024, 6, 30)));
+        assert!(!ejercicio.contiene(fecha(2023, 6, 30)));
+        assert!(!ejercicio.contiene(fecha(2024, 7, 1)));
+    }
+
+    #[test]
+    fn siguiente_conserva_el_desplazamiento_del_ejercicio() {
+        let ejercicio = Ejercicio::new(fecha(2023, 7, 1), fecha(2024, 6, 30)).unwrap();
+
+        assert_eq!(ejercicio.siguiente(), Ejercicio::new(fecha(2024, 7, 1), fecha(2025, 6, 30)).unwrap());
+        assert_eq!(Ejercicio::natural(2023).siguiente(), Ejercicio::natural(2024));
+        assert_eq!(ejercicio.anio(), 2024);
+    }
+}
@@ -0,0 +1,184 @@
+use super::moneda::Moneda;
+use super::movimiento::Divisa;
+
+/// Formatea un importe al estilo español: punto como separador de miles,
+/// coma decimal y el símbolo del euro al final (p. ej. "-1.234,56 €")
+pub fn formato_importe(importe: Moneda) -> String {
+
+    let centimos = importe.centimos().unsigned_abs();
+    let entera = (centimos / 100).to_string();
+
+    // Agrupa la parte entera de tres en tres cifras
+    let mut miles = String::new();
+    for (i, c) in entera.chars().enumerate() {
+        if i > 0 && (entera.len() - i).is_multiple_of(3) {
+            miles.push('.');
+        }
+        miles.push(c);
+    }
+
+    let signo = if importe < Moneda::CERO { "-" } else { "" };
+
+    format!("{}{},{:02} €", signo, miles, centimos % 100)
+}
+
+/// Formatea un importe en otra divisa como `formato_importe`, con el código de la divisa
+/// en lugar del símbolo del euro (p. ej. "1.234,56 USD")
+pub fn formato_importe_divisa(importe: f64, divisa: Divisa) -> String {
+    let cifra = formato_importe(Moneda::from_euros(importe));
+    format!("{} {}", cifra.trim_end_matches(" €"), divisa)
+}
+
+/// Interpreta un importe escrito con punto decimal ("15.50") o con coma decimal ("15,50" o "1.234,56").
+/// Se lee directamente en céntimos, sin pasar por `f64`; si tiene más de dos decimales se redondea
+pub fn leer_importe(texto: &str) -> Option<Moneda> {
+    texto.parse().ok()
+}
+
+/// Normaliza un texto para comparar nombres: lo pasa a minúsculas y quita tildes y diéresis.
+/// La ñ se conserva, porque en castellano es otra letra ("año" no es "ano")
+pub(crate) fn normalizar(texto: &str) -> String {
+    texto
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'á' | 'à' | 'ä' | 'â' => 'a',
+            'é' | 'è' | 'ë' | 'ê' => 'e',
+            'í' | 'ì' | 'ï' | 'î' => 'i',
+            'ó' | 'ò' | 'ö' | 'ô' => 'o',
+            'ú' | 'ù' | 'ü' | 'û' => 'u',
+            'ç' => 'c',
+            otro => otro,
+        })
+        .collect()
+}
+
+/// Redondea un importe a céntimos con redondeo bancario: los empates (medio céntimo exacto)
+/// van al céntimo par, de modo que el redondeo no sesga los totales hacia arriba.
+/// Se considera empate lo que queda a menos de 1e-9 céntimos del medio céntimo, para absorber
+/// el error de representación de `f64` (2.675 se guarda como 2.67499999...).
+pub fn redondear(importe: f64) -> f64 {
+    redondear_a(importe, 2)
+}
+
+/// Redondea un importe a los decimales indicados, con el mismo redondeo bancario que [`redondear`]
+pub fn redondear_a(importe: f64, decimales: u32) -> f64 {
+
+    let factor = 10f64.powi(decimales as i32);
+    let escalado = importe * factor;
+    let suelo = escalado.floor();
+
+    let redondeado = if (escalado - suelo - 0.5).abs() < 1e-9 {
+        if suelo % 2.0 == 0.0 { suelo } else { suelo + 1.0 }
+    } else {
+        escalado.round()
+    };
+
+    redondeado / factor
+}
+
+/// Nombres de los meses, empezando por enero
+const MESES: [&str; 12] = [
+    "Enero", "Febrero", "Marzo", "Abril", "Mayo", "Junio",
+    "Julio", "Agosto", "Septiembre", "Octubre", "Noviembre", "Diciembre",
+];
+
+/// Devuelve el nombre del mes (1 = enero), o una cadena vacía si el número no es un mes
+pub fn nombre_mes(mes: u32) -> &'static str {
+    match mes {
+        1..=12 => MESES[mes as usize - 1],
+        _ => "",
+    }
+}
+
+/// Una línea de un informe (balance, cuenta de resultados...): un epígrafe con su total o una cuenta con su saldo
+pub(crate) struct LineaInforme {
+    texto: String,
+    pub(crate) importe: Moneda,
+    epigrafe: bool,
+}
+
+impl LineaInforme {
+    pub(crate) fn epigrafe(texto: &str, importe: Moneda) -> LineaInforme {
+        LineaInforme { texto: texto.to_string(), importe, epigrafe: true }
+    }
+
+    pub(crate) fn cuenta(texto: String, importe: Moneda) -> LineaInforme {
+        LineaInforme { texto, importe, epigrafe: false }
+    }
+
+    /// Formatea la línea para que ocupe exactamente el ancho indicado, con puntos entre el texto y el importe
+    pub(crate) fn formatear(&self, ancho: usize) -> String {
+        let importe = formato_importe(self.importe);
+        let sangria = if self.epigrafe { "" } else { "  " };
+        let espacio_texto = ancho.saturating_sub(importe.chars().count() + sangria.len() + 1);
+        let texto: String = self.texto.chars().take(espacio_texto).collect();
+        let relleno = ancho.saturating_sub(sangria.len() + texto.chars().count() + importe.chars().count());
+        format!("{}{}{}{}", sangria, texto, ".".repeat(relleno), importe)
+    }
+}
+
+#[cfg(test)]
+mod formato_tests {
+
+    use super::*;
+
+    #[test]
+    fn nombre_mes_empieza_en_enero() {
+        assert_eq!(nombre_mes(1), "Enero");
+        assert_eq!(nombre_mes(12), "Diciembre");
+        assert_eq!(nombre_mes(13), "");
+    }
+
+    #[test]
+    fn formato_importe_separa_miles_y_usa_coma_decimal() {
+        assert_eq!(formato_importe(Moneda::CERO), "0,00 €");
+        assert_eq!(formato_importe(Moneda::from_centimos(1550)), "15,50 €");
+        assert_eq!(formato_importe(Moneda::from_centimos(123457)), "1.234,57 €");
+        assert_eq!(formato_importe(Moneda::from_centimos(23000000)), "230.000,00 €");
+        assert_eq!(formato_importe(Moneda::from_centimos(-123456700)), "-1.234.567,00 €");
+        assert_eq!(formato_importe(Moneda::from_centimos(-5)), "-0,05 €");
+    }
+
+    #[test]
+    fn formato_importe_no_muestra_cero_negativo() {
+        assert_eq!(formato_importe(Moneda::from_euros(-0.001)), "0,00 €");
+    }
+
+    #[test]
+    fn formato_importe_divisa_cambia_el_euro_por_el_codigo_de_la_divisa() {
+        assert_eq!(formato_importe_divisa(1234.56, Divisa::Usd), "1.234,56 USD");
+        assert_eq!(formato_importe_divisa(-0.5, Divisa::Gbp), "-0,50 GBP");
+    }
+
+    #[test]
+    fn leer_importe_acepta_punto_y_coma_decimales() {
+        assert_eq!(leer_importe("15.50"), Some(Moneda::from_centimos(1550)));
+        assert_eq!(leer_importe("15,50"), Some(Moneda::from_centimos(1550)));
+        assert_eq!(leer_importe("1.234,56"), Some(Moneda::from_centimos(123456)));
+        assert_eq!(leer_importe("3332"), Some(Moneda::from_centimos(333200)));
+        assert_eq!(leer_importe("0,125"), Some(Moneda::from_centimos(12)));
+        assert_eq!(leer_importe("abc"), None);
+    }
+
+    #[test]
+    fn normalizar_quita_tildes_y_mayusculas_pero_conserva_la_enie() {
+        assert_eq!(normalizar("José"), normalizar("jose"));
+        assert_eq!(normalizar("Télefono"), "telefono");
+        assert_eq!(normalizar("PINGÜINO"), "pinguino");
+        assert_eq!(normalizar("Año"), "año");
+        assert_ne!(normalizar("año"), normalizar("ano"));
+    }
+
+    #[test]
+    fn redondear_usa_redondeo_bancario() {
+        assert_eq!(redondear(0.125), 0.12);
+        assert_eq!(redondear(0.135), 0.14);
+        assert_eq!(redondear(2.675), 2.68);
+        assert_eq!(redondear(-0.125), -0.12);
+        assert_eq!(redondear(20.000000001), 20.0);
+        assert_eq!(redondear(10.0 / 3.0), 3.33);
+        assert_eq!(redondear_a(0.12345, 4), 0.1234);
+        assert_eq!(redondear_a(2.5, 0), 2.0);
+    }
+}
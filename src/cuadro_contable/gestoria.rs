@@ -0,0 +1,182 @@
+use std::fs;
+
+use super::{Cuadro, LibroDiario};
+use super::asiento::Asiento;
+use super::moneda::Moneda;
+use super::movimiento::Movimiento;
+
+/// Cabecera del formato delimitado
+const CABECERA_DELIMITADO: &str = "asiento;fecha;cuenta;nombre_cuenta;concepto;debe;haber;justificante";
+
+/// Formatos de importación de apuntes que admiten los programas de contabilidad de las gestorías.
+/// En ambos hay una línea por apunte (cada movimiento del debe o del haber de un asiento), los asientos
+/// van en orden cronológico y se numeran correlativamente desde 1, como esperan esos programas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FormatoGestoria {
+    /// Texto delimitado por punto y coma, con una primera línea de cabecera:
+    ///
+    /// `asiento;fecha;cuenta;nombre_cuenta;concepto;debe;haber;justificante`
+    ///
+    /// - `asiento`: número correlativo del asiento
+    /// - `fecha`: `DD/MM/AAAA`
+    /// - `cuenta` y `nombre_cuenta`: código y nombre de la cuenta en el cuadro
+    /// - `concepto`: el del asiento en una sola línea, sin puntos y coma
+    /// - `debe` y `haber`: importe con coma decimal y sin separador de miles; el lado contrario va a `0,00`
+    /// - `justificante`: el del asiento, o vacío
+    Delimitado,
+    /// Registros de ancho fijo de 93 caracteres, sin cabecera. Posiciones, empezando en 1:
+    ///
+    /// | Campo        | Posición | Ancho | Alineación                          |
+    /// |--------------|----------|-------|-------------------------------------|
+    /// | asiento      | 1-6      | 6     | derecha, con ceros                  |
+    /// | fecha        | 7-14     | 8     | `AAAAMMDD`                          |
+    /// | cuenta       | 15-26    | 12    | izquierda, con espacios             |
+    /// | concepto     | 27-51    | 25    | izquierda, recortado si no cabe     |
+    /// | debe         | 52-67    | 16    | derecha, punto decimal y 2 decimales |
+    /// | haber        | 68-83    | 16    | derecha, punto decimal y 2 decimales |
+    /// | justificante | 84-93    | 10    | izquierda, recortado si no cabe     |
+    AnchoFijo,
+}
+
+/// Ajusta un texto a un ancho fijo, recortándolo o rellenándolo con espacios por la derecha
+fn ajustar(texto: &str, ancho: usize) -> String {
+    format!("{:<ancho$}", texto.chars().take(ancho).collect::<String>())
+}
+
+/// Concepto del asiento en una sola línea
+fn concepto_en_linea(asiento: &Asiento) -> String {
+    asiento.concepto().lines().collect::<Vec<&str>>().join(" ")
+}
+
+/// Importe con coma decimal y sin separador de miles
+fn importe_delimitado(importe: Moneda) -> String {
+    format!("{:.2}", importe.as_euros()).replace('.', ",")
+}
+
+impl Cuadro {
+
+    /// Genera el texto de los apuntes del libro diario en el formato de importación de la gestoría.
+    /// Los nombres de cuenta son los actuales del cuadro
+    pub fn texto_gestoria(&self, libro_diario: &LibroDiario, formato: FormatoGestoria) -> String {
+
+        let mut texto = String::new();
+        if formato == FormatoGestoria::Delimitado {
+            texto.push_str(CABECERA_DELIMITADO);
+            texto.push('\n');
+        }
+
+        for (numero, asiento) in libro_diario.asientos_ordenados().into_iter().enumerate() {
+            let apuntes = asiento.debe().iter().map(|m| (m, m.importe(), Moneda::CERO))
+                .chain(asiento.haber().iter().map(|m| (m, Moneda::CERO, m.importe())));
+
+            for (movimiento, debe, haber) in apuntes {
+                let linea = match formato {
+                    FormatoGestoria::Delimitado => self.linea_delimitada(numero + 1, asiento, movimiento, debe, haber),
+                    FormatoGestoria::AnchoFijo => linea_ancho_fijo(numero + 1, asiento, movimiento, debe, haber),
+                };
+                texto.push_str(&linea);
+                texto.push('\n');
+            }
+        }
+
+        texto
+    }
+
+    /// Exporta los apuntes del libro diario a un archivo que la gestoría pueda importar.
+    /// El layout de cada formato se detalla en [`FormatoGestoria`]
+    pub fn exportar_gestoria(&self, libro_diario: &LibroDiario, path: &str, formato: FormatoGestoria) -> std::io::Result<()> {
+        fs::write(path, self.texto_gestoria(libro_diario, formato))
+    }
+
+    fn linea_delimitada(&self, numero: usize, asiento: &Asiento, movimiento: &Movimiento, debe: Moneda, haber: Moneda) -> String {
+        let codigo = movimiento.codigo_cuenta();
+        let nombre = self.cuentas()
+            .find(|c| c.codigo() == codigo)
+            .map_or(movimiento.nombre_cuenta(), |c| c.nombre());
+
+        format!(
+            "{};{};{};{};{};{};{};{}",
+            numero,
+            asiento.fecha().format("%d/%m/%Y"),
+            codigo,
+            nombre.replace(';', ","),
+            concepto_en_linea(asiento).replace(';', ","),
+            importe_delimitado(debe),
+            importe_delimitado(haber),
+            asiento.justificante().unwrap_or("").replace(';', ","),
+        )
+    }
+}
+
+fn linea_ancho_fijo(numero: usize, asiento: &Asiento, movimiento: &Movimiento, debe: Moneda, haber: Moneda) -> String {
+    format!(
+        "{:06}{}{}{}{:>16.2}{:>16.2}{}",
+        numero,
+        asiento.fecha().format("%Y%m%d"),
+        ajustar(&movimiento.codigo_cuenta(), 12),
+        ajustar(&concepto_en_linea(asiento), 25),
+        debe.as_euros(),
+        haber.as_euros(),
+        ajustar(asiento.justificante().unwrap_or(""), 10),
+    )
+}
+
+#[cfg(test)]
+mod gestoria_tests {
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup() -> (Cuadro, LibroDiario) {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("IVA soportado", "472", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+
+        let fecha = |d| NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Compra de material;\nfactura de marzo", Some(fecha(5)), vec![("600", Moneda::from_euros(1000.0)), ("472", Moneda::from_euros(210.0))], vec![("572", Moneda::from_euros(1210.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Aportación", Some(fecha(1)), vec![("572", Moneda::from_euros(5000.0))], vec![("100", Moneda::from_euros(5000.0))], &mut cuadro).unwrap();
+        (cuadro, libro_diario)
+    }
+
+    #[test]
+    fn texto_gestoria_delimitado_pone_un_apunte_por_linea_en_orden_cronologico() {
+        let (cuadro, libro_diario) = setup();
+
+        let texto = cuadro.texto_gestoria(&libro_diario, FormatoGestoria::Delimitado);
+        let lineas: Vec<&str> = texto.lines().collect();
+
+        assert_eq!(lineas, vec![
+            CABECERA_DELIMITADO,
+            "1;01/03/2024;572;Bancos;Aportación;5000,00;0,00;",
+            "1;01/03/2024;100;Capital;Aportación;0,00;5000,00;",
+            "2;05/03/2024;600;Compras;Compra de material, factura de marzo;1000,00;0,00;",
+            "2;05/03/2024;472;IVA soportado;Compra de material, factura de marzo;210,00;0,00;",
+            "2;05/03/2024;572;Bancos;Compra de material, factura de marzo;0,00;1210,00;",
+        ]);
+    }
+
+    #[test]
+    fn texto_gestoria_ancho_fijo_respeta_las_posiciones() {
+        let (cuadro, libro_diario) = setup();
+
+        let texto = cuadro.texto_gestoria(&libro_diario, FormatoGestoria::AnchoFijo);
+        let lineas: Vec<&str> = texto.lines().collect();
+
+        assert_eq!(lineas.len(), 5);
+        assert!(lineas.iter().all(|l| l.chars().count() == 93));
+
+        let campo = |linea: &str, inicio: usize, fin: usize| linea.chars().skip(inicio - 1).take(fin - inicio + 1).collect::<String>();
+        let linea = lineas[4];
+        assert_eq!(campo(linea, 1, 6), "000002");
+        assert_eq!(campo(linea, 7, 14), "20240305");
+        assert_eq!(campo(linea, 15, 26), "572         ");
+        assert_eq!(campo(linea, 27, 51), "Compra de material; factu");
+        assert_eq!(campo(linea, 52, 67).trim(), "0.00");
+        assert_eq!(campo(linea, 68, 83).trim(), "1210.00");
+    }
+}
@@ -0,0 +1,154 @@
+use chrono::NaiveDate;
+
+use super::{Cuadro, LibroDiario, LibroDiarioError};
+use super::moneda::Moneda;
+
+/// Cuenta de gasto por impuesto sobre beneficios
+const CUENTA_GASTO_IMPUESTO: &str = "630";
+/// Hacienda Pública, acreedora por impuesto sobre sociedades
+const CUENTA_HACIENDA_IMPUESTO: &str = "4752";
+/// Prefijos de las cuentas del epígrafe de impuestos sobre beneficios, que no forman parte de la base
+const PREFIJOS_IMPUESTO: [&str; 3] = ["630", "633", "638"];
+
+impl Cuadro {
+
+    /// Devuelve el resultado antes de impuestos: el resultado provisional sin las cuentas de impuestos sobre beneficios
+    pub fn resultado_antes_de_impuestos(&self) -> Moneda {
+        let impuestos: Moneda = self.cuentas()
+            .filter(|c| PREFIJOS_IMPUESTO.iter().any(|p| c.codigo().starts_with(p)))
+            .map(|c| c.saldo())
+            .sum();
+        self.resultado_provisional() + impuestos
+    }
+
+    /// Calcula el impuesto sobre beneficios del ejercicio aplicando el tipo (sobre 100) al resultado antes de impuestos.
+    /// Si hay pérdidas, el impuesto es cero. El resultado se redondea a céntimos
+    pub fn calcular_impuesto(&self, tipo: f64) -> Moneda {
+        let base = self.resultado_antes_de_impuestos();
+        if base <= Moneda::CERO {
+            return Moneda::CERO;
+        }
+        Moneda::from_euros(base.as_euros() * tipo / 100.0)
+    }
+}
+
+impl LibroDiario {
+
+    /// Contabiliza el impuesto sobre beneficios: cargo en la 630 y abono en la 4752 por lo que resulte de
+    /// [`Cuadro::calcular_impuesto`], descontando lo que ya se haya cargado en la 630. Así, volver a
+    /// llamarlo tras añadir asientos solo anota la diferencia. Si el impuesto ha bajado, la diferencia se anota
+    /// al revés (cargo en la 4752 y abono en la 630). Es el último paso antes de regularizar y cerrar.
+    pub fn contabilizar_impuesto(&mut self, tipo: f64, fecha: Option<NaiveDate>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+
+        // Sin la 630 no hay nada contabilizado; si hay impuesto, el asiento señalará la línea que falta
+        let contabilizado = cuadro.buscar_cuenta(CUENTA_GASTO_IMPUESTO)
+            .map(|c| c.saldo())
+            .unwrap_or(Moneda::CERO);
+        let pendiente = cuadro.calcular_impuesto(tipo) - contabilizado;
+        if pendiente == Moneda::CERO {
+            return Ok(());
+        }
+
+        // Los importes negativos solo caben en una rectificativa, así que el exceso se anota cambiando de lado
+        let (debe, haber) = match pendiente > Moneda::CERO {
+            true => (CUENTA_GASTO_IMPUESTO, CUENTA_HACIENDA_IMPUESTO),
+            false => (CUENTA_HACIENDA_IMPUESTO, CUENTA_GASTO_IMPUESTO),
+        };
+
+        self.insertar_asiento(
+            "Impuesto sobre beneficios del ejercicio",
+            fecha,
+            vec![(debe, pendiente.abs())],
+            vec![(haber, pendiente.abs())],
+            cuadro,
+        )
+    }
+}
+
+#[cfg(test)]
+mod impuesto_tests {
+
+    use super::*;
+    use crate::cuadro_contable::apunte::Lado;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup(ventas: f64, compras: f64) -> (Cuadro, LibroDiario) {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Impuesto sobre beneficios", "630", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("HP acreedora por impuesto sobre sociedades", "4752", Masa::PasivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Ventas", None, vec![("572", Moneda::from_euros(ventas))], vec![("700", Moneda::from_euros(ventas))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compras", None, vec![("600", Moneda::from_euros(compras))], vec![("572", Moneda::from_euros(compras))], &mut cuadro).unwrap();
+        (cuadro, libro_diario)
+    }
+
+    #[test]
+    fn calcular_impuesto_aplica_el_tipo_al_beneficio() {
+        let (cuadro, _) = setup(1000.0, 400.0);
+
+        assert_eq!(cuadro.calcular_impuesto(25.0), Moneda::from_euros(150.0));
+        assert_eq!(cuadro.calcular_impuesto(23.0), Moneda::from_euros(138.0));
+    }
+
+    #[test]
+    fn calcular_impuesto_es_cero_con_perdidas() {
+        let (cuadro, _) = setup(100.0, 400.0);
+
+        assert_eq!(cuadro.calcular_impuesto(25.0), Moneda::from_euros(0.0));
+    }
+
+    #[test]
+    fn contabilizar_impuesto_carga_la_630_y_solo_anota_la_diferencia() {
+        let (mut cuadro, mut libro_diario) = setup(1000.0, 400.0);
+
+        libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro).unwrap();
+        assert_eq!(cuadro.buscar_cuenta("630").unwrap().saldo(), Moneda::from_euros(150.0));
+        assert_eq!(cuadro.buscar_cuenta("4752").unwrap().saldo(), Moneda::from_euros(-150.0));
+        assert_eq!(cuadro.resultado_provisional(), Moneda::from_euros(450.0));
+        assert_eq!(cuadro.resultado_antes_de_impuestos(), Moneda::from_euros(600.0));
+
+        // Sin cambios no anota nada; con más beneficio, solo la diferencia
+        libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro).unwrap();
+        assert_eq!(libro_diario.asientos().count(), 3);
+        libro_diario.insertar_asiento("Ventas", None, vec![("572", Moneda::from_euros(200.0))], vec![("700", Moneda::from_euros(200.0))], &mut cuadro).unwrap();
+        libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro).unwrap();
+        assert_eq!(libro_diario.asientos().last().unwrap().total_debe(), Moneda::from_euros(50.0));
+        assert_eq!(cuadro.buscar_cuenta("4752").unwrap().saldo(), Moneda::from_euros(-200.0));
+    }
+
+    #[test]
+    fn contabilizar_impuesto_anota_al_reves_si_el_impuesto_baja() {
+        let (mut cuadro, mut libro_diario) = setup(1000.0, 400.0);
+        libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro).unwrap();
+
+        // Una compra más reduce el beneficio a 400 y el impuesto a 100
+        libro_diario.insertar_asiento("Compras", None, vec![("600", Moneda::from_euros(200.0))], vec![("572", Moneda::from_euros(200.0))], &mut cuadro).unwrap();
+        libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro).unwrap();
+
+        let ajuste = libro_diario.asientos().last().unwrap();
+        assert_eq!(ajuste.debe()[0].codigo_cuenta(), "4752");
+        assert_eq!(ajuste.haber()[0].codigo_cuenta(), "630");
+        assert_eq!(ajuste.total_debe(), Moneda::from_euros(50.0));
+        assert_eq!(cuadro.buscar_cuenta("630").unwrap().saldo(), Moneda::from_euros(100.0));
+        assert_eq!(cuadro.buscar_cuenta("4752").unwrap().saldo(), Moneda::from_euros(-100.0));
+    }
+
+    #[test]
+    fn contabilizar_impuesto_sin_la_630_senala_la_linea() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("HP acreedora por impuesto sobre sociedades", "4752", Masa::PasivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Ventas", None, vec![("572", Moneda::from_euros(1000.0))], vec![("700", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+
+        assert_eq!(
+            libro_diario.contabilizar_impuesto(25.0, None, &mut cuadro),
+            Err(LibroDiarioError::CuentaInexistenteEnLinea(Lado::Debe, 1, "630".to_string()))
+        );
+        assert_eq!(libro_diario.asientos().count(), 1);
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::Display;
+
+use super::{Cuadro, LibroDiario};
+use super::apunte::Lado;
+use super::formato::formato_importe;
+use super::moneda::Moneda;
+
+/// Un problema encontrado al comprobar la integridad de la contabilidad
+#[derive(Debug, PartialEq)]
+pub enum ProblemaIntegridad {
+    /// Un movimiento del asiento se refiere a una cuenta que no está en el cuadro
+    CuentaInexistente { asiento: String, cuenta: String },
+    /// El debe y el haber de un asiento ya insertado no coinciden
+    AsientoDescuadrado { asiento: String, diferencia: Moneda },
+    /// El saldo de una cuenta no es la suma de sus apuntes
+    SaldoIncoherente { cuenta: String, saldo: Moneda, suma_apuntes: Moneda },
+}
+
+impl Display for ProblemaIntegridad {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProblemaIntegridad::CuentaInexistente { asiento, cuenta } =>
+                write!(f, "El asiento {} usa la cuenta '{}', que no existe", asiento, cuenta),
+            ProblemaIntegridad::AsientoDescuadrado { asiento, diferencia } =>
+                write!(f, "El asiento {} no cuadra (diferencia: {})", asiento, formato_importe(*diferencia)),
+            ProblemaIntegridad::SaldoIncoherente { cuenta, saldo, suma_apuntes } =>
+                write!(f, "La cuenta {} tiene un saldo de {} pero sus apuntes suman {}", cuenta, formato_importe(*saldo), formato_importe(*suma_apuntes)),
+        }
+    }
+}
+
+impl Cuadro {
+
+    /// Diagnostica la contabilidad sin reparar nada: movimientos sobre cuentas inexistentes,
+    /// asientos descuadrados y saldos que no coinciden con la suma de los apuntes de su cuenta.
+    /// Útil tras importaciones o migraciones; para reparar los saldos, ver `recalcular_saldos`.
+    pub fn verificar_integridad(&self, libro_diario: &LibroDiario) -> Vec<ProblemaIntegridad> {
+
+        let mut problemas = vec![];
+
+        for asiento in &libro_diario.asientos {
+            for movimiento in asiento.debe().iter().chain(asiento.haber()) {
+                let codigo = movimiento.codigo_cuenta();
+                if !self.cuentas.iter().any(|c| c.codigo() == codigo) {
+                    problemas.push(ProblemaIntegridad::CuentaInexistente { asiento: asiento.codigo(), cuenta: codigo });
+                }
+            }
+
+            let diferencia = asiento.comprobacion();
+            if diferencia != Moneda::CERO {
+                problemas.push(ProblemaIntegridad::AsientoDescuadrado { asiento: asiento.codigo(), diferencia });
+            }
+        }
+
+        for cuenta in &self.cuentas {
+            let suma_apuntes: Moneda = cuenta.apuntes().iter()
+                .map(|a| match a.lado() {
+                    Lado::Debe => a.importe(),
+                    Lado::Haber => -a.importe(),
+                })
+                .sum();
+            if cuenta.saldo() != suma_apuntes {
+                problemas.push(ProblemaIntegridad::SaldoIncoherente { cuenta: cuenta.codigo(), saldo: cuenta.saldo(), suma_apuntes });
+            }
+        }
+
+        problemas
+    }
+}
+
+#[cfg(test)]
+mod integridad_tests {
+
+    use super::*;
+    use crate::cuadro_contable::asiento::Asiento;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup() -> (Cuadro, LibroDiario) {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        (cuadro, libro_diario)
+    }
+
+    #[test]
+    fn verificar_integridad_no_encuentra_problemas_en_una_contabilidad_sana() {
+        let (cuadro, libro_diario) = setup();
+
+        assert!(cuadro.verificar_integridad(&libro_diario).is_empty());
+    }
+
+    #[test]
+    fn verificar_integridad_detecta_cuentas_inexistentes_y_saldos_incoherentes() {
+        let (mut cuadro, libro_diario) = setup();
+        cuadro.buscar_cuenta("572").unwrap().saldo_deudor(Moneda::from_euros(50.0));
+
+        // El libro diario se comprueba contra un cuadro que no tiene sus cuentas
+        let vacio = Cuadro::new();
+        let problemas = vacio.verificar_integridad(&libro_diario);
+        assert_eq!(problemas.len(), 2);
+        assert!(matches!(&problemas[0], ProblemaIntegridad::CuentaInexistente { cuenta, .. } if cuenta == "572"));
+
+        let problemas = cuadro.verificar_integridad(&libro_diario);
+        assert_eq!(problemas, vec![ProblemaIntegridad::SaldoIncoherente {
+            cuenta: "572".to_string(),
+            saldo: Moneda::from_euros(1050.0),
+            suma_apuntes: Moneda::from_euros(1000.0),
+        }]);
+        assert_eq!(problemas[0].to_string(), "La cuenta 572 tiene un saldo de 1.050,00 € pero sus apuntes suman 1.000,00 €");
+    }
+
+    #[test]
+    fn verificar_integridad_detecta_asientos_descuadrados() {
+        let (mut cuadro, mut libro_diario) = setup();
+        let bancos = cuadro.buscar_cuenta("572").unwrap();
+        let movimiento = crate::cuadro_contable::movimiento::Movimiento::new(Moneda::from_euros(10.0), bancos);
+        let mut asiento = Asiento::new("Descuadrado", None, vec![movimiento], vec![]);
+        asiento.numerar("X");
+        libro_diario.asientos.push(asiento);
+
+        assert_eq!(
+            cuadro.verificar_integridad(&libro_diario),
+            vec![ProblemaIntegridad::AsientoDescuadrado { asiento: "X".to_string(), diferencia: Moneda::from_euros(10.0) }]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+
+use chrono::NaiveDate;
+
+use super::Cuadro;
+use super::apunte::Lado;
+use super::formato::formato_importe;
+use super::moneda::Moneda;
+
+/// Cuenta del IVA repercutido en las ventas
+const IVA_REPERCUTIDO: &str = "477";
+/// Cuenta del IVA soportado en las compras
+const IVA_SOPORTADO: &str = "472";
+
+/// IVA repercutido y soportado de un periodo, como el que se declara en el modelo 303
+#[derive(Debug, PartialEq)]
+pub struct ResumenIva {
+    /// Cuotas repercutidas a los clientes (cuenta 477 y sus subcuentas)
+    pub repercutido: Moneda,
+    /// Cuotas soportadas en las compras (cuenta 472 y sus subcuentas)
+    pub soportado: Moneda,
+    /// Repercutido menos soportado: positivo si sale a pagar y negativo si sale a compensar
+    pub diferencia: Moneda,
+    /// Repercutido por cada cuenta con movimientos en el periodo, para separar por tipos
+    /// si se llevan subcuentas por tipo (4770021, 4770010...)
+    pub repercutido_por_cuenta: Vec<(String, Moneda)>,
+    /// Soportado por cada cuenta con movimientos en el periodo
+    pub soportado_por_cuenta: Vec<(String, Moneda)>,
+}
+
+impl ResumenIva {
+    /// Indica si la liquidación sale a pagar; si no, la diferencia queda a compensar
+    pub fn a_pagar(&self) -> bool {
+        self.diferencia > Moneda::CERO
+    }
+}
+
+impl Display for ResumenIva {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "IVA repercutido: {}", formato_importe(self.repercutido))?;
+        for (cuenta, importe) in &self.repercutido_por_cuenta {
+            writeln!(f, "  ({}) {}", cuenta, formato_importe(*importe))?;
+        }
+        writeln!(f, "IVA soportado: {}", formato_importe(self.soportado))?;
+        for (cuenta, importe) in &self.soportado_por_cuenta {
+            writeln!(f, "  ({}) {}", cuenta, formato_importe(*importe))?;
+        }
+        let resultado = if self.a_pagar() { "a pagar" } else { "a compensar" };
+        writeln!(f, "Resultado ({}): {}", resultado, formato_importe(self.diferencia.abs()))
+    }
+}
+
+impl Cuadro {
+
+    /// Resume el IVA repercutido (477) y soportado (472) de los apuntes entre dos fechas, ambas incluidas.
+    /// Las devoluciones y rectificativas restan de su lado, de modo que el resultado es el neto del periodo
+    pub fn resumen_iva(&self, inicio: NaiveDate, fin: NaiveDate) -> ResumenIva {
+
+        // Neto de cada cuenta del prefijo en el periodo, en positivo para su lado natural
+        let por_cuenta = |prefijo: &str, lado_natural: Lado| -> Vec<(String, Moneda)> {
+            self.cuentas.iter()
+                .filter(|c| c.codigo().starts_with(prefijo))
+                .filter_map(|c| {
+                    let apuntes: Vec<_> = c.apuntes().iter().filter(|a| a.fecha() >= inicio && a.fecha() <= fin).collect();
+                    if apuntes.is_empty() {
+                        return None;
+                    }
+                    let neto = apuntes.iter()
+                        .map(|a| if a.lado() == lado_natural { a.importe() } else { -a.importe() })
+                        .sum::<Moneda>();
+                    Some((c.codigo(), neto))
+                })
+                .collect()
+        };
+
+        let repercutido_por_cuenta = por_cuenta(IVA_REPERCUTIDO, Lado::Haber);
+        let soportado_por_cuenta = por_cuenta(IVA_SOPORTADO, Lado::Debe);
+        let repercutido: Moneda = repercutido_por_cuenta.iter().map(|(_, i)| *i).sum();
+        let soportado: Moneda = soportado_por_cuenta.iter().map(|(_, i)| *i).sum();
+
+        ResumenIva {
+            repercutido,
+            soportado,
+            diferencia: repercutido - soportado,
+            repercutido_por_cuenta,
+            soportado_por_cuenta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod iva_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn fecha(mes: u32, dia: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, mes, dia).unwrap()
+    }
+
+    fn setup() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("IVA repercutido 21%", "4770021", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("IVA repercutido 10%", "4770010", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("IVA soportado", "472", Masa::ActivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Venta", Some(fecha(1, 15)), vec![("572", Moneda::from_euros(121.0))], vec![("700", Moneda::from_euros(100.0)), ("4770021", Moneda::from_euros(21.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(2, 10)), vec![("572", Moneda::from_euros(220.0))], vec![("700", Moneda::from_euros(200.0)), ("4770010", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", Some(fecha(3, 1)), vec![("600", Moneda::from_euros(50.0)), ("472", Moneda::from_euros(10.5))], vec![("572", Moneda::from_euros(60.5))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(4, 2)), vec![("572", Moneda::from_euros(121.0))], vec![("700", Moneda::from_euros(100.0)), ("4770021", Moneda::from_euros(21.0))], &mut cuadro).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn resumen_iva_suma_el_periodo_y_separa_por_subcuenta() {
+        let resumen = setup().resumen_iva(fecha(1, 1), fecha(3, 31));
+
+        assert_eq!(resumen.repercutido, Moneda::from_euros(41.0));
+        assert_eq!(resumen.soportado, Moneda::from_euros(10.5));
+        assert_eq!(resumen.diferencia, Moneda::from_euros(30.5));
+        assert!(resumen.a_pagar());
+        assert_eq!(resumen.repercutido_por_cuenta, vec![("4770021".to_string(), Moneda::from_euros(21.0)), ("4770010".to_string(), Moneda::from_euros(20.0))]);
+        assert_eq!(resumen.soportado_por_cuenta, vec![("472".to_string(), Moneda::from_euros(10.5))]);
+        assert!(resumen.to_string().contains("Resultado (a pagar): 30,50 €"));
+    }
+
+    #[test]
+    fn resumen_iva_sin_ventas_queda_a_compensar() {
+        let resumen = setup().resumen_iva(fecha(3, 1), fecha(3, 31));
+
+        assert_eq!(resumen.repercutido, Moneda::from_euros(0.0));
+        assert!(resumen.repercutido_por_cuenta.is_empty());
+        assert_eq!(resumen.diferencia, Moneda::from_euros(-10.5));
+        assert!(!resumen.a_pagar());
+        assert!(resumen.to_string().contains("Resultado (a compensar): 10,50 €"));
+    }
+}
@@ -0,0 +1,297 @@
+use std::fmt::Display;
+
+/// Valor JSON mínimo, suficiente para las copias de seguridad del ejercicio.
+/// Los objetos conservan el orden de sus campos, para que el archivo generado sea estable.
+#[derive(Debug, PartialEq, Clone)]
+pub(crate) enum Json {
+    Nulo,
+    Booleano(bool),
+    Numero(f64),
+    Texto(String),
+    Lista(Vec<Json>),
+    Objeto(Vec<(String, Json)>),
+}
+
+impl Json {
+
+    /// Crea un objeto a partir de sus campos
+    pub(crate) fn objeto(campos: Vec<(&str, Json)>) -> Json {
+        Json::Objeto(campos.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+    }
+
+    /// Texto o nulo, según haya valor
+    pub(crate) fn texto_opcional(texto: Option<&str>) -> Json {
+        texto.map_or(Json::Nulo, |t| Json::Texto(t.to_string()))
+    }
+
+    /// Devuelve el campo de un objeto, si lo es y lo tiene
+    pub(crate) fn campo(&self, nombre: &str) -> Option<&Json> {
+        match self {
+            Json::Objeto(campos) => campos.iter().find(|(k, _)| k == nombre).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn como_texto(&self) -> Option<&str> {
+        match self {
+            Json::Texto(t) => Some(t),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn como_numero(&self) -> Option<f64> {
+        match self {
+            Json::Numero(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn como_booleano(&self) -> Option<bool> {
+        match self {
+            Json::Booleano(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn como_lista(&self) -> Option<&[Json]> {
+        match self {
+            Json::Lista(l) => Some(l),
+            _ => None,
+        }
+    }
+
+    /// Escribe el valor con sangría de dos espacios por nivel
+    fn escribir(&self, f: &mut std::fmt::Formatter<'_>, nivel: usize) -> std::fmt::Result {
+        let sangria = "  ".repeat(nivel + 1);
+        let cierre = "  ".repeat(nivel);
+        match self {
+            Json::Nulo => write!(f, "null"),
+            Json::Booleano(b) => write!(f, "{}", b),
+            Json::Numero(n) => write!(f, "{}", n),
+            Json::Texto(t) => escribir_texto(f, t),
+            Json::Lista(l) if l.is_empty() => write!(f, "[]"),
+            Json::Lista(l) => {
+                writeln!(f, "[")?;
+                for (i, valor) in l.iter().enumerate() {
+                    write!(f, "{}", sangria)?;
+                    valor.escribir(f, nivel + 1)?;
+                    writeln!(f, "{}", if i + 1 < l.len() { "," } else { "" })?;
+                }
+                write!(f, "{}]", cierre)
+            },
+            Json::Objeto(campos) if campos.is_empty() => write!(f, "{{}}"),
+            Json::Objeto(campos) => {
+                writeln!(f, "{{")?;
+                for (i, (clave, valor)) in campos.iter().enumerate() {
+                    write!(f, "{}", sangria)?;
+                    escribir_texto(f, clave)?;
+                    write!(f, ": ")?;
+                    valor.escribir(f, nivel + 1)?;
+                    writeln!(f, "{}", if i + 1 < campos.len() { "," } else { "" })?;
+                }
+                write!(f, "{}}}", cierre)
+            },
+        }
+    }
+}
+
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.escribir(f, 0)
+    }
+}
+
+/// Escribe una cadena entre comillas, escapando los caracteres que lo necesitan
+fn escribir_texto(f: &mut std::fmt::Formatter<'_>, texto: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for c in texto.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// Interpreta un texto JSON completo. Devuelve el motivo si no es válido
+pub(crate) fn interpretar(texto: &str) -> Result<Json, String> {
+    let mut lector = Lector { caracteres: texto.chars().collect(), posicion: 0 };
+    let valor = lector.valor()?;
+    lector.saltar_espacios();
+    if lector.posicion < lector.caracteres.len() {
+        return Err(format!("sobra texto en la posición {}", lector.posicion));
+    }
+    Ok(valor)
+}
+
+/// Analizador descendente recursivo sobre los caracteres del texto
+struct Lector {
+    caracteres: Vec<char>,
+    posicion: usize,
+}
+
+impl Lector {
+
+    fn saltar_espacios(&mut self) {
+        while self.caracteres.get(self.posicion).is_some_and(|c| c.is_whitespace()) {
+            self.posicion += 1;
+        }
+    }
+
+    fn siguiente(&mut self) -> Option<char> {
+        let c = self.caracteres.get(self.posicion).copied();
+        self.posicion += 1;
+        c
+    }
+
+    fn esperar(&mut self, esperado: char) -> Result<(), String> {
+        self.saltar_espacios();
+        match self.siguiente() {
+            Some(c) if c == esperado => Ok(()),
+            _ => Err(format!("se esperaba '{}' en la posición {}", esperado, self.posicion - 1)),
+        }
+    }
+
+    fn palabra(&mut self, palabra: &str, valor: Json) -> Result<Json, String> {
+        let fin = self.posicion + palabra.chars().count();
+        if self.caracteres.get(self.posicion..fin).is_some_and(|c| c.iter().copied().eq(palabra.chars())) {
+            self.posicion = fin;
+            Ok(valor)
+        } else {
+            Err(format!("valor no válido en la posición {}", self.posicion))
+        }
+    }
+
+    fn valor(&mut self) -> Result<Json, String> {
+        self.saltar_espacios();
+        match self.caracteres.get(self.posicion) {
+            Some('{') => self.objeto(),
+            Some('[') => self.lista(),
+            Some('"') => self.texto().map(Json::Texto),
+            Some('t') => self.palabra("true", Json::Booleano(true)),
+            Some('f') => self.palabra("false", Json::Booleano(false)),
+            Some('n') => self.palabra("null", Json::Nulo),
+            Some(_) => self.numero(),
+            None => Err("el texto termina antes de tiempo".to_string()),
+        }
+    }
+
+    fn objeto(&mut self) -> Result<Json, String> {
+        self.esperar('{')?;
+        let mut campos = vec![];
+        self.saltar_espacios();
+        if self.caracteres.get(self.posicion) == Some(&'}') {
+            self.posicion += 1;
+            return Ok(Json::Objeto(campos));
+        }
+        loop {
+            self.saltar_espacios();
+            let clave = self.texto()?;
+            self.esperar(':')?;
+            campos.push((clave, self.valor()?));
+            self.saltar_espacios();
+            match self.siguiente() {
+                Some(',') => continue,
+                Some('}') => return Ok(Json::Objeto(campos)),
+                _ => return Err(format!("se esperaba ',' o '}}' en la posición {}", self.posicion - 1)),
+            }
+        }
+    }
+
+    fn lista(&mut self) -> Result<Json, String> {
+        self.esperar('[')?;
+        let mut valores = vec![];
+        self.saltar_espacios();
+        if self.caracteres.get(self.posicion) == Some(&']') {
+            self.posicion += 1;
+            return Ok(Json::Lista(valores));
+        }
+        loop {
+            valores.push(self.valor()?);
+            self.saltar_espacios();
+            match self.siguiente() {
+                Some(',') => continue,
+                Some(']') => return Ok(Json::Lista(valores)),
+                _ => return Err(format!("se esperaba ',' o ']' en la posición {}", self.posicion - 1)),
+            }
+        }
+    }
+
+    fn texto(&mut self) -> Result<String, String> {
+        self.esperar('"')?;
+        let mut texto = String::new();
+        loop {
+            match self.siguiente() {
+                Some('"') => return Ok(texto),
+                Some('\\') => match self.siguiente() {
+                    Some('n') => texto.push('\n'),
+                    Some('r') => texto.push('\r'),
+                    Some('t') => texto.push('\t'),
+                    Some('b') => texto.push('\u{8}'),
+                    Some('f') => texto.push('\u{c}'),
+                    Some('u') => {
+                        let codigo: String = (0..4).filter_map(|_| self.siguiente()).collect();
+                        let c = u32::from_str_radix(&codigo, 16).ok().and_then(char::from_u32)
+                            .ok_or(format!("escape unicode no válido: '{}'", codigo))?;
+                        texto.push(c);
+                    },
+                    Some(c) => texto.push(c),
+                    None => return Err("cadena sin terminar".to_string()),
+                },
+                Some(c) => texto.push(c),
+                None => return Err("cadena sin terminar".to_string()),
+            }
+        }
+    }
+
+    fn numero(&mut self) -> Result<Json, String> {
+        let inicio = self.posicion;
+        while self.caracteres.get(self.posicion).is_some_and(|c| c.is_ascii_digit() || "+-.eE".contains(*c)) {
+            self.posicion += 1;
+        }
+        let numero: String = self.caracteres[inicio..self.posicion].iter().collect();
+        numero.parse().map(Json::Numero).map_err(|_| format!("valor no válido en la posición {}", inicio))
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+
+    use super::*;
+
+    #[test]
+    fn escribir_e_interpretar_son_inversos() {
+        let valor = Json::objeto(vec![
+            ("version", Json::Numero(1.0)),
+            ("concepto", Json::Texto("Compra \"urgente\"\nlínea 2 \\ fin".to_string())),
+            ("importes", Json::Lista(vec![Json::Numero(0.1), Json::Numero(-1234.56)])),
+            ("vacia", Json::Lista(vec![])),
+            ("activa", Json::Booleano(false)),
+            ("tercero", Json::Nulo),
+        ]);
+
+        assert_eq!(interpretar(&valor.to_string()), Ok(valor));
+    }
+
+    #[test]
+    fn interpretar_acepta_json_compacto_y_escapes_unicode() {
+        let valor = interpretar(r#"{"a":[1,2.5e1,{}],"b":"ñ"}"#).unwrap();
+
+        assert_eq!(valor.campo("a").and_then(|a| a.como_lista()).map(|l| l.len()), Some(3));
+        assert_eq!(valor.campo("a").unwrap().como_lista().unwrap()[1].como_numero(), Some(25.0));
+        assert_eq!(valor.campo("b").and_then(|b| b.como_texto()), Some("ñ"));
+    }
+
+    #[test]
+    fn interpretar_rechaza_json_mal_formado() {
+        assert!(interpretar(r#"{"a": 1"#).is_err());
+        assert!(interpretar(r#"{"a": tru}"#).is_err());
+        assert!(interpretar(r#"[1, 2] 3"#).is_err());
+        assert!(interpretar("").is_err());
+    }
+}
@@ -0,0 +1,103 @@
+use super::LibroDiario;
+use super::asiento::Asiento;
+use super::moneda::Moneda;
+use super::movimiento::Movimiento;
+
+/// Cabecera de los libros registro en CSV
+const CABECERA: &str = "fecha,concepto,base,cuota_iva,total,justificante";
+
+impl LibroDiario {
+
+    /// Exporta en CSV el libro registro de facturas emitidas: los asientos con ventas (grupo 70)
+    /// y su IVA repercutido (477), con la fecha, el concepto, la base, la cuota, el total y el justificante
+    pub fn libro_registro_ventas_csv(&self) -> String {
+        self.libro_registro_csv("70", "477", -1)
+    }
+
+    /// Exporta en CSV el libro registro de facturas recibidas: los asientos con compras (grupo 60)
+    /// y su IVA soportado (472), con la fecha, el concepto, la base, la cuota, el total y el justificante
+    pub fn libro_registro_compras_csv(&self) -> String {
+        self.libro_registro_csv("60", "472", 1)
+    }
+
+    /// Genera un libro registro con los asientos que mueven alguna cuenta de la base.
+    /// `signo` convierte el neto (debe menos haber) en positivo para el lado natural de la base
+    fn libro_registro_csv(&self, prefijo_base: &str, prefijo_cuota: &str, signo: i64) -> String {
+
+        let neto = |asiento: &Asiento, prefijo: &str| -> Moneda {
+            let suma = |movimientos: &[Movimiento]| movimientos.iter()
+                .filter(|m| m.codigo_cuenta().starts_with(prefijo))
+                .map(|m| m.importe())
+                .sum::<Moneda>();
+            signo * (suma(asiento.debe()) - suma(asiento.haber()))
+        };
+
+        let mut csv = String::from(CABECERA);
+        csv.push('\n');
+
+        for asiento in &self.asientos {
+            let base = neto(asiento, prefijo_base);
+            if base == Moneda::CERO {
+                continue;
+            }
+            let cuota = neto(asiento, prefijo_cuota);
+            csv.push_str(&format!(
+                "{},{},{:.2},{:.2},{:.2},{}\n",
+                asiento.fecha().format("%Y-%m-%d"),
+                campo_csv(&asiento.concepto()),
+                base.as_euros(),
+                cuota.as_euros(),
+                (base + cuota).as_euros(),
+                campo_csv(asiento.justificante().unwrap_or("")),
+            ));
+        }
+
+        csv
+    }
+}
+
+/// Entrecomilla un campo de texto si contiene comas, comillas o saltos de línea, doblando las comillas
+fn campo_csv(texto: &str) -> String {
+    if texto.contains([',', '"', '\n']) {
+        format!("\"{}\"", texto.replace('"', "\"\""))
+    } else {
+        texto.to_string()
+    }
+}
+
+#[cfg(test)]
+mod libro_registro_tests {
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::cuadro_contable::Cuadro;
+
+    fn setup() -> LibroDiario {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc_filtrado(&[4, 5, 6, 7]).unwrap();
+        let fecha = |d| Some(NaiveDate::from_ymd_opt(2023, 8, d).unwrap());
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Venta, factura 1", fecha(1), vec![("430", Moneda::from_euros(1210.0))], vec![("700", Moneda::from_euros(1000.0)), ("477", Moneda::from_euros(210.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", fecha(2), vec![("600", Moneda::from_euros(500.0)), ("472", Moneda::from_euros(105.0))], vec![("400", Moneda::from_euros(605.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Cobro", fecha(3), vec![("572", Moneda::from_euros(1210.0))], vec![("430", Moneda::from_euros(1210.0))], &mut cuadro).unwrap();
+        libro_diario.asignar_justificante("202308020", "facturas/F-0042.pdf").unwrap();
+        libro_diario
+    }
+
+    #[test]
+    fn libro_registro_ventas_csv_separa_base_y_cuota() {
+        assert_eq!(
+            setup().libro_registro_ventas_csv(),
+            "fecha,concepto,base,cuota_iva,total,justificante\n2023-08-01,\"Venta, factura 1\",1000.00,210.00,1210.00,\n"
+        );
+    }
+
+    #[test]
+    fn libro_registro_compras_csv_incluye_solo_las_compras() {
+        assert_eq!(
+            setup().libro_registro_compras_csv(),
+            "fecha,concepto,base,cuota_iva,total,justificante\n2023-08-02,Compra,500.00,105.00,605.00,facturas/F-0042.pdf\n"
+        );
+    }
+}
@@ -1,10 +1,13 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
 use regex::Regex;
 
 /// Categorización en masas para clasificar las cuentas, calcular sus saldos
 /// y ayudar en la interpretación de balances de apertura y la redacción
 /// de cierres contables
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum Masa {
     ActivoCorriente,
     ActivoNoCorriente,
@@ -12,7 +15,115 @@ pub enum Masa {
     PasivoNoCorriente,
     Patrimonio,
     Ingreso,
-    Gasto
+    Gasto,
+    /// Cuentas de orden (grupo 0): compromisos y garantías que no forman parte del balance ni de los resultados
+    Orden,
+}
+
+/// Lado en el que una masa acumula normalmente su saldo
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Naturaleza {
+    /// Saldo normalmente deudor: activo y gastos
+    Deudora,
+    /// Saldo normalmente acreedor: pasivo, patrimonio neto e ingresos
+    Acreedora,
+}
+
+impl Naturaleza {
+    /// Signo por el que multiplicar un saldo (debe menos haber) para verlo en positivo cuando es el natural
+    pub fn signo(&self) -> i64 {
+        match self {
+            Naturaleza::Deudora => 1,
+            Naturaleza::Acreedora => -1,
+        }
+    }
+
+    /// Devuelve la naturaleza opuesta
+    pub fn contraria(&self) -> Naturaleza {
+        match self {
+            Naturaleza::Deudora => Naturaleza::Acreedora,
+            Naturaleza::Acreedora => Naturaleza::Deudora,
+        }
+    }
+}
+
+/// Prefijos de las cuentas correctoras de valor del activo: amortizaciones acumuladas (28) y deterioros
+/// del inmovilizado (29), de las existencias (39), de los créditos comerciales (490 y 493) y de las
+/// inversiones a corto plazo y los activos mantenidos para la venta (59)
+const PREFIJOS_CORRECTORAS: [&str; 6] = ["28", "29", "39", "490", "493", "59"];
+
+/// Indica si la cuenta es correctora de valor: pertenece a una masa de activo, pero su saldo es acreedor
+/// y minora el activo al que acompaña
+pub fn es_correctora(codigo: &str) -> bool {
+    PREFIJOS_CORRECTORAS.iter().any(|p| codigo.starts_with(p))
+}
+
+/// Naturaleza del saldo de una cuenta según su código: la de su masa, salvo en las correctoras, que tienen la contraria
+pub fn naturaleza_codigo(codigo: &str) -> Option<Naturaleza> {
+    let naturaleza = interpretar_codigo(codigo)?.naturaleza();
+    if es_correctora(codigo) {
+        return Some(naturaleza.contraria());
+    }
+    Some(naturaleza)
+}
+
+impl Masa {
+    /// Devuelve la naturaleza del saldo de la masa: deudora o acreedora
+    pub fn naturaleza(&self) -> Naturaleza {
+        match self {
+            // Las cuentas de orden van por parejas deudora/acreedora; se toman como deudoras por convención
+            Masa::ActivoCorriente | Masa::ActivoNoCorriente | Masa::Gasto | Masa::Orden => Naturaleza::Deudora,
+            Masa::PasivoCorriente | Masa::PasivoNoCorriente | Masa::Patrimonio | Masa::Ingreso => Naturaleza::Acreedora,
+        }
+    }
+
+    /// Todas las variantes, en el orden en que aparecen en el balance y la cuenta de resultados,
+    /// y al final las cuentas de orden, que van aparte
+    pub const TODAS: [Masa; 8] = [
+        Masa::ActivoNoCorriente,
+        Masa::ActivoCorriente,
+        Masa::Patrimonio,
+        Masa::PasivoNoCorriente,
+        Masa::PasivoCorriente,
+        Masa::Ingreso,
+        Masa::Gasto,
+        Masa::Orden,
+    ];
+}
+
+impl Display for Masa {
+    /// Escribe el nombre de la variante, el mismo que acepta `from_str`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Masa::ActivoCorriente => write!(f, "ActivoCorriente"),
+            Masa::ActivoNoCorriente => write!(f, "ActivoNoCorriente"),
+            Masa::PasivoCorriente => write!(f, "PasivoCorriente"),
+            Masa::PasivoNoCorriente => write!(f, "PasivoNoCorriente"),
+            Masa::Patrimonio => write!(f, "Patrimonio"),
+            Masa::Ingreso => write!(f, "Ingreso"),
+            Masa::Gasto => write!(f, "Gasto"),
+            Masa::Orden => write!(f, "Orden"),
+        }
+    }
+}
+
+impl FromStr for Masa {
+    type Err = String;
+
+    /// Interpreta el nombre de una variante ("ActivoCorriente", "Gasto"...). Si no existe, devuelve el texto recibido
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "ActivoCorriente" => Ok(Masa::ActivoCorriente),
+            "ActivoNoCorriente" => Ok(Masa::ActivoNoCorriente),
+            "PasivoCorriente" => Ok(Masa::PasivoCorriente),
+            "PasivoNoCorriente" => Ok(Masa::PasivoNoCorriente),
+            "Patrimonio" => Ok(Masa::Patrimonio),
+            "Ingreso" => Ok(Masa::Ingreso),
+            "Gasto" => Ok(Masa::Gasto),
+            "Orden" => Ok(Masa::Orden),
+            otro => Err(otro.to_string()),
+        }
+    }
 }
 
 /// Toma un código númerico e interpreta, según en PGC, a qué masa corresponde
@@ -39,7 +150,8 @@ pub fn interpretar_codigo(codigo: &str) -> Option<Masa> {
     }
 
     // Interpretación de masas según el PGC
-    return match grupo {
+    match grupo {
+        "0" => Some(Masa::Orden), // Cuentas de orden
         "1" => match subgrupo { // Financiación básica
             "0" => Some(Masa::Patrimonio), // Capital
             "1" => Some(Masa::Patrimonio), // Reservas
@@ -50,7 +162,11 @@ pub fn interpretar_codigo(codigo: &str) -> Option<Masa> {
             "6" => Some(Masa::PasivoNoCorriente), // Deudas a largo plazo con asociados
             "7" => Some(Masa::PasivoNoCorriente), // Deudas a largo plazo por préstamos y similares
             "8" => Some(Masa::PasivoNoCorriente), // Deudas a largo plazo por fianzas y garantías
-            "9" => Some(Masa::Patrimonio), // Situaciones transitorias de financiación ???
+            "9" => match n_cuenta { // Situaciones transitorias de financiación
+                "194" => Some(Masa::PasivoCorriente), // Capital emitido pendiente de inscripción
+                "195" | "197" | "199" => Some(Masa::PasivoCorriente), // Acciones consideradas como pasivos financieros
+                _ => Some(Masa::Patrimonio), // Acciones emitidas y suscriptores, que minoran el patrimonio neto
+            },
             _ => None
         },
         "2" => Some(Masa::ActivoNoCorriente), // Inmovilizado
@@ -85,7 +201,10 @@ pub fn interpretar_codigo(codigo: &str) -> Option<Masa> {
                 "5" => Some(Masa::PasivoCorriente), // Ingresos anticipados, imputación temporal
                 _ => None
             },
-            "9" => Some(Masa::PasivoCorriente), // Provisiones por operaciones comerciales
+            "9" => match cuenta { // Deterioros de créditos comerciales y provisiones a corto plazo
+                "0" | "3" => Some(Masa::ActivoCorriente), // Deterioros, que corrigen a los clientes
+                _ => Some(Masa::PasivoCorriente), // Provisiones por operaciones comerciales
+            },
             _=> None
         },
         "5" => match subgrupo { // Cuentas financieras
@@ -98,7 +217,10 @@ pub fn interpretar_codigo(codigo: &str) -> Option<Masa> {
             "6" => Some(Masa::ActivoCorriente), // Fianzas y depósitos recibidos y constituidos a corto plazo
             "7" => Some(Masa::ActivoCorriente), // Tesorería
             "8" => Some(Masa::ActivoNoCorriente), // Activos no corrientes mantenidos para la venta
-            "9" => Some(Masa::ActivoCorriente), // Deterioros de valor
+            "9" => match cuenta { // Deterioros de valor
+                "9" => Some(Masa::ActivoNoCorriente), // De los activos no corrientes mantenidos para la venta
+                _ => Some(Masa::ActivoCorriente), // De las inversiones financieras a corto plazo
+            },
             _ => None
         },
         "6" => Some(Masa::Gasto), // Compras y gastos
@@ -111,8 +233,6 @@ pub fn interpretar_codigo(codigo: &str) -> Option<Masa> {
 
 #[cfg(test)]
 mod masa_tests {
-    use crate::cuadro_contable::masa::interpretar_codigo;
-
 
     use super::*;
 
@@ -124,9 +244,84 @@ mod masa_tests {
     }
 
     #[test]
-    fn interpretar_codigo_devuelve_Masa() {
+    fn interpretar_codigo_devuelve_masa() {
         let codigo = "60";
         assert_eq!(interpretar_codigo(codigo), Some(Masa::Gasto));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn interpretar_codigo_clasifica_el_grupo_0_como_cuentas_de_orden() {
+        assert_eq!(interpretar_codigo("010"), Some(Masa::Orden));
+    }
+
+    #[test]
+    fn interpretar_codigo_clasifica_todo_el_grupo_1_del_pgc() {
+        let sin_masa: Vec<&str> = crate::cuadro_contable::cuentas_pgc::CUENTAS_PGC
+            .iter()
+            .map(|(_, codigo)| *codigo)
+            .filter(|codigo| codigo.starts_with('1') && interpretar_codigo(codigo).is_none())
+            .collect();
+
+        assert_eq!(sin_masa, Vec::<&str>::new());
+    }
+
+    #[test]
+    fn interpretar_codigo_distingue_las_situaciones_transitorias_de_financiacion() {
+        assert_eq!(interpretar_codigo("19"), Some(Masa::Patrimonio));
+        assert_eq!(interpretar_codigo("190"), Some(Masa::Patrimonio));
+        assert_eq!(interpretar_codigo("192"), Some(Masa::Patrimonio));
+        assert_eq!(interpretar_codigo("194"), Some(Masa::PasivoCorriente));
+        assert_eq!(interpretar_codigo("199"), Some(Masa::PasivoCorriente));
+    }
+
+    #[test]
+    fn from_str_interpreta_masas_y_rechaza_desconocidas() {
+        assert_eq!("PasivoNoCorriente".parse::<Masa>(), Ok(Masa::PasivoNoCorriente));
+        assert_eq!(" Gasto ".parse::<Masa>(), Ok(Masa::Gasto));
+        assert_eq!("Activo".parse::<Masa>(), Err("Activo".to_string()));
+    }
+
+    #[test]
+    fn display_y_from_str_son_inversos_para_todas_las_masas() {
+        for masa in Masa::TODAS {
+            assert_eq!(masa.to_string().parse::<Masa>(), Ok(masa));
+        }
+        assert_eq!(Masa::ActivoNoCorriente.to_string(), "ActivoNoCorriente");
+    }
+
+    #[test]
+    fn naturaleza_es_deudora_para_activo_y_gasto_y_acreedora_para_el_resto() {
+        assert_eq!(Masa::ActivoCorriente.naturaleza(), Naturaleza::Deudora);
+        assert_eq!(Masa::ActivoNoCorriente.naturaleza(), Naturaleza::Deudora);
+        assert_eq!(Masa::Gasto.naturaleza(), Naturaleza::Deudora);
+        assert_eq!(Masa::PasivoCorriente.naturaleza(), Naturaleza::Acreedora);
+        assert_eq!(Masa::PasivoNoCorriente.naturaleza(), Naturaleza::Acreedora);
+        assert_eq!(Masa::Patrimonio.naturaleza(), Naturaleza::Acreedora);
+        assert_eq!(Masa::Ingreso.naturaleza(), Naturaleza::Acreedora);
+        assert_eq!(Masa::Orden.naturaleza(), Naturaleza::Deudora);
+    }
+
+    #[test]
+    fn interpretar_codigo_pone_las_correctoras_junto_al_activo_que_corrigen() {
+        assert_eq!(interpretar_codigo("2813"), Some(Masa::ActivoNoCorriente));
+        assert_eq!(interpretar_codigo("390"), Some(Masa::ActivoCorriente));
+        assert_eq!(interpretar_codigo("490"), Some(Masa::ActivoCorriente));
+        assert_eq!(interpretar_codigo("4933"), Some(Masa::ActivoCorriente));
+        assert_eq!(interpretar_codigo("499"), Some(Masa::PasivoCorriente));
+        assert_eq!(interpretar_codigo("598"), Some(Masa::ActivoCorriente));
+        assert_eq!(interpretar_codigo("5990"), Some(Masa::ActivoNoCorriente));
+    }
+
+    #[test]
+    fn naturaleza_codigo_invierte_la_de_las_correctoras() {
+        assert!(es_correctora("2813") && es_correctora("490") && es_correctora("5935"));
+        assert!(!es_correctora("213") && !es_correctora("499") && !es_correctora("430"));
+
+        assert_eq!(naturaleza_codigo("213"), Some(Naturaleza::Deudora));
+        assert_eq!(naturaleza_codigo("2813"), Some(Naturaleza::Acreedora));
+        assert_eq!(naturaleza_codigo("490"), Some(Naturaleza::Acreedora));
+        assert_eq!(naturaleza_codigo("499"), Some(Naturaleza::Acreedora));
+        assert_eq!(naturaleza_codigo("abc"), None);
+    }
+
+}
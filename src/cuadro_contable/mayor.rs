@@ -0,0 +1,266 @@
+use std::fmt::Display;
+use std::fs;
+
+use chrono::NaiveDate;
+
+use super::Cuadro;
+use super::apunte::Lado;
+use super::formato::formato_importe;
+use super::moneda::Moneda;
+
+/// Una línea del extracto: un apunte y el saldo de la cuenta tras él
+#[derive(Debug, PartialEq)]
+pub struct LineaExtracto {
+    pub fecha: NaiveDate,
+    pub codigo_asiento: String,
+    pub debe: Moneda,
+    pub haber: Moneda,
+    pub saldo: Moneda,
+}
+
+/// Extracto de una cuenta en un periodo, como el de un banco: el saldo anterior al periodo,
+/// los apuntes del periodo con el saldo acumulado y el saldo final
+#[derive(Debug, PartialEq)]
+pub struct Extracto {
+    pub codigo_cuenta: String,
+    pub nombre_cuenta: String,
+    pub saldo_inicial: Moneda,
+    pub lineas: Vec<LineaExtracto>,
+    pub saldo_final: Moneda,
+}
+
+impl Display for Extracto {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "({}) {}", self.codigo_cuenta, self.nombre_cuenta)?;
+        writeln!(f, "{:<10} {:<12} {:>16} {:>16} {:>16}", "Fecha", "Asiento", "Debe", "Haber", "Saldo")?;
+        writeln!(f, "{:<23} {:>50}", "Saldo anterior", formato_importe(self.saldo_inicial))?;
+        for linea in &self.lineas {
+            let importe = |i: Moneda| if i == Moneda::CERO { String::new() } else { formato_importe(i) };
+            writeln!(
+                f,
+                "{} {:<12} {:>16} {:>16} {:>16}",
+                linea.fecha.format("%Y-%m-%d"),
+                linea.codigo_asiento,
+                importe(linea.debe),
+                importe(linea.haber),
+                formato_importe(linea.saldo),
+            )?;
+        }
+        writeln!(f, "{:<23} {:>50}", "Saldo final", formato_importe(self.saldo_final))
+    }
+}
+
+impl Cuadro {
+
+    /// Dibuja una cuenta como una cuenta en T: el nombre arriba, los apuntes del debe a la izquierda
+    /// y los del haber a la derecha, cada uno con su fecha, y el saldo al pie.
+    /// Devuelve `None` si la cuenta no existe.
+    pub fn cuenta_en_t(&self, codigo_cuenta: &str) -> Option<String> {
+
+        let cuenta = self.cuentas.iter().find(|c| c.codigo() == codigo_cuenta)?;
+
+        // Ancho de cada columna, sin contar los bordes
+        let col = 34;
+        let linea = format!("+{:-<col$}+{:-<col$}+\n", "", "");
+        let celda = |fecha: String, importe: Moneda| {
+            let importe = formato_importe(importe);
+            format!(" {}{:>ancho$} ", fecha, importe, ancho = col - 2 - fecha.chars().count())
+        };
+
+        let debe: Vec<String> = cuenta.apuntes().iter()
+            .filter(|a| a.lado() == Lado::Debe)
+            .map(|a| celda(a.fecha().format("%Y-%m-%d").to_string(), a.importe()))
+            .collect();
+        let haber: Vec<String> = cuenta.apuntes().iter()
+            .filter(|a| a.lado() == Lado::Haber)
+            .map(|a| celda(a.fecha().format("%Y-%m-%d").to_string(), a.importe()))
+            .collect();
+
+        let total = |lado: Lado| cuenta.apuntes().iter()
+            .filter(|a| a.lado() == lado)
+            .map(|a| a.importe())
+            .sum::<Moneda>();
+
+        let mut salida = String::new();
+        salida.push_str(&format!("+{:-<ancho$}+\n", "", ancho = col * 2 + 1));
+        let titulo: String = format!("({}) {}", cuenta.codigo(), cuenta.nombre()).chars().take(col * 2 + 1).collect();
+        salida.push_str(&format!("|{:^ancho$}|\n", titulo, ancho = col * 2 + 1));
+        salida.push_str(&linea);
+        salida.push_str(&format!("|{:^col$}|{:^col$}|\n", "DEBE", "HABER"));
+        salida.push_str(&linea);
+
+        for i in 0..debe.len().max(haber.len()) {
+            let izquierda = debe.get(i).cloned().unwrap_or(" ".repeat(col));
+            let derecha = haber.get(i).cloned().unwrap_or(" ".repeat(col));
+            salida.push_str(&format!("|{}|{}|\n", izquierda, derecha));
+        }
+
+        salida.push_str(&linea);
+        salida.push_str(&format!("|{}|{}|\n", celda("Total".to_string(), total(Lado::Debe)), celda("Total".to_string(), total(Lado::Haber))));
+        salida.push_str(&linea);
+
+        let saldo = cuenta.saldo();
+        let naturaleza = if saldo > Moneda::CERO { "deudor" } else if saldo < Moneda::CERO { "acreedor" } else { "nulo" };
+        salida.push_str(&format!("Saldo {}: {}\n", naturaleza, formato_importe(saldo.abs())));
+
+        Some(salida)
+    }
+
+    /// Devuelve el extracto de una cuenta entre dos fechas, ambas incluidas: el saldo acumulado
+    /// por los apuntes anteriores a `inicio`, los apuntes del periodo y el saldo final.
+    /// Devuelve `None` si la cuenta no existe.
+    pub fn extracto_cuenta(&self, codigo: &str, inicio: NaiveDate, fin: NaiveDate) -> Option<Extracto> {
+
+        let cuenta = self.cuentas.iter().find(|c| c.codigo() == codigo)?;
+
+        let mut apuntes: Vec<_> = cuenta.apuntes().iter().filter(|a| a.fecha() <= fin).collect();
+        apuntes.sort_by_key(|a| a.fecha());
+
+        let neto = |lado: Lado, importe: Moneda| if lado == Lado::Debe { importe } else { -importe };
+
+        let saldo_inicial: Moneda = apuntes.iter()
+            .filter(|a| a.fecha() < inicio)
+            .map(|a| neto(a.lado(), a.importe()))
+            .sum();
+
+        let mut saldo = saldo_inicial;
+        let mut lineas = vec![];
+        for apunte in apuntes.iter().filter(|a| a.fecha() >= inicio) {
+            saldo += neto(apunte.lado(), apunte.importe());
+            let (debe, haber) = match apunte.lado() {
+                Lado::Debe => (apunte.importe(), Moneda::CERO),
+                Lado::Haber => (Moneda::CERO, apunte.importe()),
+            };
+            lineas.push(LineaExtracto { fecha: apunte.fecha(), codigo_asiento: apunte.codigo_asiento(), debe, haber, saldo });
+        }
+
+        Some(Extracto {
+            codigo_cuenta: cuenta.codigo(),
+            nombre_cuenta: cuenta.nombre(),
+            saldo_inicial,
+            lineas,
+            saldo_final: saldo,
+        })
+    }
+
+    /// Devuelve el saldo de una cuenta a una fecha de corte, contando solo los apuntes de ese día o anteriores.
+    /// Devuelve `None` si la cuenta no existe.
+    pub fn saldo_a_fecha(&self, codigo: &str, fecha: NaiveDate) -> Option<Moneda> {
+        self.cuentas.iter().find(|c| c.codigo() == codigo).map(|c| c.saldo_a_fecha(fecha))
+    }
+
+    /// Escribe en un archivo el libro mayor: todas las cuentas con apuntes, cada una como cuenta en T,
+    /// separadas por una línea en blanco. Las cuentas sin movimientos no se incluyen.
+    pub fn exportar_mayor(&self, path: &str) -> std::io::Result<()> {
+
+        let mayor: Vec<String> = self.cuentas.iter()
+            .filter(|c| !c.apuntes().is_empty())
+            .filter_map(|c| self.cuenta_en_t(&c.codigo()))
+            .collect();
+
+        fs::write(path, mayor.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod mayor_tests {
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+
+        let fecha = |d| NaiveDate::from_ymd_opt(2023, 8, d).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", Some(fecha(1)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", Some(fecha(2)), vec![("600", Moneda::from_euros(150.0))], vec![("572", Moneda::from_euros(150.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", Some(fecha(3)), vec![("600", Moneda::from_euros(50.0))], vec![("572", Moneda::from_euros(50.0))], &mut cuadro).unwrap();
+
+        cuadro
+    }
+
+    #[test]
+    fn cuenta_en_t_alinea_debe_y_haber_y_muestra_el_saldo() {
+        let t = setup_cuadro().cuenta_en_t("572").unwrap();
+        let lineas: Vec<&str> = t.lines().collect();
+
+        assert!(lineas[1].contains("(572) Bancos"));
+        assert!(lineas[3].contains("DEBE") && lineas[3].contains("HABER"));
+        assert!(lineas[5].starts_with("| 2023-08-01") && lineas[5].contains("1.000,00 € | 2023-08-02"));
+        assert!(lineas[6].starts_with("|                                  | 2023-08-03"));
+        assert!(lineas[8].contains("Total") && lineas[8].contains("200,00 €"));
+        assert_eq!(lineas[10], "Saldo deudor: 800,00 €");
+
+        // Todas las filas de la caja tienen el mismo ancho
+        assert!(lineas[..10].iter().all(|l| l.chars().count() == lineas[0].chars().count()));
+    }
+
+    #[test]
+    fn cuenta_en_t_indica_saldo_acreedor() {
+        let t = setup_cuadro().cuenta_en_t("100").unwrap();
+
+        assert!(t.ends_with("Saldo acreedor: 1.000,00 €\n"));
+    }
+
+    #[test]
+    fn cuenta_en_t_devuelve_none_si_la_cuenta_no_existe() {
+        assert_eq!(setup_cuadro().cuenta_en_t("999"), None);
+    }
+
+    #[test]
+    fn extracto_cuenta_arrastra_el_saldo_anterior_al_periodo() {
+        let cuadro = setup_cuadro();
+        let fecha = |d| NaiveDate::from_ymd_opt(2023, 8, d).unwrap();
+
+        let extracto = cuadro.extracto_cuenta("572", fecha(2), fecha(2)).unwrap();
+
+        assert_eq!(extracto.saldo_inicial, Moneda::from_euros(1000.0));
+        assert_eq!(extracto.lineas, vec![LineaExtracto {
+            fecha: fecha(2),
+            codigo_asiento: "202308020".to_string(),
+            debe: Moneda::from_euros(0.0),
+            haber: Moneda::from_euros(150.0),
+            saldo: Moneda::from_euros(850.0),
+        }]);
+        assert_eq!(extracto.saldo_final, Moneda::from_euros(850.0));
+        assert!(extracto.to_string().contains("Saldo anterior"));
+        assert!(cuadro.extracto_cuenta("999", fecha(1), fecha(31)).is_none());
+    }
+
+    #[test]
+    fn saldo_a_fecha_ignora_los_apuntes_posteriores() {
+        let cuadro = setup_cuadro();
+        let fecha = |d| NaiveDate::from_ymd_opt(2023, 8, d).unwrap();
+
+        assert_eq!(cuadro.saldo_a_fecha("572", fecha(1)), Some(Moneda::from_euros(1000.0)));
+        assert_eq!(cuadro.saldo_a_fecha("572", fecha(2)), Some(Moneda::from_euros(850.0)));
+        assert_eq!(cuadro.saldo_a_fecha("600", fecha(31)), Some(Moneda::from_euros(200.0)));
+        assert_eq!(cuadro.saldo_a_fecha("572", NaiveDate::from_ymd_opt(2023, 7, 31).unwrap()), Some(Moneda::from_euros(0.0)));
+        assert_eq!(cuadro.saldo_a_fecha("999", fecha(1)), None);
+    }
+
+    #[test]
+    fn exportar_mayor_escribe_solo_las_cuentas_con_movimientos() {
+        let mut cuadro = setup_cuadro();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        let path = std::env::temp_dir().join("presupuestos_exportar_mayor.txt");
+        let path = path.to_str().unwrap();
+
+        cuadro.exportar_mayor(path).unwrap();
+        let mayor = fs::read_to_string(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert!(mayor.contains("(572) Bancos"));
+        assert!(mayor.contains("(100) Capital"));
+        assert!(mayor.contains("(600) Compras"));
+        assert!(!mayor.contains("(700) Ventas"));
+        assert_eq!(mayor.matches("Saldo ").count(), 3);
+    }
+}
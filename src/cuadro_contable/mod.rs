@@ -1,12 +1,53 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Display;
 
-use chrono::NaiveDate;
+use chrono::{Datelike, NaiveDate};
 
-mod cuenta;
-mod movimiento;
-mod asiento;
+use moneda::Moneda;
+
+pub mod cuenta;
+pub mod movimiento;
+pub mod apunte;
+pub mod asiento;
 mod cuentas_pgc;
+pub mod formato;
+mod pdf;
+mod balance;
+pub mod mayor;
+mod resultados;
+pub mod resumen;
 pub mod masa;
+pub mod ejercicio;
+pub mod plantilla;
+pub mod reparto;
+pub mod nomina;
+pub mod integridad;
+mod libro_registro;
+pub mod conciliacion;
+mod tesoreria;
+pub mod iva;
+mod traspaso;
+mod busqueda;
+mod persistencia;
+pub mod auditoria;
+pub mod borrador;
+mod json;
+mod copia;
+mod impuesto;
+pub mod ratios;
+pub mod gestoria;
+pub mod categoria;
+pub mod redondeo;
+pub mod moneda;
+
+/// Pares de código de cuenta e importe, tal y como los recibe `LibroDiario::insertar_asiento`
+type Anotaciones<'a> = Vec<(&'a str, Moneda)>;
+
+/// Toma prestados como `Anotaciones` unos pares de código e importe que guardan su propio código,
+/// como los de un borrador, una plantilla o una copia de seguridad
+pub(crate) fn prestar_anotaciones(lado: &[(String, Moneda)]) -> Anotaciones<'_> {
+    lado.iter().map(|(c, i)| (c.as_str(), *i)).collect()
+}
 
 /// Este struct almacena las cuentas,
 /// y ejecuta las operaciones superficiales relacionadas con ellas
@@ -14,6 +55,14 @@ pub mod masa;
 pub struct Cuadro {
     /// Almacena las cuentas
     cuentas: Vec<cuenta::Cuenta>,
+    /// El ejercicio en curso, si se ha definido
+    ejercicio: Option<ejercicio::Ejercicio>,
+    /// Cuenta que hace de contrapartida en las altas rápidas de gastos e ingresos
+    cuenta_tesoreria: Option<String>,
+    /// Último día de los periodos ya declarados, en los que no se admiten asientos
+    bloqueado_hasta: Option<NaiveDate>,
+    /// Operaciones hechas sobre el cuadro, para la auditoría
+    historial: Vec<auditoria::EventoAuditoria>,
 }
 
 /// Manejo de posibles errores de cuadro
@@ -22,14 +71,26 @@ pub enum CuadroError {
     CuadroNoVacio,
     CuentaDuplicada(String),
     CuentaInexistente(String),
+    NombreDuplicado(String),
+    MasaDesconocida(String),
+    ArchivoIlegible(String),
+    CuentaConSaldo(String),
+    ExtractoNoValido(usize, String),
+    CodigoNoClasificable(String),
 }
 
 impl Display for CuadroError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match &*self {
+        match self {
             CuadroError::CuadroNoVacio => write!(f, "El cuadro ya contiene cuentas. Puedes añadir de una en una, pero no cargar el PGC"),
             CuadroError::CuentaDuplicada(cuenta_s) => write!(f, "La cuenta '{}' ya existe", cuenta_s),
             CuadroError::CuentaInexistente(cuenta_s) => write!(f, "El código de cuenta '{}' no existe", cuenta_s),
+            CuadroError::NombreDuplicado(cuenta_s) => write!(f, "Ya existe una cuenta con ese nombre: '{}'", cuenta_s),
+            CuadroError::MasaDesconocida(masa_s) => write!(f, "La masa '{}' no existe", masa_s),
+            CuadroError::ArchivoIlegible(error_s) => write!(f, "No se ha podido leer el archivo: {}", error_s),
+            CuadroError::CuentaConSaldo(cuenta_s) => write!(f, "La cuenta '{}' tiene saldo y no se puede desactivar", cuenta_s),
+            CuadroError::CodigoNoClasificable(codigo_s) => write!(f, "No se puede deducir la masa de la cuenta '{}' a partir de su código", codigo_s),
+            CuadroError::ExtractoNoValido(linea, motivo_s) => write!(f, "La línea {} del extracto bancario no es válida: {}", linea, motivo_s),
         }
     }
 }
@@ -38,40 +99,213 @@ impl Cuadro {
 
     /// Crea un nuevo cuadro de cuentas
     pub fn new() -> Cuadro {     
-        Cuadro { cuentas: vec![] }
+        Cuadro { cuentas: vec![], ejercicio: None, cuenta_tesoreria: None, bloqueado_hasta: None, historial: vec![] }
     }
 
-    /// Carga todas las cuentas del Plan General de Contabilidad en el cuadro de cuentas, si este está vacío
-    pub fn cargar_pgc(&mut self) -> Result<(), CuadroError> {
+    /// Define el ejercicio en curso, que delimita las fechas de cierre, apertura e informes
+    pub fn establecer_ejercicio(&mut self, ejercicio: ejercicio::Ejercicio) {
+        self.ejercicio = Some(ejercicio);
+    }
 
-        if self.cuentas.len() == 0 {
-            for (nombre_cuenta, codigo_cuenta) in cuentas_pgc::CUENTAS_PGC {
-                let masa = masa::interpretar_codigo(codigo_cuenta);
-                if let Some(m) = masa {
-                    self.crear_cuenta(nombre_cuenta, codigo_cuenta, m)?;
-                } else {
-                    println!("Código perdido al cargar el PGC: {}", codigo_cuenta);
-                }
-            };
-        } else { 
+    /// Devuelve el ejercicio en curso, si se ha definido
+    pub fn ejercicio(&self) -> Option<ejercicio::Ejercicio> {
+        self.ejercicio
+    }
+
+    /// Bloquea los periodos ya declarados: a partir de ahora no se admiten asientos
+    /// con fecha igual o anterior a la indicada
+    pub fn bloquear_hasta(&mut self, fecha: NaiveDate) {
+        self.bloqueado_hasta = Some(fecha);
+        self.registrar(auditoria::Operacion::PeriodoBloqueado(fecha));
+    }
+
+    /// Devuelve la última fecha bloqueada, si la hay
+    pub fn bloqueado_hasta(&self) -> Option<NaiveDate> {
+        self.bloqueado_hasta
+    }
+
+    /// Fecha a la que se emiten los informes: hoy, salvo que el ejercicio ya haya terminado,
+    /// en cuyo caso su fecha de fin
+    fn fecha_informe(&self) -> NaiveDate {
+        let hoy = chrono::offset::Local::now().date_naive();
+        match self.ejercicio {
+            Some(e) if e.fin() < hoy => e.fin(),
+            _ => hoy,
+        }
+    }
+
+    /// Carga todas las cuentas del Plan General de Contabilidad en el cuadro de cuentas, si este está vacío.
+    /// Devuelve los códigos que se han saltado por no poder deducir su masa, que deberían ser ninguno
+    pub fn cargar_pgc(&mut self) -> Result<Vec<&'static str>, CuadroError> {
+        self.cargar_pgc_filtrado(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9])
+    }
+
+    /// Carga las cuentas del Plan General de Contabilidad que pertenezcan a los grupos indicados
+    /// (el primer dígito del código), si el cuadro está vacío. Devuelve los códigos que se han saltado
+    /// por no poder deducir su masa
+    pub fn cargar_pgc_filtrado(&mut self, grupos: &[u8]) -> Result<Vec<&'static str>, CuadroError> {
+
+        if !self.cuentas.is_empty() {
+            return Err(CuadroError::CuadroNoVacio)
+        }
+
+        Ok(self.cargar_plan(cuentas_pgc::CUENTAS_PGC.iter().copied().filter(|(_, codigo_cuenta)| {
+            let grupo = codigo_cuenta[..1].parse::<u8>();
+            matches!(grupo, Ok(g) if grupos.contains(&g))
+        })))
+    }
+
+    /// Carga el plan de cuentas desde un archivo editable en lugar del PGC embebido, si el cuadro está vacío.
+    /// Cada línea lleva el código y el nombre separados por la primera coma ("5720,Banco Uno, cuenta corriente");
+    /// la masa se deduce del código como en [`Cuadro::cargar_pgc`]. Se admiten una cabecera, líneas en blanco
+    /// y comentarios que empiezan por '#'. Cada cuenta pasa las mismas comprobaciones que en [`Cuadro::crear_cuenta`]
+    /// (códigos y nombres repetidos) y su código tiene que tener masa: si alguna línea falla, no se carga ninguna cuenta.
+    pub fn cargar_pgc_desde(&mut self, path: &str) -> Result<(), CuadroError> {
+
+        if !self.cuentas.is_empty() {
             return Err(CuadroError::CuadroNoVacio)
         }
+
+        let contenido = std::fs::read_to_string(path)
+            .map_err(|e| CuadroError::ArchivoIlegible(e.to_string()))?;
+
+        // Las cuentas se crean primero en un cuadro aparte, que solo se vuelca si el archivo entero es válido
+        let mut plan = Cuadro::new();
+        for linea in contenido.lines().map(|l| l.trim()).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            if linea.to_lowercase() == "codigo,nombre" {
+                continue;
+            }
+            match linea.split_once(',') {
+                Some((codigo, nombre)) if !codigo.trim().is_empty() && !nombre.trim().is_empty() => {
+                    let masa = masa::interpretar_codigo(codigo.trim())
+                        .ok_or_else(|| CuadroError::CodigoNoClasificable(codigo.trim().to_string()))?;
+                    plan.crear_cuenta(nombre.trim(), codigo.trim(), masa)?;
+                },
+                _ => return Err(CuadroError::ArchivoIlegible(format!("la línea '{}' no tiene código y nombre", linea))),
+            }
+        }
+
+        self.cuentas = plan.cuentas;
+        self.registrar(auditoria::Operacion::PgcCargado(self.cuentas.len()));
         Ok(())
     }
-    
+
+    /// Crea las cuentas de un plan de pares (nombre, código), con la masa que indica su código.
+    /// Las que no se pueden clasificar se saltan, y se devuelven sus códigos
+    fn cargar_plan<'a>(&mut self, plan: impl Iterator<Item = (&'a str, &'a str)>) -> Vec<&'a str> {
+        let previas = self.cuentas.len();
+        let mut no_clasificadas = vec![];
+        for (nombre_cuenta, codigo_cuenta) in plan {
+            match masa::interpretar_codigo(codigo_cuenta) {
+                // El PGC repite algunos nombres en distintos subgrupos,
+                // así que sus cuentas no pasan por la comprobación de nombres de crear_cuenta
+                Some(m) => self.cuentas.push(cuenta::Cuenta::new(nombre_cuenta, codigo_cuenta, m)),
+                None => no_clasificadas.push(codigo_cuenta),
+            }
+        };
+        self.registrar(auditoria::Operacion::PgcCargado(self.cuentas.len() - previas));
+        no_clasificadas
+    }
+
+    /// Completa el cuadro con las cuentas del PGC cuyo código aún no existe, aunque ya haya cuentas propias.
+    /// Las que ya existen se omiten sin error y conservan su nombre y su masa.
+    /// Devuelve cuántas cuentas se han añadido y cuántas se han omitido, por estar ya en el cuadro
+    /// o por no poder deducir su masa
+    pub fn cargar_pgc_complementario(&mut self) -> (usize, usize) {
+        let nuevas: Vec<(&str, &str)> = cuentas_pgc::CUENTAS_PGC
+            .iter()
+            .copied()
+            .filter(|(_, codigo)| !self.cuentas.iter().any(|c| c.codigo() == *codigo))
+            .collect();
+        let omitidas = cuentas_pgc::CUENTAS_PGC.len() - nuevas.len();
+
+        let previas = self.cuentas.len();
+        let no_clasificadas = self.cargar_plan(nuevas.into_iter());
+        (self.cuentas.len() - previas, omitidas + no_clasificadas.len())
+    }
+
+    /// Lista, sin cargar nada, los códigos del PGC a los que `interpretar_codigo` no asigna masa
+    /// y que, por tanto, `cargar_pgc` se saltaría. Sirve para comprobar la cobertura de las reglas de masas
+    pub fn cuentas_pgc_no_clasificables() -> Vec<&'static str> {
+        cuentas_pgc::CUENTAS_PGC
+            .iter()
+            .map(|(_, codigo)| *codigo)
+            .filter(|codigo| masa::interpretar_codigo(codigo).is_none())
+            .collect()
+    }
+
+    /// Recorre las cuentas del cuadro, en el orden en que se crearon
+    pub fn cuentas(&self) -> impl Iterator<Item = &cuenta::Cuenta> {
+        self.cuentas.iter()
+    }
+
+    /// Cambia la masa de una cuenta existente, conservando sus apuntes y saldos.
+    /// Sirve para corregir la clasificación automática de `interpretar_codigo`.
+    pub fn reclasificar_cuenta(&mut self, codigo: &str, nueva_masa: masa::Masa) -> Result<(), CuadroError> {
+        match self.buscar_cuenta(codigo) {
+            Some(cuenta) => {
+                cuenta.cambiar_masa(nueva_masa);
+                self.registrar(auditoria::Operacion::CuentaReclasificada { codigo: codigo.to_string(), masa: nueva_masa });
+                Ok(())
+            },
+            None => Err(CuadroError::CuentaInexistente(codigo.to_string())),
+        }
+    }
+
+    /// Oculta una cuenta de los listados sin borrarla. Falla si la cuenta tiene saldo
+    pub fn desactivar_cuenta(&mut self, codigo: &str) -> Result<(), CuadroError> {
+        match self.buscar_cuenta(codigo) {
+            Some(cuenta) => {
+                if cuenta.desactivar() {
+                    self.registrar(auditoria::Operacion::CuentaDesactivada(codigo.to_string()));
+                    Ok(())
+                } else {
+                    Err(CuadroError::CuentaConSaldo(codigo.to_string()))
+                }
+            },
+            None => Err(CuadroError::CuentaInexistente(codigo.to_string())),
+        }
+    }
+
+    /// Vuelve a mostrar en los listados una cuenta desactivada
+    pub fn activar_cuenta(&mut self, codigo: &str) -> Result<(), CuadroError> {
+        match self.buscar_cuenta(codigo) {
+            Some(cuenta) => {
+                cuenta.activar();
+                self.registrar(auditoria::Operacion::CuentaActivada(codigo.to_string()));
+                Ok(())
+            },
+            None => Err(CuadroError::CuentaInexistente(codigo.to_string())),
+        }
+    }
+
+    /// Recorre las cuentas desactivadas, que no aparecen en los listados
+    pub fn cuentas_inactivas(&self) -> impl Iterator<Item = &cuenta::Cuenta> {
+        self.cuentas.iter().filter(|c| !c.activa())
+    }
+
+    /// Recorre las cuentas de orden (grupo 0), que no aparecen en el balance ni en la cuenta de resultados
+    pub fn cuentas_de_orden(&self) -> impl Iterator<Item = &cuenta::Cuenta> {
+        self.cuentas.iter().filter(|c| c.masa() == masa::Masa::Orden)
+    }
+
     /// Encuentra una cuenta y devuelve su referencia mutable si la encuentra
     pub fn buscar_cuenta(&mut self, codigo_cuenta: &str) -> Option<&mut cuenta::Cuenta> {
         for id in 0..self.cuentas.len() {
-            if String::from(codigo_cuenta) == self.cuentas[id].codigo() {
+            if self.cuentas[id].codigo() == codigo_cuenta {
                 return Some(&mut self.cuentas[id])
             }
         };
         None
     }
 
-    /// Crea una cuenta y la inserta en el cuadro, si no existe ya
+    /// Crea una cuenta y la inserta en el cuadro, si no existe ya otra con el mismo código o el mismo nombre
     pub fn crear_cuenta(&mut self, nombre_cuenta: &str, codigo_cuenta: &str, masa: masa::Masa) -> Result<(), CuadroError> {
 
+        if let Some(c) = self.cuentas.iter().find(|c| c.nombre() == nombre_cuenta && c.codigo() != codigo_cuenta) {
+            return Err(CuadroError::NombreDuplicado(format!("{} ~ {}", c.codigo(), c.nombre())))
+        }
+
         match self.buscar_cuenta(codigo_cuenta) {
             Some(c) => {
                 Err(CuadroError::CuentaDuplicada(format!("{} ~ {}", c.codigo(), c.nombre())))
@@ -79,17 +313,286 @@ impl Cuadro {
             None => {
                 let cuenta = cuenta::Cuenta::new(nombre_cuenta, codigo_cuenta, masa);
                 self.cuentas.push(cuenta);
+                self.registrar(auditoria::Operacion::CuentaCreada { codigo: codigo_cuenta.to_string(), nombre: nombre_cuenta.to_string() });
                 Ok(())
             }
         }
     }
 
+    /// Importa cuentas desde un CSV con tres columnas: código, nombre y masa (p. ej. "5720,Banco Uno,ActivoCorriente").
+    /// La masa se indica con el nombre de la variante, de modo que no depende de `interpretar_codigo`.
+    /// Se admite una cabecera y líneas en blanco. Si alguna línea está mal, no se importa ninguna cuenta.
+    pub fn importar_cuentas_csv(&mut self, path: &str) -> Result<(), CuadroError> {
+
+        let contenido = std::fs::read_to_string(path)
+            .map_err(|e| CuadroError::ArchivoIlegible(e.to_string()))?;
+
+        let mut cuentas: Vec<(&str, &str, masa::Masa)> = vec![];
+
+        for linea in contenido.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+            if linea.to_lowercase() == "codigo,nombre,masa" {
+                continue;
+            }
+            // El nombre puede contener comas, así que el código es el primer campo y la masa el último
+            let campos = linea
+                .split_once(',')
+                .and_then(|(codigo, resto)| resto.rsplit_once(',').map(|(nombre, masa)| (codigo, nombre, masa)));
+            match campos {
+                Some((codigo, nombre, masa)) => {
+                    let masa = masa.parse::<masa::Masa>().map_err(CuadroError::MasaDesconocida)?;
+                    cuentas.push((codigo.trim(), nombre.trim(), masa));
+                },
+                None => return Err(CuadroError::ArchivoIlegible(format!("la línea '{}' no tiene tres columnas", linea))),
+            }
+        }
+
+        for (codigo, nombre, masa) in cuentas {
+            self.crear_cuenta(nombre, codigo, masa)?;
+        }
+
+        Ok(())
+    }
+
+    /// Pone a cero todas las cuentas y reconstruye sus saldos y apuntes recorriendo el libro diario.
+    /// Devuelve los códigos de las cuentas cuyo saldo no coincidía con el recalculado,
+    /// que deberían ser ninguno si la mayorización incremental funciona bien.
+    pub fn recalcular_saldos(&mut self, libro_diario: &LibroDiario) -> Vec<String> {
+
+        let saldos_previos: Vec<(String, Moneda)> = self.cuentas
+            .iter()
+            .map(|c| (c.codigo(), c.saldo()))
+            .collect();
+
+        for cuenta in self.cuentas.iter_mut() {
+            cuenta.reiniciar();
+        }
+
+        for asiento in &libro_diario.asientos {
+            let lados = [(asiento.debe(), apunte::Lado::Debe), (asiento.haber(), apunte::Lado::Haber)];
+            for (movimientos, lado) in lados {
+                for movimiento in movimientos {
+                    if let Some(c) = self.buscar_cuenta(&movimiento.codigo_cuenta()) {
+                        c.anotar(apunte::Apunte::new(asiento.fecha(), movimiento.importe(), lado, &asiento.codigo()));
+                    }
+                }
+            }
+        }
+
+        // El orden de las cuentas no cambia, así que se pueden comparar una a una
+        saldos_previos
+            .into_iter()
+            .zip(self.cuentas.iter())
+            .filter(|((_, saldo), cuenta)| cuenta.saldo() != *saldo)
+            .map(|((codigo, _), _)| codigo)
+            .collect()
+    }
+
+    /// Toma los saldos actuales de todas las cuentas como saldos de apertura del ejercicio.
+    /// Se llama justo después de anotar el asiento de apertura.
+    pub fn fijar_saldos_apertura(&mut self) {
+        for cuenta in self.cuentas.iter_mut() {
+            cuenta.fijar_saldo_apertura();
+        }
+    }
+
+    /// Cierra el ejercicio a la fecha indicada: anota en el libro diario la regularización de las cuentas
+    /// de gestión contra la cuenta 129 y el asiento de cierre de las cuentas de balance.
+    /// Devuelve un cuadro nuevo con las mismas cuentas y un libro diario nuevo con el asiento de apertura
+    /// del día siguiente, anotado a partir de los saldos de balance; este cuadro queda cerrado.
+    /// Los asientos se validan todos antes de anotar ninguno: si alguno falla, ni el cuadro ni el libro diario cambian.
+    /// Si el cuadro tiene un ejercicio definido, el cuadro nuevo pasa al ejercicio siguiente.
+    /// Para cerrar en la fecha de fin del ejercicio, ver [`Cuadro::cerrar_ejercicio_en_curso`].
+    pub fn cerrar_ejercicio(&mut self, libro_diario: &mut LibroDiario, fecha_cierre: NaiveDate) -> Result<(Cuadro, LibroDiario), LibroDiarioError> {
+
+        let es_gestion = |m: masa::Masa| m == masa::Masa::Ingreso || m == masa::Masa::Gasto;
+
+        // Si hay que crear la 129 y el cierre falla, se retiran la cuenta y su evento de auditoría
+        let eventos_previos = self.historial.len();
+        let crea_129 = self.buscar_cuenta("129").is_none();
+        if crea_129 {
+            self.crear_cuenta("Resultado del ejercicio", "129", masa::Masa::Patrimonio)
+                .map_err(LibroDiarioError::CuentaNoCreada)?;
+        }
+
+        // Regularización: salda las cuentas de gestión contra el resultado del ejercicio
+        let gestion: Vec<(String, Moneda)> = self.cuentas
+            .iter()
+            .filter(|c| es_gestion(c.masa()) && c.saldo() != Moneda::CERO)
+            .map(|c| (c.codigo(), c.saldo()))
+            .collect();
+        let resultado: Moneda = gestion.iter().map(|(_, s)| *s).sum();
+
+        // Cierre: salda las cuentas de balance con los saldos que dejará la regularización
+        let balance: Vec<(String, Moneda)> = self.cuentas
+            .iter()
+            .filter(|c| !es_gestion(c.masa()))
+            .map(|c| (c.codigo(), if c.codigo() == "129" { c.saldo() + resultado } else { c.saldo() }))
+            .filter(|(_, saldo)| *saldo != Moneda::CERO)
+            .collect();
+
+        // Apertura del ejercicio siguiente: los mismos saldos de balance, en su lado natural
+        let mut nuevo = Cuadro::new();
+        nuevo.cuenta_tesoreria = self.cuenta_tesoreria.clone();
+        nuevo.ejercicio = self.ejercicio
+            .and_then(|e| fecha_cierre.succ_opt().and_then(|inicio| ejercicio::Ejercicio::new(inicio, e.siguiente().fin())));
+        for c in &self.cuentas {
+            let mut cuenta = cuenta::Cuenta::new(&c.nombre(), &c.codigo(), c.masa());
+            if !c.activa() {
+                cuenta.desactivar();
+            }
+            nuevo.cuentas.push(cuenta);
+        }
+
+        let mut nuevo_diario = LibroDiario::new();
+        let cierre = self.preparar_cierre(libro_diario, &gestion, resultado, &balance, fecha_cierre)
+            .and_then(|cierre| {
+                let (haber, debe) = Cuadro::saldar(&balance);
+                let apertura = match balance.is_empty() {
+                    true => vec![],
+                    false => vec![nuevo_diario.preparar("Asiento de apertura", fecha_cierre.succ_opt(), debe, haber, asiento::TipoAsiento::Apertura, &mut nuevo)?],
+                };
+                Ok((cierre, apertura))
+            })
+            .and_then(|(cierre, apertura)| {
+                libro_diario.anotar(cierre, self)?;
+                nuevo_diario.anotar(apertura, &mut nuevo)
+            });
+
+        if let Err(error) = cierre {
+            if crea_129 {
+                self.cuentas.retain(|c| c.codigo() != "129");
+                self.historial.truncate(eventos_previos);
+            }
+            return Err(error);
+        }
+        nuevo.fijar_saldos_apertura();
+
+        Ok((nuevo, nuevo_diario))
+    }
+
+    /// Prepara la regularización (si hay cuentas de gestión con saldo) y el cierre (si hay cuentas de balance con saldo)
+    fn preparar_cierre<'a>(&mut self, libro_diario: &LibroDiario, gestion: &'a [(String, Moneda)], resultado: Moneda, balance: &'a [(String, Moneda)], fecha_cierre: NaiveDate) -> Result<Vec<AsientoPreparado<'a>>, LibroDiarioError> {
+
+        let mut preparados = vec![];
+
+        if !gestion.is_empty() {
+            let (mut debe, mut haber) = Cuadro::saldar(gestion);
+            // Sin beneficio ni pérdida, los ingresos y los gastos se saldan entre sí y la 129 no interviene
+            if resultado > Moneda::CERO {
+                debe.push(("129", resultado));
+            } else if resultado < Moneda::CERO {
+                haber.push(("129", -resultado));
+            }
+            preparados.push(libro_diario.preparar("Regularización", Some(fecha_cierre), debe, haber, asiento::TipoAsiento::Regularizacion, self)?);
+        }
+
+        if !balance.is_empty() {
+            let (debe, haber) = Cuadro::saldar(balance);
+            preparados.push(libro_diario.preparar("Asiento de cierre", Some(fecha_cierre), debe, haber, asiento::TipoAsiento::Cierre, self)?);
+        }
+
+        Ok(preparados)
+    }
+
+    /// Cierra el ejercicio en curso en su fecha de fin. Si no hay ejercicio definido,
+    /// se toma el año natural de hoy, como se venía haciendo.
+    pub fn cerrar_ejercicio_en_curso(&mut self, libro_diario: &mut LibroDiario) -> Result<(Cuadro, LibroDiario), LibroDiarioError> {
+        let ejercicio = self.ejercicio
+            .unwrap_or(ejercicio::Ejercicio::natural(chrono::offset::Local::now().year()));
+        self.cerrar_ejercicio(libro_diario, ejercicio.fin())
+    }
+
+    /// Reparte unos saldos en los movimientos que los dejan a cero:
+    /// los deudores se abonan en el haber y los acreedores se cargan en el debe
+    fn saldar(saldos: &[(String, Moneda)]) -> (Anotaciones<'_>, Anotaciones<'_>) {
+        let mut debe = vec![];
+        let mut haber = vec![];
+        for (codigo, saldo) in saldos {
+            if *saldo > Moneda::CERO {
+                haber.push((codigo.as_str(), *saldo));
+            } else {
+                debe.push((codigo.as_str(), -*saldo));
+            }
+        }
+        (debe, haber)
+    }
+
+    /// Devuelve una línea por cada cuenta con saldo o con algún apunte, usando el formato de ancho fijo de las cuentas
+    pub fn imprimir_cuentas_activas(&self) -> String {
+        let mut salida = String::new();
+        for cuenta in self.cuentas.iter().filter(|c| c.activa() && (c.saldo() != Moneda::CERO || !c.apuntes().is_empty())) {
+            salida.push_str(&format!("{:width$}\n", cuenta, width=80));
+        }
+        salida
+    }
+
+    /// Suma los saldos (deudor menos acreedor) de las cuentas de cada masa
+    pub fn totales_por_masa(&self) -> HashMap<masa::Masa, Moneda> {
+        let mut totales = HashMap::new();
+        for cuenta in &self.cuentas {
+            *totales.entry(cuenta.masa()).or_insert(Moneda::CERO) += cuenta.saldo();
+        }
+        totales
+    }
+
+    /// Consolida varios cuadros en uno: las cuentas con el mismo código se funden en una sola
+    /// que recibe los apuntes de todas, así que su saldo es la suma de los saldos.
+    /// Las cuentas que solo están en algún cuadro se incorporan tal cual; si el nombre o la masa
+    /// difieren entre cuadros, se queda el del primero. Pensado para informes globales.
+    pub fn consolidar(cuadros: &[&Cuadro]) -> Cuadro {
+        let mut consolidado = Cuadro::new();
+        for cuadro in cuadros {
+            for cuenta in &cuadro.cuentas {
+                if consolidado.buscar_cuenta(&cuenta.codigo()).is_none() {
+                    consolidado.cuentas.push(cuenta::Cuenta::new(&cuenta.nombre(), &cuenta.codigo(), cuenta.masa()));
+                }
+                if let Some(destino) = consolidado.buscar_cuenta(&cuenta.codigo()) {
+                    for apunte in cuenta.apuntes() {
+                        destino.anotar(apunte.clone());
+                    }
+                }
+            }
+        }
+        consolidado
+    }
+
+    /// Comprueba la ecuación patrimonial: Activo = Pasivo + Patrimonio + (Ingresos − Gastos).
+    /// Devuelve la diferencia (activo menos el resto) si no es cero.
+    /// Las cuentas de orden no intervienen. Si no cuadra, hay un error en la lógica de saldos
+    /// o algún asiento mezcla cuentas de orden con cuentas de balance o de gestión.
+    pub fn verificar_ecuacion(&self) -> Result<(), Moneda> {
+        use masa::Masa;
+
+        let totales = self.totales_por_masa();
+        let total = |masa: Masa| masa.naturaleza().signo() * totales.get(&masa).copied().unwrap_or(Moneda::CERO);
+
+        let activo = total(Masa::ActivoCorriente) + total(Masa::ActivoNoCorriente);
+        let pasivo = total(Masa::PasivoCorriente) + total(Masa::PasivoNoCorriente);
+        let diferencia = activo - (pasivo + total(Masa::Patrimonio) + total(Masa::Ingreso) - total(Masa::Gasto));
+
+        if diferencia == Moneda::CERO {
+            Ok(())
+        } else {
+            Err(diferencia)
+        }
+    }
+
+}
+
+impl Default for Cuadro {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Display for Cuadro {
+    /// Imprime las cuentas activas ordenadas por código numérico ("20" antes que "100");
+    /// las de código no numérico van al final, en orden alfabético
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for cuenta in &self.cuentas {
-            write!(f, "{}\n", cuenta)?;
+        let mut cuentas: Vec<&cuenta::Cuenta> = self.cuentas.iter().filter(|c| c.activa()).collect();
+        cuentas.sort_by_key(|c| (c.codigo_numerico().map_or((1, 0), |n| (0, n)), c.codigo()));
+        for cuenta in cuentas {
+            writeln!(f, "{}", cuenta)?;
         };
         Ok(())
     }
@@ -100,12 +603,94 @@ mod cuadro_tests {
 
     use super::*;
 
+    #[test]
+    fn consolidar_suma_cuentas_comunes_y_une_las_demas() {
+        let fecha = |d| Some(NaiveDate::from_ymd_opt(2023, 8, d).unwrap());
+
+        let mut personal = Cuadro::new();
+        personal.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        personal.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        let mut diario_personal = LibroDiario::new();
+        diario_personal.insertar_asiento("Ahorros", fecha(2), vec![("572", Moneda::from_euros(500.0))], vec![("100", Moneda::from_euros(500.0))], &mut personal).unwrap();
+
+        let mut negocio = Cuadro::new();
+        negocio.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        negocio.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        let mut diario_negocio = LibroDiario::new();
+        diario_negocio.insertar_asiento("Venta", fecha(1), vec![("572", Moneda::from_euros(200.0))], vec![("700", Moneda::from_euros(200.0))], &mut negocio).unwrap();
+
+        let mut consolidado = Cuadro::consolidar(&[&personal, &negocio]);
+        let diario = LibroDiario::consolidar(&[&diario_personal, &diario_negocio]);
+
+        assert_eq!(consolidado.cuentas().count(), 3);
+        assert_eq!(consolidado.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(700.0));
+        assert_eq!(consolidado.buscar_cuenta("100").unwrap().saldo(), Moneda::from_euros(-500.0));
+        assert_eq!(consolidado.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(-200.0));
+        assert_eq!(diario.asientos().map(|a| a.total_debe()).collect::<Vec<Moneda>>(), vec![Moneda::from_euros(200.0), Moneda::from_euros(500.0)]);
+        assert_eq!(personal.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(500.0));
+    }
+
+    #[test]
+    fn verificar_ecuacion_devuelve_la_diferencia_si_no_cuadra() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", masa::Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(300.0))], vec![("700", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(100.0))], vec![("572", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.verificar_ecuacion(), Ok(()));
+
+        cuadro.buscar_cuenta("572").unwrap().saldo_deudor(Moneda::from_euros(50.0));
+
+        assert_eq!(cuadro.verificar_ecuacion(), Err(Moneda::from_euros(50.0)));
+    }
+
+    #[test]
+    fn insertar_asiento_con_cuenta_de_orden_y_de_balance_no_comprueba_la_ecuacion() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Avales concedidos", "010", masa::Masa::Orden).unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        // La ecuación deja fuera las cuentas de orden, así que no cuadra, pero el asiento sí y se inserta
+        assert!(libro_diario.insertar_asiento("Aval", None, vec![("010", Moneda::from_euros(500.0))], vec![("572", Moneda::from_euros(500.0))], &mut cuadro).is_ok());
+        assert_eq!(cuadro.verificar_ecuacion(), Err(Moneda::from_euros(-500.0)));
+    }
+
+    #[test]
+    fn reclasificar_cuenta_cambia_la_masa_o_falla_si_no_existe() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Caja de ahorros", "555", masa::Masa::ActivoCorriente).unwrap();
+
+        assert!(cuadro.reclasificar_cuenta("555", masa::Masa::PasivoCorriente).is_ok());
+        assert_eq!(cuadro.buscar_cuenta("555").unwrap().masa(), masa::Masa::PasivoCorriente);
+        assert_eq!(
+            cuadro.reclasificar_cuenta("556", masa::Masa::PasivoCorriente),
+            Err(CuadroError::CuentaInexistente("556".to_string()))
+        );
+    }
+
+    #[test]
+    fn cuentas_recorre_las_cuentas_en_orden() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("test", "0000", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("test1", "0001", masa::Masa::Patrimonio).unwrap();
+
+        let codigos: Vec<String> = cuadro.cuentas().map(|c| c.codigo()).collect();
+
+        assert_eq!(codigos, vec!["0000".to_string(), "0001".to_string()]);
+    }
+
     #[test]
     fn new_crea_cuadro_vacio() {
       
       let cuadro = Cuadro::new();
 
-      assert_eq!(cuadro, Cuadro { cuentas: vec![] });
+      assert_eq!(cuadro, Cuadro { cuentas: vec![], ejercicio: None, cuenta_tesoreria: None, bloqueado_hasta: None, historial: vec![] });
 
     }
 
@@ -118,6 +703,85 @@ mod cuadro_tests {
         assert_eq!(cuadro.cuentas.len(), 899);
     }
 
+    #[test]
+    fn cuentas_pgc_no_clasificables_esta_vacia() {
+        assert_eq!(Cuadro::cuentas_pgc_no_clasificables(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn cargar_pgc_desde_lee_codigo_y_nombre_y_deduce_la_masa() {
+        let path = std::env::temp_dir().join("presupuestos_pgc.csv");
+        std::fs::write(&path, "codigo,nombre\n# Plan reducido\n572,Bancos e instituciones de crédito c/c vista, euros\n\n6280,Energía\n").unwrap();
+        let mut cuadro = Cuadro::new();
+
+        cuadro.cargar_pgc_desde(path.to_str().unwrap()).unwrap();
+
+        assert_eq!(cuadro.cuentas.len(), 2);
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().nombre(), "Bancos e instituciones de crédito c/c vista, euros");
+        assert_eq!(cuadro.buscar_cuenta("6280").unwrap().masa(), masa::Masa::Gasto);
+        assert_eq!(cuadro.cargar_pgc_desde(path.to_str().unwrap()), Err(CuadroError::CuadroNoVacio));
+    }
+
+    #[test]
+    fn cargar_pgc_desde_no_carga_nada_si_una_linea_esta_mal() {
+        let path = std::env::temp_dir().join("presupuestos_pgc_mal.csv");
+        std::fs::write(&path, "572,Bancos\n6280\n").unwrap();
+        let mut cuadro = Cuadro::new();
+
+        assert_eq!(
+            cuadro.cargar_pgc_desde(path.to_str().unwrap()),
+            Err(CuadroError::ArchivoIlegible("la línea '6280' no tiene código y nombre".to_string()))
+        );
+        assert!(cuadro.cuentas.is_empty());
+    }
+
+    #[test]
+    fn cargar_pgc_desde_no_carga_nada_con_codigos_repetidos_o_sin_masa() {
+        let casos = [
+            ("presupuestos_pgc_repetido.csv", "572,Bancos\n6280,Energía\n572,Otro banco\n", CuadroError::CuentaDuplicada("572 ~ Bancos".to_string())),
+            ("presupuestos_pgc_nombre.csv", "572,Bancos\n5720,Bancos\n", CuadroError::NombreDuplicado("572 ~ Bancos".to_string())),
+            ("presupuestos_pgc_sin_masa.csv", "572,Bancos\nX1,Sin grupo\n", CuadroError::CodigoNoClasificable("X1".to_string())),
+        ];
+
+        for (archivo, contenido, error) in casos {
+            let path = std::env::temp_dir().join(archivo);
+            std::fs::write(&path, contenido).unwrap();
+            let mut cuadro = Cuadro::new();
+
+            assert_eq!(cuadro.cargar_pgc_desde(path.to_str().unwrap()), Err(error));
+            assert!(cuadro.cuentas.is_empty());
+            assert!(cuadro.historial().is_empty());
+        }
+    }
+
+    #[test]
+    fn cargar_pgc_complementario_respeta_las_cuentas_existentes() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Mi banco", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Luz y gas", "628", masa::Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Auxiliar", "A01", masa::Masa::ActivoCorriente).unwrap();
+
+        let (anadidas, omitidas) = cuadro.cargar_pgc_complementario();
+
+        assert_eq!((anadidas, omitidas), (897, 2));
+        assert_eq!(cuadro.cuentas.len(), 900);
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().nombre(), "Mi banco");
+        assert!(cuadro.buscar_cuenta("400").is_some());
+        assert_eq!(cuadro.cargar_pgc_complementario(), (0, 899));
+    }
+
+    #[test]
+    fn cargar_plan_devuelve_los_codigos_que_no_puede_clasificar() {
+        let mut cuadro = Cuadro::new();
+
+        let no_clasificadas = cuadro.cargar_plan([("Bancos", "572"), ("Sin grupo", "X1")].into_iter());
+
+        assert_eq!(no_clasificadas, vec!["X1"]);
+        assert_eq!(cuadro.cuentas.len(), 1);
+        assert_eq!(cuadro.cargar_pgc_filtrado(&[5]), Err(CuadroError::CuadroNoVacio));
+        assert_eq!(Cuadro::new().cargar_pgc(), Ok(vec![]));
+    }
+
     #[test]
     fn cargar_pgc_falla_si_ya_hay_cuentas() {
         let mut cuadro = Cuadro::new();
@@ -127,6 +791,26 @@ mod cuadro_tests {
         assert_eq!(cuadro.cargar_pgc(), Err(CuadroError::CuadroNoVacio));
     }
 
+    #[test]
+    fn cargar_pgc_filtrado_carga_solo_los_grupos_indicados() {
+        let mut cuadro = Cuadro::new();
+
+        assert!(cuadro.cargar_pgc_filtrado(&[5, 7]).is_ok());
+        assert!(!cuadro.cuentas.is_empty());
+        assert!(cuadro.cuentas.iter().all(|c| c.codigo().starts_with('5') || c.codigo().starts_with('7')));
+        assert!(cuadro.buscar_cuenta("572").is_some());
+        assert!(cuadro.buscar_cuenta("600").is_none());
+    }
+
+    #[test]
+    fn cargar_pgc_filtrado_falla_si_ya_hay_cuentas() {
+        let mut cuadro = Cuadro::new();
+        let cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
+        cuadro.cuentas.push(cuenta);
+
+        assert_eq!(cuadro.cargar_pgc_filtrado(&[6]), Err(CuadroError::CuadroNoVacio));
+    }
+
     #[test]
     fn buscar_cuenta_encuentra_una_cuenta_por_codigo() {
         let mut cuadro = Cuadro::new();
@@ -145,40 +829,347 @@ mod cuadro_tests {
     }
 
     #[test]
-    fn crear_cuenta_falla_si_ya_existe() {
+    fn totales_por_masa_suma_los_saldos_de_cada_masa() {
         let mut cuadro = Cuadro::new();
-        let cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
+        let mut cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
+        cuenta.saldo_deudor(Moneda::from_euros(10.0));
+        cuadro.cuentas.push(cuenta);
+        let mut cuenta = cuenta::Cuenta::new("test1", "0001", masa::Masa::ActivoCorriente);
+        cuenta.saldo_deudor(Moneda::from_euros(5.0));
+        cuadro.cuentas.push(cuenta);
+        let mut cuenta = cuenta::Cuenta::new("test2", "0002", masa::Masa::Patrimonio);
+        cuenta.saldo_acreedor(Moneda::from_euros(15.0));
         cuadro.cuentas.push(cuenta);
 
-        let r = cuadro.crear_cuenta("Nueva cuenta", "0000", masa::Masa::ActivoCorriente);
+        let totales = cuadro.totales_por_masa();
 
-        assert!(r.is_err());
-        assert!(match r {
-            Ok(()) => false,
-            Err(e) => {
-                assert_eq!(e.to_string(), "La cuenta '0000 ~ test' ya existe");
-                true
-            }
-        })
+        assert_eq!(totales.get(&masa::Masa::ActivoCorriente), Some(&Moneda::from_euros(15.0)));
+        assert_eq!(totales.get(&masa::Masa::Patrimonio), Some(&Moneda::from_euros(-15.0)));
+        assert_eq!(totales.get(&masa::Masa::Gasto), None);
     }
-  
-}
 
-/// Este struct se ocupa del manejo de asientos
-pub struct LibroDiario {
-    asientos: Vec<asiento::Asiento>
-}
+    #[test]
+    fn recalcular_saldos_repara_un_saldo_corrompido() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("test", "0000", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("test1", "0001", masa::Masa::Patrimonio).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Primero", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", None, vec![("0001", Moneda::from_euros(5.0))], vec![("0000", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
 
-#[derive(Debug, PartialEq)]
-pub enum LibroDiarioError {
-    AsientoDesequilibrado
-}
+        assert!(cuadro.recalcular_saldos(&libro_diario).is_empty());
 
-impl Display for LibroDiarioError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match *self {
-            Self::AsientoDesequilibrado => write!(f, "el debe y el haber del asiento que intentas insertar no coinciden")
-        }
+        cuadro.buscar_cuenta("0000").unwrap().saldo_deudor(Moneda::from_euros(100.0));
+
+        assert_eq!(cuadro.recalcular_saldos(&libro_diario), vec!["0000".to_string()]);
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(15.0));
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().apuntes().len(), 2);
+        assert_eq!(cuadro.buscar_cuenta("0001").unwrap().saldo(), Moneda::from_euros(-15.0));
+    }
+
+    #[test]
+    fn cerrar_ejercicio_regulariza_cierra_y_abre_el_siguiente() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", masa::Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(300.0))], vec![("700", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(100.0))], vec![("572", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+
+        let fecha_cierre = NaiveDate::from_ymd_opt(2023, 12, 31).unwrap();
+        let (mut nuevo, nuevo_diario) = cuadro.cerrar_ejercicio(&mut libro_diario, fecha_cierre).unwrap();
+
+        // Regularización y cierre
+        assert_eq!(libro_diario.asientos.len(), 5);
+        assert!(cuadro.cuentas.iter().all(|c| c.saldo() == Moneda::from_euros(0.00)));
+        assert_eq!(cuadro.buscar_cuenta("129").unwrap().apuntes().len(), 2);
+        assert!(cuadro.historial().iter().any(|e| e.operacion == auditoria::Operacion::CuentaCreada {
+            codigo: "129".to_string(),
+            nombre: "Resultado del ejercicio".to_string(),
+        }));
+
+        assert_eq!(libro_diario.asientos[3].tipo(), asiento::TipoAsiento::Regularizacion);
+        assert_eq!(libro_diario.asientos[4].tipo(), asiento::TipoAsiento::Cierre);
+
+        // Apertura
+        assert_eq!(nuevo_diario.asientos.len(), 1);
+        assert_eq!(nuevo_diario.asientos[0].tipo(), asiento::TipoAsiento::Apertura);
+        assert_eq!(nuevo_diario.asientos[0].fecha(), NaiveDate::from_ymd_opt(2024, 1, 1).unwrap());
+        assert_eq!(nuevo.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(1200.0));
+        assert_eq!(nuevo.buscar_cuenta("100").unwrap().saldo(), Moneda::from_euros(-1000.0));
+        assert_eq!(nuevo.buscar_cuenta("129").unwrap().saldo(), Moneda::from_euros(-200.0));
+        assert_eq!(nuevo.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(0.0));
+        assert_eq!(nuevo.buscar_cuenta("572").unwrap().saldo_apertura(), Moneda::from_euros(1200.0));
+        assert_eq!(nuevo.buscar_cuenta("572").unwrap().saldo_ejercicio(), Moneda::from_euros(0.0));
+    }
+
+    #[test]
+    fn cerrar_ejercicio_en_curso_usa_el_fin_del_ejercicio_y_abre_el_siguiente() {
+        let fecha = |a, m, d| NaiveDate::from_ymd_opt(a, m, d).unwrap();
+        let mut cuadro = Cuadro::new();
+        cuadro.establecer_ejercicio(ejercicio::Ejercicio::new(fecha(2023, 7, 1), fecha(2024, 6, 30)).unwrap());
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", Some(fecha(2023, 7, 1)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+
+        let (nuevo, nuevo_diario) = cuadro.cerrar_ejercicio_en_curso(&mut libro_diario).unwrap();
+
+        assert_eq!(libro_diario.asientos[1].fecha(), fecha(2024, 6, 30));
+        assert_eq!(nuevo_diario.asientos[0].fecha(), fecha(2024, 7, 1));
+        assert_eq!(nuevo.ejercicio(), ejercicio::Ejercicio::new(fecha(2024, 7, 1), fecha(2025, 6, 30)));
+    }
+
+    #[test]
+    fn cerrar_ejercicio_que_falla_no_toca_el_cuadro_ni_el_libro_diario() {
+        let fecha = |a, m, d| NaiveDate::from_ymd_opt(a, m, d).unwrap();
+        let mut cuadro = Cuadro::new();
+        cuadro.establecer_ejercicio(ejercicio::Ejercicio::natural(2023));
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Aportación", Some(fecha(2023, 1, 2)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(2023, 3, 1)), vec![("572", Moneda::from_euros(300.0))], vec![("700", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+
+        let eventos = cuadro.historial().len();
+        let cierre = cuadro.cerrar_ejercicio(&mut libro_diario, fecha(2024, 1, 15));
+
+        assert_eq!(cierre.err(), Some(LibroDiarioError::FechaFueraDeEjercicio(fecha(2024, 1, 15))));
+        assert_eq!(libro_diario.asientos.len(), 2);
+        assert!(cuadro.buscar_cuenta("129").is_none());
+        assert_eq!(cuadro.historial().len(), eventos);
+        assert_eq!(cuadro.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(-300.0));
+
+        // El cuadro sigue siendo del llamador y se puede cerrar en la fecha correcta
+        assert!(cuadro.cerrar_ejercicio(&mut libro_diario, fecha(2023, 12, 31)).is_ok());
+        assert_eq!(libro_diario.asientos.len(), 4);
+    }
+
+    #[test]
+    fn cerrar_ejercicio_no_crea_la_129_si_su_nombre_ya_esta_en_uso() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Resultado del ejercicio", "1290", masa::Masa::Patrimonio).unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        let cierre = cuadro.cerrar_ejercicio(&mut libro_diario, NaiveDate::from_ymd_opt(2023, 12, 31).unwrap());
+
+        assert!(matches!(cierre.err(), Some(LibroDiarioError::CuentaNoCreada(CuadroError::NombreDuplicado(_)))));
+        assert!(cuadro.buscar_cuenta("129").is_none());
+    }
+
+    #[test]
+    fn cerrar_ejercicio_sin_resultado_no_anota_la_129() {
+        let fecha = |a, m, d| NaiveDate::from_ymd_opt(a, m, d).unwrap();
+        let mut cuadro = Cuadro::new();
+        cuadro.establecer_ejercicio(ejercicio::Ejercicio::natural(2023));
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", masa::Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Aportación", Some(fecha(2023, 1, 2)), vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", Some(fecha(2023, 3, 1)), vec![("572", Moneda::from_euros(300.0))], vec![("700", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", Some(fecha(2023, 4, 1)), vec![("600", Moneda::from_euros(300.0))], vec![("572", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+
+        let (mut nuevo, _) = cuadro.cerrar_ejercicio(&mut libro_diario, fecha(2023, 12, 31)).unwrap();
+
+        let regularizacion = libro_diario.asientos().find(|a| a.tipo() == asiento::TipoAsiento::Regularizacion).unwrap();
+        assert!(regularizacion.debe().iter().chain(regularizacion.haber()).all(|m| m.codigo_cuenta() != "129"));
+        assert!(cuadro.buscar_cuenta("129").unwrap().apuntes().is_empty());
+        assert_eq!(nuevo.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(1000.0));
+    }
+
+    #[test]
+    fn display_ordena_las_cuentas_por_codigo_numerico() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Auxiliar", "A01", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Inmovilizado intangible", "20", masa::Masa::ActivoNoCorriente).unwrap();
+        cuadro.crear_cuenta("Banco Uno", "5720001", masa::Masa::ActivoCorriente).unwrap();
+
+        let codigos: Vec<String> = cuadro.to_string()
+            .lines()
+            .map(|l| l[1..l.find(')').unwrap()].to_string())
+            .collect();
+
+        assert_eq!(codigos, vec!["20", "100", "572", "5720001", "A01"]);
+    }
+
+    #[test]
+    fn desactivar_cuenta_la_oculta_de_los_listados_salvo_si_tiene_saldo() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Caja", "570", masa::Masa::ActivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Aportación", None, vec![("572", Moneda::from_euros(500.0))], vec![("100", Moneda::from_euros(500.0))], &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.desactivar_cuenta("570"), Ok(()));
+        assert_eq!(cuadro.desactivar_cuenta("572"), Err(CuadroError::CuentaConSaldo("572".to_string())));
+        assert_eq!(cuadro.desactivar_cuenta("999"), Err(CuadroError::CuentaInexistente("999".to_string())));
+
+        assert!(!cuadro.to_string().contains("Caja"));
+        assert!(cuadro.to_string().contains("Bancos"));
+        assert_eq!(cuadro.cuentas_inactivas().map(|c| c.codigo()).collect::<Vec<String>>(), vec!["570"]);
+        assert_eq!(cuadro.cuentas().count(), 3);
+
+        cuadro.activar_cuenta("570").unwrap();
+        assert!(cuadro.to_string().contains("Caja"));
+    }
+
+    #[test]
+    fn imprimir_cuentas_activas_omite_las_cuentas_sin_saldo_ni_apuntes() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", masa::Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Caja", "570", masa::Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", masa::Masa::Ingreso).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(100.0))], vec![("100", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Ida", None, vec![("570", Moneda::from_euros(10.0))], vec![("572", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Vuelta", None, vec![("572", Moneda::from_euros(10.0))], vec![("570", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+
+        let activas = cuadro.imprimir_cuentas_activas();
+
+        assert_eq!(activas.lines().count(), 3);
+        assert!(activas.contains("(572) Bancos"));
+        assert!(activas.contains("(570) Caja"));
+        assert!(!activas.contains("(700) Ventas"));
+        assert!(activas.lines().all(|l| l.chars().count() == 80));
+    }
+
+    #[test]
+    fn crear_cuenta_falla_si_ya_existe() {
+        let mut cuadro = Cuadro::new();
+        let cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
+        cuadro.cuentas.push(cuenta);
+
+        let r = cuadro.crear_cuenta("Nueva cuenta", "0000", masa::Masa::ActivoCorriente);
+
+        assert!(r.is_err());
+        assert!(match r {
+            Ok(()) => false,
+            Err(e) => {
+                assert_eq!(e.to_string(), "La cuenta '0000 ~ test' ya existe");
+                true
+            }
+        })
+    }
+
+    #[test]
+    fn importar_cuentas_csv_crea_cuentas_con_su_masa() {
+        let path = std::env::temp_dir().join("presupuestos_importar_cuentas.csv");
+        std::fs::write(&path, "codigo,nombre,masa\n5720,Banco Uno, cuenta corriente,ActivoCorriente\n\n6290,Suscripciones,Gasto\n").unwrap();
+        let mut cuadro = Cuadro::new();
+
+        assert_eq!(cuadro.importar_cuentas_csv(path.to_str().unwrap()), Ok(()));
+        assert_eq!(cuadro.cuentas.len(), 2);
+        assert_eq!(cuadro.buscar_cuenta("5720").unwrap().nombre(), "Banco Uno, cuenta corriente");
+        assert_eq!(cuadro.buscar_cuenta("6290").unwrap().masa(), masa::Masa::Gasto);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn importar_cuentas_csv_falla_con_masa_desconocida() {
+        let path = std::env::temp_dir().join("presupuestos_importar_cuentas_masa.csv");
+        std::fs::write(&path, "5720,Banco Uno,ActivoCorriente\n6290,Suscripciones,Gastos\n").unwrap();
+        let mut cuadro = Cuadro::new();
+
+        assert_eq!(cuadro.importar_cuentas_csv(path.to_str().unwrap()), Err(CuadroError::MasaDesconocida("Gastos".to_string())));
+        assert!(cuadro.cuentas.is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn crear_cuenta_falla_si_ya_existe_el_nombre() {
+        let mut cuadro = Cuadro::new();
+
+        assert!(cuadro.crear_cuenta("Bancos", "572", masa::Masa::ActivoCorriente).is_ok());
+
+        let r = cuadro.crear_cuenta("Bancos", "5720", masa::Masa::ActivoCorriente);
+
+        assert_eq!(r, Err(CuadroError::NombreDuplicado("572 ~ Bancos".to_string())));
+        assert_eq!(cuadro.cuentas.len(), 1);
+    }
+  
+}
+
+/// Este struct se ocupa del manejo de asientos
+pub struct LibroDiario {
+    asientos: Vec<asiento::Asiento>,
+    /// Carpeta en la que se guarda cada asiento al insertarlo, si se ha activado la persistencia
+    persistencia: Option<std::path::PathBuf>,
+    /// Diferencia máxima entre el debe y el haber que se cuadra sola, si se ha activado el ajuste de redondeo
+    ajuste_redondeo: Option<Moneda>,
+}
+
+/// Un asiento por insertar junto con otros mediante `LibroDiario::insertar_varios`
+pub(crate) struct AsientoNuevo<'a> {
+    pub(crate) concepto: String,
+    pub(crate) fecha: Option<NaiveDate>,
+    pub(crate) tipo: asiento::TipoAsiento,
+    pub(crate) debe: Anotaciones<'a>,
+    pub(crate) haber: Anotaciones<'a>,
+}
+
+/// Asiento validado, con las líneas que anotará en las cuentas, a falta de numerarlo e insertarlo
+struct AsientoPreparado<'a> {
+    asiento: asiento::Asiento,
+    debe: Anotaciones<'a>,
+    haber: Anotaciones<'a>,
+    /// La diferencia cuadrada con la cuenta de redondeo, si se ha añadido esa línea
+    ajuste: Option<Moneda>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LibroDiarioError {
+    /// El debe y el haber no coinciden; guarda la diferencia (debe menos haber)
+    AsientoDesequilibrado(Moneda),
+    /// La fecha del asiento no pertenece al ejercicio del cuadro
+    FechaFueraDeEjercicio(NaiveDate),
+    /// La fecha del asiento cae en un periodo bloqueado (ver `Cuadro::bloquear_hasta`)
+    PeriodoBloqueado(NaiveDate),
+    /// No hay ningún asiento con ese código en el libro diario
+    AsientoInexistente(String),
+    /// La cuenta de una línea del asiento no existe en el cuadro: el lado, el número de línea (desde 1) y el código
+    CuentaInexistenteEnLinea(apunte::Lado, usize, String),
+    /// La cuenta de una línea del asiento está desactivada (ver `Cuadro::desactivar_cuenta`):
+    /// el lado, el número de línea (desde 1) y el código
+    CuentaInactiva(apunte::Lado, usize, String),
+    /// El importe de una línea del asiento es cero, o negativo sin ser el asiento rectificativo:
+    /// el lado, el número de línea (desde 1) y el importe
+    ImporteInvalido(apunte::Lado, usize, Moneda),
+    /// No se ha podido crear una cuenta que necesita la operación, como la 129 al cerrar el ejercicio
+    CuentaNoCreada(CuadroError),
+    /// Se ha pedido un alta rápida sin haber fijado la cuenta de tesorería del cuadro
+    SinCuentaTesoreria,
+    /// No se ha podido guardar el asiento en disco; el asiento no se ha insertado
+    ErrorPersistencia(String),
+}
+
+impl Display for LibroDiarioError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            Self::AsientoDesequilibrado(diferencia) => write!(f, "el debe y el haber del asiento que intentas insertar no coinciden (diferencia: {})", diferencia),
+            Self::FechaFueraDeEjercicio(fecha) => write!(f, "la fecha del asiento ({}) está fuera del ejercicio", fecha.format("%Y-%m-%d")),
+            Self::PeriodoBloqueado(fecha) => write!(f, "la fecha del asiento ({}) pertenece a un periodo bloqueado", fecha.format("%Y-%m-%d")),
+            Self::AsientoInexistente(ref codigo) => write!(f, "no existe ningún asiento con el código '{}'", codigo),
+            Self::CuentaInexistenteEnLinea(lado, linea, ref codigo) => write!(f, "el código de cuenta '{}' de la línea {} del {} no existe", codigo, linea, lado),
+            Self::CuentaInactiva(lado, linea, ref codigo) => write!(f, "la cuenta '{}' de la línea {} del {} está desactivada; actívala antes de anotar en ella", codigo, linea, lado),
+            Self::ImporteInvalido(lado, linea, importe) if importe < Moneda::CERO => write!(f, "el importe de la línea {} del {} es negativo ({}) y el asiento no es rectificativo", linea, lado, importe),
+            Self::ImporteInvalido(lado, linea, _) => write!(f, "el importe de la línea {} del {} es cero", linea, lado),
+            Self::CuentaNoCreada(ref error) => write!(f, "no se ha podido crear una cuenta necesaria: {}", error),
+            Self::SinCuentaTesoreria => write!(f, "no se ha fijado la cuenta de tesorería del cuadro"),
+            Self::ErrorPersistencia(ref error) => write!(f, "no se ha podido guardar el asiento: {}", error),
+        }
     }
 }
 
@@ -186,52 +1177,465 @@ impl LibroDiario {
 
     /// Crea un Libro Diario
     pub fn new() -> LibroDiario {
-        LibroDiario { asientos: vec![] }
+        LibroDiario { asientos: vec![], persistencia: None, ajuste_redondeo: None }
+    }
+
+    /// Une los asientos de varios libros diarios en uno, ordenados por fecha (y, dentro de cada día,
+    /// con la apertura primero y el cierre al final). Los asientos conservan el código de su libro de origen, por lo que puede haber códigos repetidos.
+    pub fn consolidar(libros: &[&LibroDiario]) -> LibroDiario {
+        let mut asientos: Vec<asiento::Asiento> = libros
+            .iter()
+            .flat_map(|l| l.asientos.iter().cloned())
+            .collect();
+        asientos.sort_by_key(|a| a.clave_orden());
+        LibroDiario { asientos, persistencia: None, ajuste_redondeo: None }
+    }
+
+    /// Recorre los asientos del libro diario, en el orden en que se insertaron, en solo lectura
+    pub fn asientos(&self) -> impl Iterator<Item = &asiento::Asiento> {
+        self.asientos.iter()
+    }
+
+    /// Devuelve los asientos en el orden formal del libro diario: por fecha y, dentro de cada día,
+    /// la apertura primero, luego los asientos normales en orden de inserción, la regularización y el cierre
+    pub fn asientos_ordenados(&self) -> Vec<&asiento::Asiento> {
+        let mut asientos: Vec<&asiento::Asiento> = self.asientos.iter().collect();
+        asientos.sort_by_key(|a| a.clave_orden());
+        asientos
+    }
+
+    /// Devuelve los asientos con algún movimiento, en el debe o en el haber, sobre una cuenta
+    /// o sobre cualquiera de sus subcuentas (las que empiezan por el mismo código)
+    pub fn asientos_con_cuenta(&self, codigo: &str) -> Vec<&asiento::Asiento> {
+        self.asientos
+            .iter()
+            .filter(|a| a.debe().iter().chain(a.haber()).any(|m| m.codigo_cuenta().starts_with(codigo)))
+            .collect()
+    }
+
+    /// Devuelve los asientos con fecha posterior a hoy, para revisarlos:
+    /// suelen ser errores al teclear el año, aunque a veces se anotan a propósito
+    pub fn asientos_futuros(&self) -> Vec<&asiento::Asiento> {
+        self.asientos_posteriores_a(chrono::offset::Local::now().date_naive())
+    }
+
+    /// Devuelve los asientos con fecha posterior a la indicada, sin incluirla.
+    /// `asientos_futuros` la usa con la fecha de hoy
+    pub fn asientos_posteriores_a(&self, fecha: NaiveDate) -> Vec<&asiento::Asiento> {
+        self.asientos
+            .iter()
+            .filter(|a| a.fecha() > fecha)
+            .collect()
+    }
+
+    /// Asocia un tercero (proveedor, cliente...) al asiento con el código indicado
+    pub fn asignar_tercero(&mut self, codigo_asiento: &str, tercero: &str) -> Result<(), LibroDiarioError> {
+        match self.asientos.iter_mut().find(|a| a.codigo() == codigo_asiento) {
+            Some(asiento) => {
+                asiento.fijar_tercero(tercero);
+                Ok(())
+            },
+            None => Err(LibroDiarioError::AsientoInexistente(codigo_asiento.to_string())),
+        }
+    }
+
+    /// Marca el asiento con el código indicado como de apertura, cierre, regularización o normal
+    pub fn marcar_tipo(&mut self, codigo_asiento: &str, tipo: asiento::TipoAsiento) -> Result<(), LibroDiarioError> {
+        match self.asientos.iter_mut().find(|a| a.codigo() == codigo_asiento) {
+            Some(asiento) => {
+                asiento.fijar_tipo(tipo);
+                Ok(())
+            },
+            None => Err(LibroDiarioError::AsientoInexistente(codigo_asiento.to_string())),
+        }
+    }
+
+    /// Inserta una copia del asiento indicado en otra fecha, con los mismos movimientos y concepto.
+    /// El nuevo asiento recibe su propio código y se valida como cualquier otra inserción.
+    /// La copia de una rectificativa sigue siendo rectificativa, para admitir sus importes negativos;
+    /// la de cualquier otro asiento (también la de una apertura o un cierre) es un asiento normal.
+    pub fn duplicar_asiento(&mut self, codigo: &str, nueva_fecha: NaiveDate, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        let original = self.asientos
+            .iter()
+            .find(|a| a.codigo() == codigo)
+            .ok_or_else(|| LibroDiarioError::AsientoInexistente(codigo.to_string()))?;
+
+        let concepto = original.concepto();
+        let tipo = match original.tipo() {
+            asiento::TipoAsiento::Rectificativo => asiento::TipoAsiento::Rectificativo,
+            _ => asiento::TipoAsiento::Normal,
+        };
+        let anotaciones = |movimientos: &[movimiento::Movimiento]| movimientos
+            .iter()
+            .map(|m| (m.codigo_cuenta(), m.importe()))
+            .collect::<Vec<(String, Moneda)>>();
+        let debe = anotaciones(original.debe());
+        let haber = anotaciones(original.haber());
+
+        self.insertar(
+            &concepto,
+            Some(nueva_fecha),
+            prestar_anotaciones(&debe),
+            prestar_anotaciones(&haber),
+            tipo,
+            cuadro,
+        )
+    }
+
+    /// Vincula el asiento con el código indicado con su justificante (nombre de archivo o URL)
+    pub fn asignar_justificante(&mut self, codigo_asiento: &str, justificante: &str) -> Result<(), LibroDiarioError> {
+        match self.asientos.iter_mut().find(|a| a.codigo() == codigo_asiento) {
+            Some(asiento) => {
+                asiento.fijar_justificante(justificante);
+                Ok(())
+            },
+            None => Err(LibroDiarioError::AsientoInexistente(codigo_asiento.to_string())),
+        }
+    }
+
+    /// Devuelve los asientos asociados a un tercero, en orden de inserción
+    pub fn asientos_de_tercero(&self, nombre: &str) -> Vec<&asiento::Asiento> {
+        self.asientos
+            .iter()
+            .filter(|a| a.tercero() == Some(nombre))
+            .collect()
+    }
+
+    /// Agrupa los asientos por (año, mes) con su número y el total movido en el debe.
+    /// Al ser un `BTreeMap`, los meses quedan en orden cronológico; los meses sin asientos no aparecen.
+    pub fn resumen_mensual(&self) -> BTreeMap<(i32, u32), (usize, Moneda)> {
+        let mut resumen = BTreeMap::new();
+        for asiento in &self.asientos {
+            let fecha = asiento.fecha();
+            let (numero, total) = resumen.entry((fecha.year(), fecha.month())).or_insert((0, Moneda::CERO));
+            *numero += 1;
+            *total += asiento.total_debe();
+        }
+        resumen
+    }
+
+    /// Crea e inserta un asiento. Este es el punto de conexión entre Libro Diario y Cuadro de Cuentas.
+    /// Cada línea ha de tener una cuenta existente y un importe positivo; si no, el error indica el lado y
+    /// el número de la primera línea incorrecta. Si alguna línea falla o el asiento no cuadra, no se modifica nada.
+    /// Si el cuadro tiene un ejercicio definido, rechaza los asientos con fecha fuera de él.
+    /// Para anular o corregir importes con líneas en negativo, ver [`LibroDiario::insertar_rectificativo`].
+    /// Las diferencias de céntimos se pueden cuadrar solas con [`LibroDiario::activar_ajuste_redondeo`].
+    pub fn insertar_asiento(&mut self, concepto: &str, fecha: Option<NaiveDate>, debe: Vec<(&str, Moneda)>, haber: Vec<(&str, Moneda)>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        self.insertar(concepto, fecha, debe, haber, asiento::TipoAsiento::Normal, cuadro)
+    }
+
+    /// Inserta un asiento rectificativo, que admite importes negativos para restar de su lado lo anotado
+    /// por error (por ejemplo, la factura original con los importes en negativo). Los importes cero se
+    /// siguen rechazando y el asiento queda marcado como [`asiento::TipoAsiento::Rectificativo`].
+    pub fn insertar_rectificativo(&mut self, concepto: &str, fecha: Option<NaiveDate>, debe: Vec<(&str, Moneda)>, haber: Vec<(&str, Moneda)>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        self.insertar(concepto, fecha, debe, haber, asiento::TipoAsiento::Rectificativo, cuadro)
+    }
+
+    /// Inserta un asiento hecho en otra divisa. Los importes de las líneas están en la divisa indicada y se pasan
+    /// a euros con su tasa (euros por unidad de divisa), redondeados a céntimos. El asiento se valida y se cuadra
+    /// en euros como cualquier otro, y cada movimiento conserva su importe original (ver [`movimiento::Movimiento::en_divisa`])
+    pub fn insertar_asiento_en_divisa<'a>(&mut self, concepto: &str, fecha: Option<NaiveDate>, debe: Vec<(&'a str, f64)>, haber: Vec<(&'a str, f64)>, (divisa, tasa): (movimiento::Divisa, f64), cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+
+        let en_euros = |lineas: &[(&'a str, f64)]| lineas
+            .iter()
+            .map(|(codigo_cuenta, importe)| (*codigo_cuenta, Moneda::from_euros(importe * tasa)))
+            .collect::<Anotaciones<'a>>();
+        let mut preparado = self.preparar(concepto, fecha, en_euros(&debe), en_euros(&haber), asiento::TipoAsiento::Normal, cuadro)?;
+
+        // Las cuentas ya se han validado; los movimientos en divisa llevan los mismos euros que los preparados
+        let mut en_divisa = |lineas: &[(&str, f64)]| lineas
+            .iter()
+            .filter_map(|(codigo_cuenta, importe)| cuadro.buscar_cuenta(codigo_cuenta).map(|c| movimiento::Movimiento::en_divisa(*importe, divisa, tasa, c)))
+            .collect::<Vec<movimiento::Movimiento>>();
+        let (movimientos_debe, movimientos_haber) = (en_divisa(&debe), en_divisa(&haber));
+        preparado.asiento.sustituir_movimientos(movimientos_debe, movimientos_haber);
+
+        self.anotar(vec![preparado], cuadro)
+    }
+
+    /// Inserta varios asientos, todos o ninguno: se validan todos antes de anotar el primero.
+    /// Si uno no es válido, devuelve su posición (desde 1) con el error; sin posición si lo que falla es guardarlos
+    pub(crate) fn insertar_varios(&mut self, asientos: Vec<AsientoNuevo>, cuadro: &mut Cuadro) -> Result<(), (Option<usize>, LibroDiarioError)> {
+        let mut preparados = vec![];
+        for (i, nuevo) in asientos.into_iter().enumerate() {
+            let preparado = self.preparar(&nuevo.concepto, nuevo.fecha, nuevo.debe, nuevo.haber, nuevo.tipo, cuadro)
+                .map_err(|e| (Some(i + 1), e))?;
+            preparados.push(preparado);
+        }
+        self.anotar(preparados, cuadro).map_err(|e| (None, e))
+    }
+
+    /// Inserta un asiento del tipo indicado, que decide si admite importes negativos. Lo usan también
+    /// las copias de seguridad y los duplicados, que vuelven a insertar los asientos con su tipo
+    fn insertar(&mut self, concepto: &str, fecha: Option<NaiveDate>, debe: Vec<(&str, Moneda)>, haber: Vec<(&str, Moneda)>, tipo: asiento::TipoAsiento, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        let preparado = self.preparar(concepto, fecha, debe, haber, tipo, cuadro)?;
+        self.anotar(vec![preparado], cuadro)
     }
 
-    /// Crea e inserta un asiento. Este es el punto de conexión entre Libro Diario y Cuadro de Cuentas
-    pub fn insertar_asiento(&mut self, concepto: &str, fecha: Option<NaiveDate>, debe: Vec<(&str, f64)>, haber: Vec<(&str, f64)>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+    /// Valida un asiento y crea sus movimientos sin tocar el libro diario ni los saldos del cuadro.
+    /// Permite comprobar varios asientos que han de entrar juntos (como la regularización y el cierre)
+    /// antes de anotar ninguno.
+    fn preparar<'a>(&self, concepto: &str, fecha: Option<NaiveDate>, mut debe: Anotaciones<'a>, mut haber: Anotaciones<'a>, tipo: asiento::TipoAsiento, cuadro: &mut Cuadro) -> Result<AsientoPreparado<'a>, LibroDiarioError> {
+
+        // Todas las líneas se validan antes de tocar nada, para no dejar el asiento a medio aplicar
+        let lineas = debe.iter().enumerate().map(|(i, l)| (apunte::Lado::Debe, i + 1, l))
+            .chain(haber.iter().enumerate().map(|(i, l)| (apunte::Lado::Haber, i + 1, l)));
+        for (lado, linea, (codigo_cuenta, importe)) in lineas {
+            match cuadro.buscar_cuenta(codigo_cuenta) {
+                None => return Err(LibroDiarioError::CuentaInexistenteEnLinea(lado, linea, codigo_cuenta.to_string())),
+                Some(cuenta) if !cuenta.activa() => return Err(LibroDiarioError::CuentaInactiva(lado, linea, codigo_cuenta.to_string())),
+                Some(_) => (),
+            }
+            // Un apunte de 0 € casi siempre es un error de datos; los negativos solo caben en una rectificativa
+            if *importe == Moneda::CERO || (*importe < Moneda::CERO && tipo != asiento::TipoAsiento::Rectificativo) {
+                return Err(LibroDiarioError::ImporteInvalido(lado, linea, *importe))
+            }
+        }
+
+        // Con el ajuste de redondeo activado, una diferencia de céntimos se cuadra con una línea más.
+        // Se añade después de validar las líneas, para que los errores solo señalen las que ha escrito el usuario;
+        // sus cuentas se crean al activar el ajuste, pero pueden haberse desactivado después
+        let ajuste = self.ajuste_redondeo.and_then(|umbral| redondeo::linea_ajuste(&debe, &haber, umbral));
+        if let Some((lado, codigo_cuenta, diferencia)) = ajuste {
+            let lineas = match lado {
+                apunte::Lado::Debe => &mut debe,
+                apunte::Lado::Haber => &mut haber,
+            };
+            lineas.push((codigo_cuenta, diferencia));
+            match cuadro.buscar_cuenta(codigo_cuenta) {
+                None => return Err(LibroDiarioError::CuentaInexistenteEnLinea(lado, lineas.len(), codigo_cuenta.to_string())),
+                Some(cuenta) if !cuenta.activa() => return Err(LibroDiarioError::CuentaInactiva(lado, lineas.len(), codigo_cuenta.to_string())),
+                Some(_) => (),
+            }
+        }
 
         // Vectores para guardar movimientos de debe y haber
         let mut vec_debe: Vec<movimiento::Movimiento> = vec![];
         let mut vec_haber: Vec<movimiento::Movimiento> = vec![];
 
-        // Busca las cuentas de debe y haber y crea un movimiento copiándolas, además de modificar sus saldos
-        for (codigo_cuenta, importe) in debe.into_iter() {
-            let cuenta = cuadro.buscar_cuenta(codigo_cuenta);
-            if let Some(c) = cuenta {
-                let movimiento = movimiento::Movimiento::new(importe, c);
-                c.saldo_deudor(importe);
-                vec_debe.push(movimiento)
+        // Busca las cuentas de debe y haber y crea un movimiento copiándolas
+        for (codigo_cuenta, importe) in debe.iter() {
+            if let Some(c) = cuadro.buscar_cuenta(codigo_cuenta) {
+                vec_debe.push(movimiento::Movimiento::new(*importe, c))
             }
         }
 
-        for (codigo_cuenta, importe) in haber.into_iter() {
-            let cuenta = cuadro.buscar_cuenta(codigo_cuenta);
-            if let Some(c) = cuenta {
-                let movimiento = movimiento::Movimiento::new(importe, c);
-                c.saldo_acreedor(importe);
-                vec_haber.push(movimiento)
+        for (codigo_cuenta, importe) in haber.iter() {
+            if let Some(c) = cuadro.buscar_cuenta(codigo_cuenta) {
+                vec_haber.push(movimiento::Movimiento::new(*importe, c))
             }
         }
 
         // Crea el asiento
-        let asiento = asiento::Asiento::new(concepto, fecha, vec_debe, vec_haber);
+        let mut asiento = asiento::Asiento::new(concepto, fecha, vec_debe, vec_haber);
+        asiento.fijar_tipo(tipo);
+
+        // Valida antes de tocar ningún saldo, para no dejar las cuentas a medias
+        if !asiento.validar_saldos() {
+            return Err(LibroDiarioError::AsientoDesequilibrado(asiento.comprobacion()))
+        }
+
+        if let Some(ejercicio) = cuadro.ejercicio {
+            if !ejercicio.contiene(asiento.fecha()) {
+                return Err(LibroDiarioError::FechaFueraDeEjercicio(asiento.fecha()))
+            }
+        }
+
+        // Los periodos ya declarados no admiten asientos; las correcciones van al periodo abierto
+        if cuadro.bloqueado_hasta.is_some_and(|bloqueo| asiento.fecha() <= bloqueo) {
+            return Err(LibroDiarioError::PeriodoBloqueado(asiento.fecha()))
+        }
+
+        Ok(AsientoPreparado { asiento, debe, haber, ajuste: ajuste.map(|(_, _, diferencia)| diferencia) })
+    }
+
+    /// Numera y anota en el cuadro y en el libro diario unos asientos ya preparados, todos o ninguno:
+    /// con persistencia se guardan todos antes de tocar ningún saldo
+    fn anotar(&mut self, mut preparados: Vec<AsientoPreparado>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+
+        // Numera los asientos: <FECHA(YYYYMMDD)><Nº de asiento del día>
+        for i in 0..preparados.len() {
+            let fecha = preparados[i].asiento.fecha();
+            let orden = self.asientos.iter().map(|a| a.fecha())
+                .chain(preparados[..i].iter().map(|p| p.asiento.fecha()))
+                .filter(|f| *f == fecha)
+                .count();
+            preparados[i].asiento.numerar(&format!("{}{}", fecha.format("%Y%m%d"), orden));
+        }
+
+        // Con persistencia, los asientos se guardan antes de tocar los saldos: si falla la escritura, no se inserta ninguno
+        if let Some(directorio) = &self.persistencia {
+            persistencia::guardar_asientos(directorio, preparados.iter().map(|p| &p.asiento))?;
+        }
+
+        for AsientoPreparado { asiento, debe, haber, ajuste } in preparados {
+            let fecha = asiento.fecha();
+            let codigo = asiento.codigo();
+
+            // Anota los apuntes en las cuentas, lo que actualiza sus saldos
+            for (codigo_cuenta, importe) in debe.into_iter() {
+                if let Some(c) = cuadro.buscar_cuenta(codigo_cuenta) {
+                    c.anotar(apunte::Apunte::new(fecha, importe, apunte::Lado::Debe, &codigo));
+                }
+            }
+
+            for (codigo_cuenta, importe) in haber.into_iter() {
+                if let Some(c) = cuadro.buscar_cuenta(codigo_cuenta) {
+                    c.anotar(apunte::Apunte::new(fecha, importe, apunte::Lado::Haber, &codigo));
+                }
+            }
+
+            cuadro.registrar(auditoria::Operacion::AsientoInsertado(codigo.clone()));
+            if let Some(diferencia) = ajuste {
+                cuadro.registrar(auditoria::Operacion::AjusteRedondeo { codigo, diferencia });
+            }
 
-        // Valida e inserta
-        if asiento.validar_saldos() {
             // Lo inserta en el Libro Diario
-            self.asientos.push(asiento)
-        } else {
-            return Err(LibroDiarioError::AsientoDesequilibrado)
+            self.asientos.push(asiento);
         }
 
         Ok(())
 
     }
 
+    /// Devuelve los movimientos (del debe y del haber) de una cuenta en los asientos
+    /// cuya fecha está entre `inicio` y `fin`, ambas incluidas
+    pub fn movimientos_de_cuenta_entre(&self, codigo_cuenta: &str, inicio: NaiveDate, fin: NaiveDate) -> Vec<&movimiento::Movimiento> {
+        self.asientos
+            .iter()
+            .filter(|a| a.fecha() >= inicio && a.fecha() <= fin)
+            .flat_map(|a| a.debe().iter().chain(a.haber().iter()))
+            .filter(|m| m.codigo_cuenta() == codigo_cuenta)
+            .collect()
+    }
+
+    /// Devuelve las parejas de asientos que parecen duplicados: mismo concepto, fecha e importes
+    /// en el debe y en el haber. No elimina nada; solo sirve para revisarlos a mano.
+    pub fn detectar_duplicados(&self) -> Vec<(&asiento::Asiento, &asiento::Asiento)> {
+        let mut duplicados = vec![];
+        for (i, asiento) in self.asientos.iter().enumerate() {
+            for otro in &self.asientos[i + 1..] {
+                if asiento.es_duplicado_de(otro) {
+                    duplicados.push((asiento, otro));
+                }
+            }
+        }
+        duplicados
+    }
+
+    /// Imprime todos los asientos del libro diario y, al pie, el total del debe y del haber.
+    /// Con `por_meses`, los agrupa por mes con una cabecera y un subtotal (ver [`LibroDiario::libro_diario_por_meses`])
+    pub fn print_libro_diario(&self, por_meses: bool) {
+        if por_meses {
+            print!("{}", self.libro_diario_por_meses());
+        } else {
+            print!("{}", self);
+        }
+    }
+
+    /// Devuelve el libro diario en orden de fecha agrupado por meses: cada mes empieza con una cabecera
+    /// "=== Enero 2024 ===" y termina con el subtotal del debe y del haber de sus asientos.
+    /// Al pie va el total de todo el libro, como en el formato normal
+    pub fn libro_diario_por_meses(&self) -> String {
+
+        let mut salida = String::new();
+        let mut mes_en_curso: Option<(i32, u32)> = None;
+        let mut subtotal = (Moneda::CERO, Moneda::CERO);
+
+        let subtotal_mes = |(anio, mes): (i32, u32), (debe, haber): (Moneda, Moneda)| {
+            format!("Subtotal {} {}: DEBE {} | HABER {}\n\n", formato::nombre_mes(mes), anio, formato::formato_importe(debe), formato::formato_importe(haber))
+        };
+
+        for asiento in self.asientos_ordenados() {
+            let mes = (asiento.fecha().year(), asiento.fecha().month());
+            if mes_en_curso != Some(mes) {
+                if let Some(anterior) = mes_en_curso {
+                    salida.push_str(&subtotal_mes(anterior, subtotal));
+                }
+                salida.push_str(&format!("=== {} {} ===\n", formato::nombre_mes(mes.1), mes.0));
+                mes_en_curso = Some(mes);
+                subtotal = (Moneda::CERO, Moneda::CERO);
+            }
+            salida.push_str(&asiento.to_string());
+            subtotal.0 += asiento.total_debe();
+            subtotal.1 += asiento.total_haber();
+        }
+        if let Some(ultimo) = mes_en_curso {
+            salida.push_str(&subtotal_mes(ultimo, subtotal));
+        }
+
+        salida.push_str(&self.linea_totales());
+        salida
+    }
+
+    /// Imprime el libro diario en páginas de `por_pagina` asientos (ver [`LibroDiario::paginas`])
+    pub fn print_libro_diario_paginado(&self, por_pagina: usize) {
+        print!("{}", self.paginas(por_pagina).concat());
+    }
+
+    /// Divide el libro diario en páginas de hasta `por_pagina` asientos, en su orden formal.
+    /// Cada página repite la cabecera de columnas y termina con el pie "Página X de Y";
+    /// la última lleva además el total del debe y del haber. Los cortes caen siempre entre asientos.
+    pub fn paginas(&self, por_pagina: usize) -> Vec<String> {
+
+        let w = asiento::Asiento::ANCHO;
+        let col = asiento::Asiento::ANCHO_COLUMNA;
+        let asientos = self.asientos_ordenados();
+        let bloques: Vec<&[&asiento::Asiento]> = if asientos.is_empty() {
+            vec![&[]]
+        } else {
+            asientos.chunks(por_pagina.max(1)).collect()
+        };
+
+        bloques.iter().enumerate().map(|(i, bloque)| {
+            let mut pagina = format!("+{:-^width$}+\n", "", width=w - 2);
+            pagina.push_str(&format!("|{:^width$}|\n", "LIBRO DIARIO", width=w - 2));
+            pagina.push_str(&format!("+{:-<col$}+{:-<col$}+\n", "", ""));
+            pagina.push_str(&format!("|{:^col$}|{:^col$}|\n", "DEBE", "HABER"));
+            pagina.push_str(&format!("+{:-<col$}+{:-<col$}+\n", "", ""));
+            for asiento in bloque.iter() {
+                pagina.push_str(&asiento.to_string());
+            }
+            if i + 1 == bloques.len() {
+                pagina.push_str(&self.linea_totales());
+            }
+            pagina.push_str(&format!("{:^width$}\n\n", format!("Página {} de {}", i + 1, bloques.len()), width=w));
+            pagina
+        }).collect()
+    }
+
+    /// Línea con el total del debe y del haber de todo el libro, en rojo si no cuadran
+    fn linea_totales(&self) -> String {
+        let total_debe: Moneda = self.asientos.iter().map(|a| a.total_debe()).sum();
+        let total_haber: Moneda = self.asientos.iter().map(|a| a.total_haber()).sum();
+
+        let totales = format!("TOTAL DEBE: {} | TOTAL HABER: {}", formato::formato_importe(total_debe), formato::formato_importe(total_haber));
+
+        if total_debe == total_haber {
+            format!("{}\n", totales)
+        } else {
+            format!("\x1b[31m{} ¡NO CUADRAN!\x1b[0m\n", totales)
+        }
+    }
+
 }
 
+impl Display for LibroDiario {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+
+        for asiento in self.asientos_ordenados() {
+            write!(f, "{}", asiento)?;
+        }
+
+        write!(f, "{}", self.linea_totales())
+    }
+}
+
+impl Default for LibroDiario {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 #[cfg(test)]
 mod libro_diario_tests {
@@ -248,6 +1652,318 @@ mod libro_diario_tests {
         cuadro
     }
 
+    #[test]
+    fn asientos_recorre_los_asientos_en_orden_de_insercion() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Primero", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", None, vec![("0001", Moneda::from_euros(5.0))], vec![("0000", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let totales: Vec<Moneda> = libro_diario.asientos().map(|a| a.total_debe()).collect();
+
+        assert_eq!(totales, vec![Moneda::from_euros(20.0), Moneda::from_euros(5.0)]);
+    }
+
+    #[test]
+    fn insertar_asiento_en_divisa_cuadra_en_euros_y_conserva_el_importe_original() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        libro_diario.insertar_asiento_en_divisa("Compra en dólares", None, vec![("0000", 100.0)], vec![("0001", 100.0)], (movimiento::Divisa::Usd, 0.92), &mut cuadro).unwrap();
+
+        let asiento = libro_diario.asientos().next().unwrap();
+        assert_eq!(asiento.total_debe(), Moneda::from_euros(92.0));
+        assert_eq!(asiento.debe()[0].divisa(), movimiento::Divisa::Usd);
+        assert_eq!(asiento.haber()[0].importe_divisa(), 100.0);
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(92.0));
+        assert!(asiento.to_string().contains("(100,00 USD) 92,00 €"));
+        assert!(asiento.debe()[0].to_string().ends_with("92,00 € (100,00 USD)"));
+
+        // Una cuenta que no existe se rechaza como en cualquier otro asiento
+        let error = libro_diario.insertar_asiento_en_divisa("Compra", None, vec![("9999", 10.0)], vec![("0001", 10.0)], (movimiento::Divisa::Gbp, 1.15), &mut cuadro);
+        assert_eq!(error, Err(LibroDiarioError::CuentaInexistenteEnLinea(apunte::Lado::Debe, 1, "9999".to_string())));
+        assert_eq!(libro_diario.asientos().count(), 1);
+    }
+
+    #[test]
+    fn asientos_ordenados_pone_la_apertura_primero_y_el_cierre_al_final_de_su_dia() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = |a, m, d| Some(NaiveDate::from_ymd_opt(a, m, d).unwrap());
+        libro_diario.insertar_asiento("Cierre", fecha(2023, 12, 31), vec![("0001", Moneda::from_euros(20.0))], vec![("0000", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Aportación", fecha(2023, 12, 31), vec![("0000", Moneda::from_euros(5.0))], vec![("0001", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", fecha(2023, 1, 1), vec![("0000", Moneda::from_euros(8.0))], vec![("0002", Moneda::from_euros(8.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Apertura", fecha(2023, 1, 1), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.marcar_tipo("202312310", asiento::TipoAsiento::Cierre).unwrap();
+        libro_diario.marcar_tipo("202301011", asiento::TipoAsiento::Apertura).unwrap();
+
+        let conceptos: Vec<String> = libro_diario.asientos_ordenados().iter().map(|a| a.concepto()).collect();
+
+        assert_eq!(conceptos, vec!["Apertura", "Compra", "Aportación", "Cierre"]);
+        assert!(libro_diario.to_string().find("Apertura").unwrap() < libro_diario.to_string().find("Compra").unwrap());
+        assert_eq!(
+            libro_diario.marcar_tipo("202308099", asiento::TipoAsiento::Cierre),
+            Err(LibroDiarioError::AsientoInexistente("202308099".to_string()))
+        );
+    }
+
+    #[test]
+    fn paginas_corta_entre_asientos_y_numera_las_paginas() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        for importe in [10.0, 20.0, 30.0, 40.0, 50.0] {
+            libro_diario.insertar_asiento("Aportación", None, vec![("0000", Moneda::from_euros(importe))], vec![("0001", Moneda::from_euros(importe))], &mut cuadro).unwrap();
+        }
+
+        let paginas = libro_diario.paginas(2);
+
+        assert_eq!(paginas.len(), 3);
+        assert_eq!(paginas.iter().map(|p| p.matches("Aportación").count()).collect::<Vec<usize>>(), vec![2, 2, 1]);
+        assert!(paginas.iter().all(|p| p.contains("LIBRO DIARIO")));
+        assert!(paginas[0].trim_end().ends_with("Página 1 de 3"));
+        assert!(paginas[2].trim_end().ends_with("Página 3 de 3"));
+        assert!(!paginas[0].contains("TOTAL DEBE"));
+        assert!(paginas[2].contains("TOTAL DEBE: 150,00 € | TOTAL HABER: 150,00 €"));
+        assert_eq!(LibroDiario::new().paginas(2).len(), 1);
+    }
+
+    #[test]
+    fn libro_diario_por_meses_separa_los_meses_con_subtotales() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = |a, m, d| Some(NaiveDate::from_ymd_opt(a, m, d).unwrap());
+        libro_diario.insertar_asiento("Marzo", fecha(2024, 3, 2), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Enero", fecha(2024, 1, 15), vec![("0000", Moneda::from_euros(10.0))], vec![("0001", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Enero", fecha(2024, 1, 20), vec![("0001", Moneda::from_euros(5.0))], vec![("0000", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let texto = libro_diario.libro_diario_por_meses();
+        let posicion = |buscado: &str| texto.find(buscado).unwrap();
+
+        assert!(posicion("=== Enero 2024 ===") < posicion("Subtotal Enero 2024: DEBE 15,00 € | HABER 15,00 €"));
+        assert!(posicion("Subtotal Enero 2024") < posicion("=== Marzo 2024 ==="));
+        assert!(posicion("=== Marzo 2024 ===") < posicion("Subtotal Marzo 2024: DEBE 20,00 € | HABER 20,00 €"));
+        assert!(!texto.contains("Febrero"));
+        assert!(texto.ends_with("TOTAL DEBE: 35,00 € | TOTAL HABER: 35,00 €\n"));
+    }
+
+    #[test]
+    fn resumen_mensual_agrupa_por_mes_en_orden_cronologico() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = |a, m, d| Some(NaiveDate::from_ymd_opt(a, m, d).unwrap());
+        libro_diario.insertar_asiento("Marzo", fecha(2023, 3, 2), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Enero", fecha(2023, 1, 15), vec![("0000", Moneda::from_euros(10.0))], vec![("0001", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Marzo", fecha(2023, 3, 20), vec![("0001", Moneda::from_euros(5.0))], vec![("0000", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let resumen: Vec<((i32, u32), (usize, Moneda))> = libro_diario.resumen_mensual().into_iter().collect();
+
+        assert_eq!(resumen, vec![((2023, 1), (1, Moneda::from_euros(10.0))), ((2023, 3), (2, Moneda::from_euros(25.0)))]);
+    }
+
+    #[test]
+    fn asientos_de_tercero_filtra_por_el_tercero_asignado() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = Some(NaiveDate::from_ymd_opt(2023, 8, 9).unwrap());
+        libro_diario.insertar_asiento("Compra", fecha, vec![("0000", Moneda::from_euros(20.0))], vec![("0002", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Aportación", fecha, vec![("0000", Moneda::from_euros(5.0))], vec![("0001", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", fecha, vec![("0000", Moneda::from_euros(8.0))], vec![("0002", Moneda::from_euros(8.0))], &mut cuadro).unwrap();
+
+        libro_diario.asignar_tercero("202308090", "Proveedor A").unwrap();
+        libro_diario.asignar_tercero("202308092", "Proveedor A").unwrap();
+
+        let asientos = libro_diario.asientos_de_tercero("Proveedor A");
+        assert_eq!(asientos.iter().map(|a| a.total_debe()).collect::<Vec<Moneda>>(), vec![Moneda::from_euros(20.0), Moneda::from_euros(8.0)]);
+        assert!(libro_diario.asientos_de_tercero("Proveedor B").is_empty());
+        assert_eq!(
+            libro_diario.asignar_tercero("202308099", "Proveedor A"),
+            Err(LibroDiarioError::AsientoInexistente("202308099".to_string()))
+        );
+    }
+
+    #[test]
+    fn insertar_asiento_rechaza_importes_cero_o_negativos_indicando_la_linea() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        assert_eq!(
+            libro_diario.insertar_asiento("Abono", None, vec![("0000", Moneda::from_euros(-30.0))], vec![("0002", Moneda::from_euros(-30.0))], &mut cuadro),
+            Err(LibroDiarioError::ImporteInvalido(apunte::Lado::Debe, 1, Moneda::from_euros(-30.0)))
+        );
+        assert_eq!(
+            libro_diario.insertar_asiento("Factura", None, vec![("0000", Moneda::from_euros(100.0))], vec![("0002", Moneda::from_euros(100.0)), ("0001", Moneda::from_euros(0.0))], &mut cuadro),
+            Err(LibroDiarioError::ImporteInvalido(apunte::Lado::Haber, 2, Moneda::from_euros(0.0)))
+        );
+        assert!(libro_diario.asientos.is_empty());
+        assert!(cuadro.cuentas().all(|c| c.apuntes().is_empty()));
+        assert_eq!(
+            LibroDiarioError::ImporteInvalido(apunte::Lado::Haber, 2, Moneda::from_euros(0.0)).to_string(),
+            "el importe de la línea 2 del haber es cero"
+        );
+    }
+
+    #[test]
+    fn insertar_rectificativo_admite_negativos_que_restan_de_su_lado() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Factura", None, vec![("0000", Moneda::from_euros(100.0))], vec![("0002", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+
+        // Rectificativa parcial en el mismo lado que la original
+        libro_diario.insertar_rectificativo("Abono", None, vec![("0000", Moneda::from_euros(-30.0))], vec![("0002", Moneda::from_euros(-30.0))], &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(70.0));
+        assert_eq!(cuadro.buscar_cuenta("0002").unwrap().saldo(), Moneda::from_euros(-70.0));
+        assert_eq!(cuadro.verificar_ecuacion(), Ok(()));
+        assert_eq!(libro_diario.asientos.last().unwrap().tipo(), asiento::TipoAsiento::Rectificativo);
+
+        // Un negativo en el debe no cuadra con el mismo importe positivo en el haber, y el cero nunca vale
+        assert_eq!(
+            libro_diario.insertar_rectificativo("Mal", None, vec![("0000", Moneda::from_euros(-30.0))], vec![("0002", Moneda::from_euros(30.0))], &mut cuadro),
+            Err(LibroDiarioError::AsientoDesequilibrado(Moneda::from_euros(-60.0)))
+        );
+        assert_eq!(
+            libro_diario.insertar_rectificativo("Mal", None, vec![("0000", Moneda::from_euros(0.0))], vec![("0002", Moneda::from_euros(0.0))], &mut cuadro),
+            Err(LibroDiarioError::ImporteInvalido(apunte::Lado::Debe, 1, Moneda::from_euros(0.0)))
+        );
+    }
+
+    #[test]
+    fn duplicar_asiento_copia_los_movimientos_en_la_nueva_fecha() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        let nueva_fecha = NaiveDate::from_ymd_opt(2023, 9, 9).unwrap();
+        libro_diario.insertar_asiento("Alquiler", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(15.0)), ("0002", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        libro_diario.duplicar_asiento("202308090", nueva_fecha, &mut cuadro).unwrap();
+
+        let copia = libro_diario.asientos().last().unwrap();
+        assert_eq!(copia.codigo(), "202309090");
+        assert_eq!(copia.fecha(), nueva_fecha);
+        assert_eq!(copia.concepto(), "Alquiler");
+        assert_eq!(copia.haber().len(), 2);
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(40.0));
+        assert_eq!(
+            libro_diario.duplicar_asiento("202308099", nueva_fecha, &mut cuadro),
+            Err(LibroDiarioError::AsientoInexistente("202308099".to_string()))
+        );
+    }
+
+    #[test]
+    fn duplicar_asiento_de_una_rectificativa_admite_sus_importes_negativos() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        libro_diario.insertar_asiento("Factura", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_rectificativo("Abono", Some(fecha), vec![("0000", Moneda::from_euros(-5.0))], vec![("0001", Moneda::from_euros(-5.0))], &mut cuadro).unwrap();
+
+        libro_diario.duplicar_asiento("202308091", NaiveDate::from_ymd_opt(2023, 9, 9).unwrap(), &mut cuadro).unwrap();
+        libro_diario.duplicar_asiento("202308090", NaiveDate::from_ymd_opt(2023, 9, 9).unwrap(), &mut cuadro).unwrap();
+
+        let tipos: Vec<asiento::TipoAsiento> = libro_diario.asientos().skip(2).map(|a| a.tipo()).collect();
+        assert_eq!(tipos, vec![asiento::TipoAsiento::Rectificativo, asiento::TipoAsiento::Normal]);
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(30.0));
+    }
+
+    #[test]
+    fn asientos_con_cuenta_incluye_debe_haber_y_subcuentas() {
+        let mut cuadro = setup_cuadro();
+        cuadro.crear_cuenta("test3", "00020", masa::Masa::PasivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Primero", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", None, vec![("0000", Moneda::from_euros(5.0))], vec![("0002", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Tercero", None, vec![("00020", Moneda::from_euros(7.0))], vec![("0000", Moneda::from_euros(7.0))], &mut cuadro).unwrap();
+
+        let totales = |asientos: Vec<&asiento::Asiento>| asientos.iter().map(|a| a.total_debe()).collect::<Vec<Moneda>>();
+
+        assert_eq!(totales(libro_diario.asientos_con_cuenta("0000")), vec![Moneda::from_euros(20.0), Moneda::from_euros(5.0), Moneda::from_euros(7.0)]);
+        assert_eq!(totales(libro_diario.asientos_con_cuenta("0002")), vec![Moneda::from_euros(5.0), Moneda::from_euros(7.0)]);
+        assert_eq!(totales(libro_diario.asientos_con_cuenta("0001")), vec![Moneda::from_euros(20.0)]);
+        assert!(libro_diario.asientos_con_cuenta("9").is_empty());
+    }
+
+    #[test]
+    fn insertar_asiento_con_cuenta_inexistente_no_modifica_nada() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        let insercion = libro_diario.insertar_asiento("Con error", None, vec![("0000", Moneda::from_euros(20.0)), ("9999", Moneda::from_euros(5.0))], vec![("0001", Moneda::from_euros(25.0))], &mut cuadro);
+
+        assert_eq!(insercion, Err(LibroDiarioError::CuentaInexistenteEnLinea(apunte::Lado::Debe, 2, "9999".to_string())));
+        assert!(libro_diario.asientos.is_empty());
+        assert!(cuadro.cuentas().all(|c| c.apuntes().is_empty() && c.saldo() == Moneda::from_euros(0.0)));
+    }
+
+    #[test]
+    fn insertar_asiento_rechaza_cuentas_desactivadas() {
+        let mut cuadro = setup_cuadro();
+        cuadro.desactivar_cuenta("0002").unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        let insercion = libro_diario.insertar_asiento("En cuenta oculta", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(15.0)), ("0002", Moneda::from_euros(5.0))], &mut cuadro);
+
+        assert_eq!(insercion, Err(LibroDiarioError::CuentaInactiva(apunte::Lado::Haber, 2, "0002".to_string())));
+        assert!(libro_diario.asientos.is_empty());
+        assert!(cuadro.cuentas().all(|c| c.apuntes().is_empty()));
+
+        cuadro.activar_cuenta("0002").unwrap();
+        assert!(libro_diario.insertar_asiento("En cuenta activa", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(15.0)), ("0002", Moneda::from_euros(5.0))], &mut cuadro).is_ok());
+    }
+
+    #[test]
+    fn asientos_posteriores_a_lista_sin_rechazar_los_posteriores_a_la_fecha() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        libro_diario.insertar_asiento("Ese día", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Al día siguiente", fecha.succ_opt(), vec![("0000", Moneda::from_euros(5.0))], vec![("0001", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let posteriores = libro_diario.asientos_posteriores_a(fecha);
+
+        assert_eq!(posteriores.len(), 1);
+        assert_eq!(posteriores[0].fecha(), fecha.succ_opt().unwrap());
+        assert_eq!(libro_diario.asientos_posteriores_a(fecha.pred_opt().unwrap()).len(), 2);
+        assert!(libro_diario.asientos_posteriores_a(fecha.succ_opt().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn insertar_asiento_rechaza_fechas_de_periodos_bloqueados() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let cierre_trimestre = NaiveDate::from_ymd_opt(2023, 3, 31).unwrap();
+        cuadro.bloquear_hasta(cierre_trimestre);
+
+        let insercion = libro_diario.insertar_asiento("Tardío", Some(cierre_trimestre), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro);
+
+        assert_eq!(insercion, Err(LibroDiarioError::PeriodoBloqueado(cierre_trimestre)));
+        assert!(libro_diario.asientos.is_empty());
+        assert_eq!(cuadro.bloqueado_hasta(), Some(cierre_trimestre));
+
+        let abierto = cierre_trimestre.succ_opt().unwrap();
+        assert!(libro_diario.insertar_asiento("Corrección", Some(abierto), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).is_ok());
+        assert_eq!(
+            libro_diario.duplicar_asiento("202304010", cierre_trimestre, &mut cuadro),
+            Err(LibroDiarioError::PeriodoBloqueado(cierre_trimestre))
+        );
+    }
+
+    #[test]
+    fn insertar_asiento_rechaza_fechas_fuera_del_ejercicio() {
+        let mut cuadro = setup_cuadro();
+        cuadro.establecer_ejercicio(ejercicio::Ejercicio::natural(2023));
+        let mut libro_diario = LibroDiario::new();
+        let anterior = NaiveDate::from_ymd_opt(2022, 12, 31).unwrap();
+
+        let insercion = libro_diario.insertar_asiento("Del año pasado", Some(anterior), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro);
+
+        assert_eq!(insercion, Err(LibroDiarioError::FechaFueraDeEjercicio(anterior)));
+        assert!(libro_diario.asientos.is_empty());
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(0.0));
+
+        let dentro = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        assert!(libro_diario.insertar_asiento("Del año", Some(dentro), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).is_ok());
+    }
+
     #[test]
     fn insertar_asiento_crea_asiento_y_modifica_las_cuentas() {
         let mut cuadro = setup_cuadro();
@@ -256,8 +1972,8 @@ mod libro_diario_tests {
         let insercion = libro_diario.insertar_asiento(
             "Primer asiento", 
             None, 
-            vec![("0000", 20.0)],
-            vec![("0001", 20.0)], 
+            vec![("0000", Moneda::from_euros(20.0))],
+            vec![("0001", Moneda::from_euros(20.0))], 
             &mut cuadro
         );
 
@@ -266,17 +1982,90 @@ mod libro_diario_tests {
 
         let cuenta0000 = cuadro.buscar_cuenta("0000");
         assert!( match cuenta0000 {
-            Some(v) => {assert_eq!(v.saldo(), 20.00); true},
+            Some(v) => {assert_eq!(v.saldo(), Moneda::from_euros(20.00)); true},
             None => false
         });
         let cuenta0001 = cuadro.buscar_cuenta("0001");
         assert!( match cuenta0001 {
-            Some(v) => {assert_eq!(v.saldo(), -20.00); true},
+            Some(v) => {assert_eq!(v.saldo(), Moneda::from_euros(-20.00)); true},
             None => false
         })
 
     }
 
+    #[test]
+    fn insertar_asiento_numera_y_guarda_los_apuntes_en_las_cuentas() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+
+        libro_diario.insertar_asiento("Primero", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", Some(fecha), vec![("0000", Moneda::from_euros(5.0))], vec![("0002", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        assert!(libro_diario.asientos[1].to_string().contains("N.º 202308091"));
+
+        let apuntes = cuadro.buscar_cuenta("0000").unwrap().apuntes().to_vec();
+        assert_eq!(apuntes, vec![
+            apunte::Apunte::new(fecha, Moneda::from_euros(20.0), apunte::Lado::Debe, "202308090"),
+            apunte::Apunte::new(fecha, Moneda::from_euros(5.0), apunte::Lado::Debe, "202308091"),
+        ]);
+        assert_eq!(cuadro.buscar_cuenta("0002").unwrap().apuntes()[0].lado(), apunte::Lado::Haber);
+    }
+
+    #[test]
+    fn movimientos_de_cuenta_entre_filtra_por_fecha_y_cuenta() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = |d| NaiveDate::from_ymd_opt(2023, 8, d).unwrap();
+
+        libro_diario.insertar_asiento("Primero", Some(fecha(1)), vec![("0000", Moneda::from_euros(10.0))], vec![("0001", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", Some(fecha(10)), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Tercero", Some(fecha(20)), vec![("0002", Moneda::from_euros(30.0))], vec![("0000", Moneda::from_euros(30.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Cuarto", Some(fecha(31)), vec![("0000", Moneda::from_euros(40.0))], vec![("0001", Moneda::from_euros(40.0))], &mut cuadro).unwrap();
+
+        let importes: Vec<Moneda> = libro_diario
+            .movimientos_de_cuenta_entre("0000", fecha(10), fecha(20))
+            .iter()
+            .map(|m| m.importe())
+            .collect();
+
+        assert_eq!(importes, vec![Moneda::from_euros(20.0), Moneda::from_euros(30.0)]);
+        assert!(libro_diario.movimientos_de_cuenta_entre("0002", fecha(1), fecha(10)).is_empty());
+    }
+
+    #[test]
+    fn display_muestra_los_totales_del_diario_al_pie() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        libro_diario.insertar_asiento("Primero", None, vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(20.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Segundo", None, vec![("0000", Moneda::from_euros(5.5))], vec![("0002", Moneda::from_euros(5.5))], &mut cuadro).unwrap();
+
+        let texto = libro_diario.to_string();
+
+        assert!(texto.contains("Primero") && texto.contains("Segundo"));
+        assert!(texto.ends_with("TOTAL DEBE: 25,50 € | TOTAL HABER: 25,50 €\n"));
+    }
+
+    #[test]
+    fn detectar_duplicados_encuentra_asientos_repetidos() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+
+        libro_diario.insertar_asiento("Alquiler", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(15.0)), ("0002", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Alquiler", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0002", Moneda::from_euros(5.0)), ("0001", Moneda::from_euros(15.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Alquiler", Some(fecha), vec![("0000", Moneda::from_euros(21.0))], vec![("0001", Moneda::from_euros(21.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Otro", Some(fecha), vec![("0000", Moneda::from_euros(20.0))], vec![("0001", Moneda::from_euros(15.0)), ("0002", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let duplicados = libro_diario.detectar_duplicados();
+
+        assert_eq!(duplicados.len(), 1);
+        assert_eq!(duplicados[0].0.codigo(), "202308090");
+        assert_eq!(duplicados[0].1.codigo(), "202308091");
+        assert_eq!(libro_diario.asientos.len(), 4);
+    }
+
     #[test]
     fn insertar_asiento_mal_formado_falla() {
         let mut cuadro = setup_cuadro();
@@ -285,12 +2074,31 @@ mod libro_diario_tests {
         let insercion = libro_diario.insertar_asiento(
             "Primer asiento", 
             None, 
-            vec![("0000", 20.0)],
-            vec![("0001", 22.0)], 
+            vec![("0000", Moneda::from_euros(20.0))],
+            vec![("0001", Moneda::from_euros(22.0))], 
+            &mut cuadro
+        );
+
+        assert!(insercion.is_err());
+        assert_eq!(insercion, Err(LibroDiarioError::AsientoDesequilibrado(Moneda::from_euros(-2.0))));
+    }
+
+    #[test]
+    fn insertar_asiento_mal_formado_no_modifica_saldos() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        let insercion = libro_diario.insertar_asiento(
+            "Primer asiento", 
+            None, 
+            vec![("0000", Moneda::from_euros(20.0))],
+            vec![("0001", Moneda::from_euros(22.0))], 
             &mut cuadro
         );
 
         assert!(insercion.is_err());
-        assert_eq!(insercion, Err(LibroDiarioError::AsientoDesequilibrado));
+        assert!(libro_diario.asientos.is_empty());
+        assert_eq!(cuadro.buscar_cuenta("0000").unwrap().saldo(), Moneda::from_euros(0.00));
+        assert_eq!(cuadro.buscar_cuenta("0001").unwrap().saldo(), Moneda::from_euros(0.00));
     }
 }
\ No newline at end of file
@@ -0,0 +1,295 @@
+use std::fmt::Display;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use super::formato::redondear_a;
+
+/// Importe en euros guardado como un número entero de la unidad más pequeña de su precisión, para que
+/// las sumas de saldos y asientos sean exactas (en `f64`, 0.1 + 0.2 no es 0.3).
+/// La precisión son los decimales que se guardan: por defecto dos, es decir, céntimos, que es la de la
+/// contabilidad oficial; los cálculos auxiliares, como los precios unitarios, pueden usar más (`Moneda<4>`).
+/// Como la precisión forma parte del tipo, solo se pueden sumar, restar y comparar importes de la misma
+/// precisión; para pasar de una a otra está [`Moneda::con_decimales`].
+/// Las operaciones (`+`, `-`, negación, multiplicar por un entero y `sum`) comprueban el desbordamiento
+/// y, si el resultado no cabe en un `i64`, paran el programa con un mensaje tanto en depuración como en
+/// release: un importe que da la vuelta pasaría por un saldo válido. Para tratarlo sin parar están
+/// [`Moneda::checked_add`] y [`Moneda::checked_sub`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Moneda<const DECIMALES: u32 = 2>(i64);
+
+impl Moneda {
+
+    /// Cero euros. En otras precisiones, `Moneda::default()`
+    pub const CERO: Moneda = Moneda(0);
+
+    /// Crea un importe a partir de un número de céntimos
+    pub const fn from_centimos(centimos: i64) -> Moneda {
+        Moneda(centimos)
+    }
+
+    /// Crea un importe a partir de euros, redondeado a céntimos con redondeo bancario
+    pub fn from_euros(euros: f64) -> Moneda {
+        Moneda::from_f64(euros)
+    }
+
+    /// Devuelve el importe en euros
+    pub fn as_euros(&self) -> f64 {
+        self.as_f64()
+    }
+
+    /// Devuelve el importe en céntimos
+    pub fn centimos(&self) -> i64 {
+        self.0
+    }
+}
+
+impl<const DECIMALES: u32> Moneda<DECIMALES> {
+
+    /// Número de unidades que hay en un euro con esta precisión (100 con dos decimales)
+    const FACTOR: i64 = 10i64.pow(DECIMALES);
+
+    /// Crea un importe a partir de un número de unidades de su precisión (céntimos con dos decimales,
+    /// diezmilésimas de euro con cuatro)
+    pub const fn from_unidades(unidades: i64) -> Moneda<DECIMALES> {
+        Moneda(unidades)
+    }
+
+    /// Crea un importe a partir de euros, redondeado a su precisión con redondeo bancario
+    pub fn from_f64(euros: f64) -> Moneda<DECIMALES> {
+        Moneda((redondear_a(euros, DECIMALES) * Self::FACTOR as f64).round() as i64)
+    }
+
+    /// Devuelve el importe en euros, con todos sus decimales
+    pub fn as_f64(&self) -> f64 {
+        self.0 as f64 / Self::FACTOR as f64
+    }
+
+    /// Devuelve el importe en unidades de su precisión
+    pub fn unidades(&self) -> i64 {
+        self.0
+    }
+
+    /// Devuelve el número de decimales que guarda el importe
+    pub fn decimales(&self) -> u32 {
+        DECIMALES
+    }
+
+    /// Devuelve el importe sin signo
+    pub fn abs(&self) -> Moneda<DECIMALES> {
+        Moneda(desbordado(self.0.checked_abs()))
+    }
+
+    /// Suma dos importes, o `None` si el resultado se desborda
+    pub fn checked_add(self, otra: Moneda<DECIMALES>) -> Option<Moneda<DECIMALES>> {
+        self.0.checked_add(otra.0).map(Moneda)
+    }
+
+    /// Resta dos importes, o `None` si el resultado se desborda
+    pub fn checked_sub(self, otra: Moneda<DECIMALES>) -> Option<Moneda<DECIMALES>> {
+        self.0.checked_sub(otra.0).map(Moneda)
+    }
+
+    /// Pasa el importe a otra precisión. Si tiene menos decimales, se redondea con redondeo bancario,
+    /// como al crear el importe desde euros (0,12345 € a cuatro decimales es 0,1234 €)
+    pub fn con_decimales<const OTROS: u32>(self) -> Moneda<OTROS> {
+        if OTROS >= DECIMALES {
+            return Moneda(desbordado(self.0.checked_mul(10i64.pow(OTROS - DECIMALES))))
+        }
+
+        let divisor = 10i64.pow(DECIMALES - OTROS);
+        let (cociente, resto) = (self.0.div_euclid(divisor), self.0.rem_euclid(divisor));
+
+        // El empate va a la unidad par
+        if resto * 2 > divisor || (resto * 2 == divisor && cociente % 2 != 0) {
+            Moneda(cociente + 1)
+        } else {
+            Moneda(cociente)
+        }
+    }
+}
+
+/// Resultado de una operación comprobada; si se ha desbordado, para el programa (ver [`Moneda`])
+fn desbordado(resultado: Option<i64>) -> i64 {
+    resultado.expect("el importe se sale del rango de Moneda")
+}
+
+impl<const DECIMALES: u32> Display for Moneda<DECIMALES> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let signo = if self.0 < 0 { "-" } else { "" };
+        let unidades = self.0.unsigned_abs();
+        let factor = Self::FACTOR as u64;
+        let texto = match DECIMALES {
+            0 => format!("{}{} €", signo, unidades),
+            _ => format!("{}{}.{:0ancho$} €", signo, unidades / factor, unidades % factor, ancho = DECIMALES as usize),
+        };
+        f.pad(&texto)
+    }
+}
+
+impl<const DECIMALES: u32> FromStr for Moneda<DECIMALES> {
+    type Err = String;
+
+    /// Interpreta un importe escrito con punto decimal ("15.50") o con coma decimal ("15,50" o "1.234,56")
+    /// directamente en unidades de su precisión, sin pasar por `f64`. Si tiene más decimales de los que guarda,
+    /// se redondea con redondeo bancario. Si no es un importe, devuelve el texto recibido
+    fn from_str(texto: &str) -> Result<Self, Self::Err> {
+        let error = || texto.to_string();
+        let limpio = texto.trim();
+
+        let (negativo, cifras) = match limpio.strip_prefix('-') {
+            Some(resto) => (true, resto),
+            None => (false, limpio.strip_prefix('+').unwrap_or(limpio)),
+        };
+
+        // Con coma decimal, los puntos son separadores de miles
+        let cifras = if cifras.contains(',') { cifras.replace('.', "").replacen(',', ".", 1) } else { cifras.to_string() };
+        let (entera, decimal) = cifras.split_once('.').unwrap_or((&cifras, ""));
+
+        let solo_cifras = |parte: &str| parte.chars().all(|c| c.is_ascii_digit());
+        if (entera.is_empty() && decimal.is_empty()) || !solo_cifras(entera) || !solo_cifras(decimal) {
+            return Err(error());
+        }
+
+        let euros: i64 = if entera.is_empty() { 0 } else { entera.parse().map_err(|_| error())? };
+        let (fraccion, resto) = decimal.split_at(decimal.len().min(DECIMALES as usize));
+        let fraccion: i64 = match DECIMALES {
+            0 => 0,
+            _ => format!("{:0<ancho$}", fraccion, ancho = DECIMALES as usize).parse().map_err(|_| error())?,
+        };
+        let mut total = euros
+            .checked_mul(Self::FACTOR)
+            .and_then(|e| e.checked_add(fraccion))
+            .ok_or_else(error)?;
+
+        // Lo que pasa de la última unidad: por encima de la media unidad sube, y el empate va a la unidad par
+        let primera = resto.chars().next().unwrap_or('0');
+        let medio_exacto = primera == '5' && resto.chars().skip(1).all(|c| c == '0');
+        if primera > '5' || (primera == '5' && !medio_exacto) || (medio_exacto && total % 2 == 1) {
+            total = total.checked_add(1).ok_or_else(error)?;
+        }
+
+        Ok(Moneda(if negativo { -total } else { total }))
+    }
+}
+
+impl<const DECIMALES: u32> Add for Moneda<DECIMALES> {
+    type Output = Moneda<DECIMALES>;
+
+    fn add(self, otra: Moneda<DECIMALES>) -> Moneda<DECIMALES> {
+        Moneda(desbordado(self.0.checked_add(otra.0)))
+    }
+}
+
+impl<const DECIMALES: u32> Sub for Moneda<DECIMALES> {
+    type Output = Moneda<DECIMALES>;
+
+    fn sub(self, otra: Moneda<DECIMALES>) -> Moneda<DECIMALES> {
+        Moneda(desbordado(self.0.checked_sub(otra.0)))
+    }
+}
+
+impl<const DECIMALES: u32> AddAssign for Moneda<DECIMALES> {
+    fn add_assign(&mut self, otra: Moneda<DECIMALES>) {
+        *self = *self + otra;
+    }
+}
+
+impl<const DECIMALES: u32> SubAssign for Moneda<DECIMALES> {
+    fn sub_assign(&mut self, otra: Moneda<DECIMALES>) {
+        *self = *self - otra;
+    }
+}
+
+impl<const DECIMALES: u32> Neg for Moneda<DECIMALES> {
+    type Output = Moneda<DECIMALES>;
+
+    fn neg(self) -> Moneda<DECIMALES> {
+        Moneda(desbordado(self.0.checked_neg()))
+    }
+}
+
+impl<const DECIMALES: u32> Mul<Moneda<DECIMALES>> for i64 {
+    type Output = Moneda<DECIMALES>;
+
+    fn mul(self, importe: Moneda<DECIMALES>) -> Moneda<DECIMALES> {
+        Moneda(desbordado(self.checked_mul(importe.0)))
+    }
+}
+
+impl<const DECIMALES: u32> Sum for Moneda<DECIMALES> {
+    fn sum<I: Iterator<Item = Moneda<DECIMALES>>>(iter: I) -> Moneda<DECIMALES> {
+        iter.fold(Moneda(0), Add::add)
+    }
+}
+
+#[cfg(test)]
+mod moneda_tests {
+
+    use super::*;
+
+    #[test]
+    fn suma_de_centimos_es_exacta() {
+        let total: Moneda = [0.1, 0.2].iter().map(|e| Moneda::from_euros(*e)).sum();
+
+        assert_eq!(total, Moneda::from_euros(0.3));
+        assert_eq!(total.as_euros(), 0.3);
+        assert_eq!(Moneda::from_euros(10.0) - Moneda::from_euros(10.01), Moneda::from_centimos(-1));
+        assert_eq!(Moneda::from_euros(2.675).centimos(), 268);
+    }
+
+    #[test]
+    fn display_muestra_dos_decimales_y_el_euro() {
+        assert_eq!(Moneda::from_euros(1234.5).to_string(), "1234.50 €");
+        assert_eq!(Moneda::from_centimos(-5).to_string(), "-0.05 €");
+        assert_eq!(Moneda::CERO.to_string(), "0.00 €");
+    }
+
+    #[test]
+    fn from_str_lee_el_importe_en_centimos() {
+        assert_eq!("15.50".parse(), Ok(Moneda::from_centimos(1550)));
+        assert_eq!("1.234,56".parse(), Ok(Moneda::from_centimos(123456)));
+        assert_eq!("-0,1".parse(), Ok(Moneda::from_centimos(-10)));
+        assert_eq!(" 3332 ".parse(), Ok(Moneda::from_centimos(333200)));
+        assert_eq!("0.125".parse(), Ok(Moneda::from_centimos(12)));
+        assert_eq!("0.1251".parse(), Ok(Moneda::from_centimos(13)));
+        assert_eq!("diez".parse::<Moneda>(), Err("diez".to_string()));
+        assert_eq!("1e3".parse::<Moneda>(), Err("1e3".to_string()));
+        assert_eq!("-".parse::<Moneda>(), Err("-".to_string()));
+    }
+
+    #[test]
+    fn las_operaciones_comprueban_el_desbordamiento() {
+        let maximo = Moneda::from_centimos(i64::MAX);
+
+        assert_eq!(maximo.checked_add(Moneda::from_centimos(1)), None);
+        assert_eq!(Moneda::from_centimos(i64::MIN).checked_sub(Moneda::from_centimos(1)), None);
+        assert_eq!(maximo.checked_sub(Moneda::from_centimos(1)), Some(Moneda::from_centimos(i64::MAX - 1)));
+        assert!(std::panic::catch_unwind(|| maximo + Moneda::from_centimos(1)).is_err());
+        assert!(std::panic::catch_unwind(|| 2 * maximo).is_err());
+        assert!(std::panic::catch_unwind(|| -Moneda::from_centimos(i64::MIN)).is_err());
+        assert!(std::panic::catch_unwind(|| [maximo, maximo].into_iter().sum::<Moneda>()).is_err());
+        assert!(std::panic::catch_unwind(|| maximo.con_decimales::<4>()).is_err());
+    }
+
+    #[test]
+    fn la_precision_decide_los_decimales_que_se_guardan_y_se_muestran() {
+        let precio: Moneda<4> = "0,12345".parse().unwrap();
+
+        assert_eq!(precio, Moneda::from_unidades(1234));
+        assert_eq!(precio.decimales(), 4);
+        assert_eq!(precio.to_string(), "0.1234 €");
+        assert_eq!(Moneda::<4>::from_f64(1.00005).unidades(), 10000);
+        assert_eq!(Moneda::<0>::from_f64(2.5).to_string(), "2 €");
+        assert_eq!((precio + Moneda::from_unidades(6)).as_f64(), 0.124);
+    }
+
+    #[test]
+    fn con_decimales_cambia_la_precision_con_redondeo_bancario() {
+        assert_eq!(Moneda::<4>::from_unidades(1235).con_decimales::<2>(), Moneda::from_centimos(12));
+        assert_eq!(Moneda::<4>::from_unidades(1250).con_decimales::<2>(), Moneda::from_centimos(12));
+        assert_eq!(Moneda::<4>::from_unidades(1350).con_decimales::<2>(), Moneda::from_centimos(14));
+        assert_eq!(Moneda::<4>::from_unidades(-1251).con_decimales::<2>(), Moneda::from_centimos(-13));
+        assert_eq!(Moneda::from_centimos(1550).con_decimales::<4>(), Moneda::<4>::from_unidades(155000));
+    }
+}
@@ -1,40 +1,121 @@
 use std::fmt::Display;
 
 use super::cuenta;
-use super::masa;
+use super::formato::{formato_importe, formato_importe_divisa};
+use super::moneda::Moneda;
+
+/// Divisas en las que se puede registrar el importe original de un movimiento
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Divisa {
+    Eur,
+    Usd,
+    Gbp,
+    Chf,
+    Jpy,
+}
+
+impl Display for Divisa {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Divisa::Eur => write!(f, "EUR"),
+            Divisa::Usd => write!(f, "USD"),
+            Divisa::Gbp => write!(f, "GBP"),
+            Divisa::Chf => write!(f, "CHF"),
+            Divisa::Jpy => write!(f, "JPY"),
+        }
+    }
+}
 
 /// Representa un movimiento.
 /// Este almacena solo el código de cuenta, puesto que no es probable que las cuentas cambien como tales
 /// y solo deben servir de referencia. Además, al guardarse mediante una referencia, se garantiza que existirán
 /// en el momento de ir a guardarlas.
-#[derive(PartialEq, Debug)]
+/// El importe siempre está en euros, que es lo que cuenta a efectos contables;
+/// si el movimiento se hizo en otra divisa, se conserva también el importe original.
+#[derive(PartialEq, Debug, Clone)]
 pub struct Movimiento {
-    importe: f64,
+    importe: Moneda,
     codigo_cuenta: String,
     nombre_cuenta: String,
+    divisa: Divisa,
+    importe_divisa: f64,
+    /// Código del asiento al que pertenece; vacío hasta que el asiento se numera
+    codigo_asiento: String,
 }
 
 impl Movimiento {
 
-    /// Almacena un movimiento con importe y código de cuenta, que toma de una referencia
-    pub fn new(importe: f64, cuenta: &mut cuenta::Cuenta) -> Movimiento {
+    /// Almacena un movimiento con importe y código de cuenta, que toma de una referencia.
+    pub fn new(importe: Moneda, cuenta: &mut cuenta::Cuenta) -> Movimiento {
         Movimiento { 
             importe, 
             codigo_cuenta: cuenta.codigo(),
             nombre_cuenta: cuenta.nombre(),
+            divisa: Divisa::Eur,
+            importe_divisa: importe.as_euros(),
+            codigo_asiento: String::new(),
         }
     }
 
+    /// Almacena un movimiento hecho en otra divisa, calculando su importe en euros con la tasa indicada
+    pub fn en_divisa(importe_divisa: f64, divisa: Divisa, tasa: f64, cuenta: &mut cuenta::Cuenta) -> Movimiento {
+        let mut movimiento = Movimiento::new(Moneda::CERO, cuenta);
+        movimiento.divisa = divisa;
+        movimiento.importe_divisa = importe_divisa;
+        movimiento.convertir(tasa);
+        movimiento
+    }
+
+    /// Recalcula el importe en euros a partir del importe en divisa y la tasa (euros por unidad de divisa),
+    /// redondeado a céntimos
+    pub fn convertir(&mut self, tasa: f64) {
+        self.importe = Moneda::from_euros(self.importe_divisa * tasa);
+    }
+
+    /// Devuelve la divisa original del movimiento
+    pub fn divisa(&self) -> Divisa {
+        self.divisa
+    }
+
+    /// Devuelve el importe en la divisa original
+    pub fn importe_divisa(&self) -> f64 {
+        self.importe_divisa
+    }
+
     /// Devuelve el importe que figura en el movimiento
-    pub fn importe(&self) -> f64 {
+    pub fn importe(&self) -> Moneda {
         self.importe
     }
 
+    /// Devuelve el código de la cuenta del movimiento
+    pub fn codigo_cuenta(&self) -> String {
+        self.codigo_cuenta.clone()
+    }
+
+    /// Devuelve el nombre de la cuenta del movimiento
+    pub fn nombre_cuenta(&self) -> String {
+        self.nombre_cuenta.clone()
+    }
+
+    /// Enlaza el movimiento con el asiento al que pertenece
+    pub fn asignar_asiento(&mut self, codigo_asiento: &str) {
+        self.codigo_asiento = codigo_asiento.to_string();
+    }
+
+    /// Devuelve el código del asiento al que pertenece el movimiento
+    pub fn codigo_asiento(&self) -> String {
+        self.codigo_asiento.clone()
+    }
+
 }
 
 impl Display for Movimiento {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "({}) {} {:.2} €", self.codigo_cuenta, self.nombre_cuenta, self.importe)
+        write!(f, "({}) {} {}", self.codigo_cuenta, self.nombre_cuenta, formato_importe(self.importe))?;
+        if self.divisa != Divisa::Eur {
+            write!(f, " ({})", formato_importe_divisa(self.importe_divisa, self.divisa))?;
+        }
+        Ok(())
     }
 }
 
@@ -42,16 +123,34 @@ impl Display for Movimiento {
 mod movimiento_tests {
     
     use super::*;
+    use crate::cuadro_contable::masa;
 
     #[test]
     fn new_crea_movimiento() {
         let mut cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
-        let movimiento = Movimiento::new(23.07, &mut cuenta);
+        let movimiento = Movimiento::new(Moneda::from_euros(23.07), &mut cuenta);
 
         assert_eq!(movimiento, Movimiento { 
             codigo_cuenta: "0000".to_string(), 
             nombre_cuenta: "test".to_string(), 
-            importe: 23.07
+            importe: Moneda::from_centimos(2307),
+            divisa: Divisa::Eur,
+            importe_divisa: 23.07,
+            codigo_asiento: String::new(),
         });
     }
+
+    #[test]
+    fn en_divisa_guarda_el_importe_original_y_lo_convierte_a_euros() {
+        let mut cuenta = cuenta::Cuenta::new("test", "0000", masa::Masa::ActivoCorriente);
+        let mut movimiento = Movimiento::en_divisa(100.0, Divisa::Usd, 0.9, &mut cuenta);
+
+        assert_eq!(movimiento.importe(), Moneda::from_euros(90.0));
+        assert_eq!(movimiento.importe_divisa(), 100.0);
+        assert_eq!(movimiento.divisa(), Divisa::Usd);
+        assert_eq!(movimiento.to_string(), "(0000) test 90,00 € (100,00 USD)");
+
+        movimiento.convertir(0.95);
+        assert_eq!(movimiento.importe(), Moneda::from_euros(95.0));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,135 @@
+use chrono::NaiveDate;
+
+use super::{AsientoNuevo, Cuadro, LibroDiario, LibroDiarioError};
+use super::asiento::TipoAsiento;
+use super::moneda::Moneda;
+
+/// Sueldos y salarios
+const SUELDOS: &str = "640";
+/// Seguridad Social a cargo de la empresa
+const SS_EMPRESA: &str = "642";
+/// Remuneraciones pendientes de pago
+const REMUNERACIONES_PENDIENTES: &str = "465";
+/// Hacienda Pública, acreedora por retenciones practicadas
+const RETENCIONES: &str = "4751";
+/// Organismos de la Seguridad Social, acreedores
+const SS_ACREEDORA: &str = "476";
+/// Bancos
+const BANCOS: &str = "572";
+
+/// Los importes de una nómina, tal y como vienen en el recibo de salarios
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Nomina {
+    bruto: Moneda,
+    retencion_irpf: Moneda,
+    ss_trabajador: Moneda,
+    ss_empresa: Moneda,
+}
+
+impl Nomina {
+
+    /// Crea una nómina a partir del salario bruto, la retención de IRPF y las cuotas
+    /// de Seguridad Social del trabajador y de la empresa
+    pub fn new(bruto: Moneda, retencion_irpf: Moneda, ss_trabajador: Moneda, ss_empresa: Moneda) -> Nomina {
+        Nomina { bruto, retencion_irpf, ss_trabajador, ss_empresa }
+    }
+
+    /// Devuelve el líquido a percibir por el trabajador
+    pub fn neto(&self) -> Moneda {
+        self.bruto - self.retencion_irpf - self.ss_trabajador
+    }
+}
+
+impl LibroDiario {
+
+    /// Anota una nómina con el desglose estándar en dos asientos de la misma fecha:
+    /// el devengo (640 y 642 contra 476, 4751 y 465) y el pago del líquido (465 contra 572).
+    /// Los dos asientos se validan antes de anotar ninguno, para no dejar la nómina a medias; si falta
+    /// una cuenta, el error señala su línea. Las partidas a cero (por ejemplo, una nómina sin retención
+    /// de IRPF) no se anotan.
+    pub fn crear_asiento_nomina(&mut self, concepto: &str, fecha: Option<NaiveDate>, nomina: &Nomina, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+
+        let neto = nomina.neto();
+        // insertar_asiento rechaza las líneas de 0 €
+        let sin_ceros = |lineas: Vec<(&'static str, Moneda)>| lineas.into_iter().filter(|(_, importe)| *importe != Moneda::CERO).collect();
+
+        let mut asientos = vec![AsientoNuevo {
+            concepto: concepto.to_string(),
+            fecha,
+            tipo: TipoAsiento::Normal,
+            debe: sin_ceros(vec![(SUELDOS, nomina.bruto), (SS_EMPRESA, nomina.ss_empresa)]),
+            haber: sin_ceros(vec![
+                (SS_ACREEDORA, nomina.ss_trabajador + nomina.ss_empresa),
+                (RETENCIONES, nomina.retencion_irpf),
+                (REMUNERACIONES_PENDIENTES, neto),
+            ]),
+        }];
+
+        if neto != Moneda::CERO {
+            asientos.push(AsientoNuevo {
+                concepto: format!("Pago: {}", concepto),
+                fecha,
+                tipo: TipoAsiento::Normal,
+                debe: vec![(REMUNERACIONES_PENDIENTES, neto)],
+                haber: vec![(BANCOS, neto)],
+            });
+        }
+
+        self.insertar_varios(asientos, cuadro).map_err(|(_, error)| error)
+    }
+}
+
+#[cfg(test)]
+mod nomina_tests {
+
+    use super::*;
+    use crate::cuadro_contable::apunte::Lado;
+
+    #[test]
+    fn crear_asiento_nomina_anota_devengo_y_pago_cuadrados() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc_filtrado(&[4, 5, 6]).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        let nomina = Nomina::new(Moneda::from_euros(2000.0), Moneda::from_euros(300.0), Moneda::from_euros(127.0), Moneda::from_euros(598.0));
+
+        libro_diario.crear_asiento_nomina("Nómina de enero", None, &nomina, &mut cuadro).unwrap();
+
+        assert_eq!(nomina.neto(), Moneda::from_euros(1573.0));
+        assert_eq!(libro_diario.asientos().count(), 2);
+        assert!(libro_diario.asientos().all(|a| a.validar_saldos()));
+        assert_eq!(cuadro.buscar_cuenta("640").unwrap().saldo(), Moneda::from_euros(2000.0));
+        assert_eq!(cuadro.buscar_cuenta("642").unwrap().saldo(), Moneda::from_euros(598.0));
+        assert_eq!(cuadro.buscar_cuenta("476").unwrap().saldo(), Moneda::from_euros(-725.0));
+        assert_eq!(cuadro.buscar_cuenta("4751").unwrap().saldo(), Moneda::from_euros(-300.0));
+        assert_eq!(cuadro.buscar_cuenta("465").unwrap().saldo(), Moneda::from_euros(0.0));
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(-1573.0));
+    }
+
+    #[test]
+    fn crear_asiento_nomina_falla_sin_anotar_nada_si_falta_una_cuenta() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc_filtrado(&[4, 6]).unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        let resultado = libro_diario.crear_asiento_nomina("Nómina", None, &Nomina::new(Moneda::from_euros(2000.0), Moneda::from_euros(300.0), Moneda::from_euros(127.0), Moneda::from_euros(598.0)), &mut cuadro);
+
+        assert_eq!(resultado, Err(LibroDiarioError::CuentaInexistenteEnLinea(Lado::Haber, 1, "572".to_string())));
+        assert_eq!(libro_diario.asientos().count(), 0);
+    }
+
+    #[test]
+    fn crear_asiento_nomina_no_anota_las_partidas_a_cero() {
+        let mut cuadro = Cuadro::new();
+        cuadro.cargar_pgc_filtrado(&[4, 5, 6]).unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        // Sin retención de IRPF
+        libro_diario.crear_asiento_nomina("Nómina de becario", None, &Nomina::new(Moneda::from_euros(600.0), Moneda::from_euros(0.0), Moneda::from_euros(38.1), Moneda::from_euros(0.0)), &mut cuadro).unwrap();
+
+        let devengo = libro_diario.asientos().next().unwrap();
+        assert_eq!(devengo.debe().len(), 1);
+        assert_eq!(devengo.haber().len(), 2);
+        assert!(cuadro.buscar_cuenta("4751").unwrap().apuntes().is_empty());
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(-561.9));
+    }
+}
@@ -0,0 +1,104 @@
+use std::io;
+
+use printpdf::{BuiltinFont, Mm, PdfDocument, Pt};
+
+/// Ancho de una página A4, en puntos
+pub const ANCHO_PAGINA: f64 = 595.0;
+/// Alto de una página A4, en puntos
+pub const ALTO_PAGINA: f64 = 842.0;
+
+/// Un texto colocado en una posición de la página
+struct Texto {
+    x: f64,
+    y: f64,
+    tamano: f64,
+    contenido: String,
+}
+
+/// Documento PDF de texto, con páginas A4 y fuente Courier, que se genera con `printpdf`.
+/// Al ser una fuente de ancho fijo, cada carácter ocupa el 60 % del tamaño de la fuente,
+/// lo que permite alinear columnas sin conocer las métricas de cada glifo.
+pub struct DocumentoPdf {
+    titulo: String,
+    paginas: Vec<Vec<Texto>>,
+}
+
+/// Convierte una medida en puntos a la unidad de `printpdf`
+fn mm(puntos: f64) -> Mm {
+    Mm::from(Pt(puntos as f32))
+}
+
+impl DocumentoPdf {
+
+    /// Crea un documento con una página vacía y el título indicado en sus metadatos
+    pub fn new(titulo: &str) -> DocumentoPdf {
+        DocumentoPdf { titulo: titulo.to_string(), paginas: vec![vec![]] }
+    }
+
+    /// Añade una página vacía, que pasa a ser la actual
+    pub fn nueva_pagina(&mut self) {
+        self.paginas.push(vec![]);
+    }
+
+    /// Escribe un texto en la página actual. Las coordenadas parten de la esquina inferior izquierda
+    pub fn escribir(&mut self, x: f64, y: f64, tamano: f64, contenido: &str) {
+        if let Some(pagina) = self.paginas.last_mut() {
+            pagina.push(Texto { x, y, tamano, contenido: contenido.to_string() });
+        }
+    }
+
+    /// Devuelve los textos del documento, página a página y en el orden en que se escribieron.
+    /// El contenido del PDF va comprimido en las versiones optimizadas, así que los tests miran aquí
+    #[cfg(test)]
+    pub fn textos(&self) -> impl Iterator<Item = &str> {
+        self.paginas.iter().flatten().map(|t| t.contenido.as_str())
+    }
+
+    /// Genera el contenido binario del documento. La fuente Courier usa WinAnsiEncoding,
+    /// así que el símbolo del euro y las tildes se ven bien
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+
+        let (documento, primera_pagina, primera_capa) = PdfDocument::new(&self.titulo, mm(ANCHO_PAGINA), mm(ALTO_PAGINA), "Texto");
+        let fuente = documento.add_builtin_font(BuiltinFont::Courier).map_err(io::Error::other)?;
+
+        for (i, textos) in self.paginas.iter().enumerate() {
+            let (pagina, capa) = match i {
+                0 => (primera_pagina, primera_capa),
+                _ => documento.add_page(mm(ANCHO_PAGINA), mm(ALTO_PAGINA), "Texto"),
+            };
+            let capa = documento.get_page(pagina).get_layer(capa);
+            for texto in textos {
+                capa.use_text(texto.contenido.as_str(), texto.tamano as f32, mm(texto.x), mm(texto.y), &fuente);
+            }
+        }
+
+        documento.save_to_bytes().map_err(io::Error::other)
+    }
+
+    /// Guarda el documento en la ruta indicada
+    pub fn guardar(&self, path: &str) -> io::Result<()> {
+        std::fs::write(path, self.bytes()?)
+    }
+}
+
+#[cfg(test)]
+mod pdf_tests {
+
+    use super::*;
+
+    #[test]
+    fn bytes_genera_un_pdf_con_una_pagina_por_cada_pagina_del_documento() {
+        let mut documento = DocumentoPdf::new("Prueba");
+        documento.escribir(50.0, 800.0, 10.0, "Primera");
+        documento.nueva_pagina();
+        documento.escribir(50.0, 800.0, 10.0, "Tesorería (572) 1,00 €");
+
+        let bytes = documento.bytes().unwrap();
+        let texto = String::from_utf8_lossy(&bytes);
+
+        assert_eq!(documento.textos().collect::<Vec<&str>>(), vec!["Primera", "Tesorería (572) 1,00 €"]);
+        assert!(texto.starts_with("%PDF-1.3"));
+        assert!(texto.contains("/Count 2"));
+        assert!(texto.trim_end().ends_with("%%EOF"));
+    }
+}
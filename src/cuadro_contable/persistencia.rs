@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{LibroDiario, LibroDiarioError};
+use super::asiento::{Asiento, TipoAsiento};
+use super::movimiento::Movimiento;
+
+impl LibroDiario {
+
+    /// Crea un libro diario que guarda cada asiento insertado en la carpeta indicada, en el mismo
+    /// formato `.data` que lee el programa al arrancar, para no perder nada si se cierra.
+    /// La carpeta ha de existir; si un asiento no se puede guardar, tampoco se inserta.
+    pub fn con_persistencia(directorio: &str) -> LibroDiario {
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.persistencia = Some(PathBuf::from(directorio));
+        libro_diario
+    }
+}
+
+/// Escribe el asiento en formato `.data`: la línea `FECHA`, la línea `TIPO` si no es un asiento normal,
+/// el concepto y los bloques DEBE y HABER
+fn formato_data(asiento: &Asiento) -> String {
+    let lineas = |movimientos: &[Movimiento]| movimientos
+        .iter()
+        .map(|m| format!("{} {:.2}", m.codigo_cuenta(), m.importe().as_euros()))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // El tipo decide, entre otras cosas, si el asiento admite importes negativos al volver a cargarlo
+    let tipo = match asiento.tipo() {
+        TipoAsiento::Normal => String::new(),
+        tipo => format!("TIPO {}\n", tipo),
+    };
+
+    format!(
+        "FECHA {}\n{}{}\n\nDEBE\n{}\n\nHABER\n{}\n",
+        asiento.fecha().format("%Y-%m-%d"),
+        tipo,
+        asiento.concepto(),
+        lineas(asiento.debe()),
+        lineas(asiento.haber()),
+    )
+}
+
+/// Guarda varios asientos, todos o ninguno: si alguno no se puede escribir, borra los que ya se habían guardado
+pub(super) fn guardar_asientos<'a>(directorio: &Path, asientos: impl Iterator<Item = &'a Asiento>) -> Result<(), LibroDiarioError> {
+    let mut guardados: Vec<PathBuf> = vec![];
+    for asiento in asientos {
+        match guardar_asiento(directorio, asiento) {
+            Ok(ruta) => guardados.push(ruta),
+            Err(error) => {
+                for ruta in guardados {
+                    let _ = fs::remove_file(ruta);
+                }
+                return Err(error);
+            },
+        }
+    }
+    Ok(())
+}
+
+/// Guarda el asiento en `<código>.data` dentro del directorio, o en el siguiente nombre libre
+/// del mismo día si ya hay un archivo con ese nombre, y devuelve la ruta. Escribe primero a un temporal
+/// y luego lo renombra, de modo que un fallo a mitad de escritura no deja un `.data` corrupto.
+fn guardar_asiento(directorio: &Path, asiento: &Asiento) -> Result<PathBuf, LibroDiarioError> {
+
+    let error = |e: std::io::Error| LibroDiarioError::ErrorPersistencia(e.to_string());
+
+    let dia = asiento.fecha().format("%Y%m%d").to_string();
+    let orden: usize = asiento.codigo()[dia.len()..].parse().unwrap_or(0);
+    let destino = (orden..)
+        .map(|n| directorio.join(format!("{}{}.data", dia, n)))
+        .find(|ruta| !ruta.exists())
+        .unwrap();
+
+    let temporal = destino.with_extension("data.tmp");
+    fs::write(&temporal, formato_data(asiento)).map_err(error)?;
+    fs::rename(&temporal, &destino).map_err(|e| {
+        let _ = fs::remove_file(&temporal);
+        error(e)
+    })?;
+    Ok(destino)
+}
+
+#[cfg(test)]
+mod persistencia_tests {
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::cuadro_contable::Cuadro;
+    use crate::cuadro_contable::masa::Masa;
+    use crate::cuadro_contable::moneda::Moneda;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("IVA repercutido", "477", Masa::PasivoCorriente).unwrap();
+        cuadro
+    }
+
+    fn directorio_vacio(nombre: &str) -> PathBuf {
+        let directorio = std::env::temp_dir().join(nombre);
+        let _ = fs::remove_dir_all(&directorio);
+        fs::create_dir_all(&directorio).unwrap();
+        directorio
+    }
+
+    #[test]
+    fn con_persistencia_guarda_cada_asiento_en_un_data() {
+        let directorio = directorio_vacio("presupuestos_persistencia");
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::con_persistencia(directorio.to_str().unwrap());
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9);
+
+        libro_diario.insertar_asiento("Venta\nfactura 7", fecha, vec![("572", Moneda::from_euros(121.0))], vec![("700", Moneda::from_euros(100.0)), ("477", Moneda::from_euros(21.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", fecha, vec![("572", Moneda::from_euros(10.0))], vec![("700", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(directorio.join("202308090.data")).unwrap(),
+            "FECHA 2023-08-09\nVenta\nfactura 7\n\nDEBE\n572 121.00\n\nHABER\n700 100.00\n477 21.00\n"
+        );
+        assert!(directorio.join("202308091.data").exists());
+        assert!(!directorio.join("202308090.data.tmp").exists());
+    }
+
+    #[test]
+    fn con_persistencia_guarda_el_tipo_de_los_asientos_que_no_son_normales() {
+        let directorio = directorio_vacio("presupuestos_persistencia_tipo");
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::con_persistencia(directorio.to_str().unwrap());
+
+        libro_diario.insertar_rectificativo("Abono", NaiveDate::from_ymd_opt(2023, 8, 9), vec![("572", Moneda::from_euros(-10.0))], vec![("700", Moneda::from_euros(-10.0))], &mut cuadro).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(directorio.join("202308090.data")).unwrap(),
+            "FECHA 2023-08-09\nTIPO Rectificativo\nAbono\n\nDEBE\n572 -10.00\n\nHABER\n700 -10.00\n"
+        );
+    }
+
+    #[test]
+    fn con_persistencia_no_sobrescribe_archivos_existentes() {
+        let directorio = directorio_vacio("presupuestos_persistencia_existente");
+        fs::write(directorio.join("202308090.data"), "otro asiento").unwrap();
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::con_persistencia(directorio.to_str().unwrap());
+
+        libro_diario.insertar_asiento("Venta", NaiveDate::from_ymd_opt(2023, 8, 9), vec![("572", Moneda::from_euros(10.0))], vec![("700", Moneda::from_euros(10.0))], &mut cuadro).unwrap();
+
+        assert_eq!(fs::read_to_string(directorio.join("202308090.data")).unwrap(), "otro asiento");
+        assert!(directorio.join("202308091.data").exists());
+    }
+
+    #[test]
+    fn con_persistencia_no_inserta_si_no_puede_guardar() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::con_persistencia("/no/existe/presupuestos");
+
+        let insercion = libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(10.0))], vec![("700", Moneda::from_euros(10.0))], &mut cuadro);
+
+        assert!(matches!(insercion, Err(LibroDiarioError::ErrorPersistencia(_))));
+        assert_eq!(libro_diario.asientos().count(), 0);
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(0.0));
+    }
+}
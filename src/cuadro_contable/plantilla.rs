@@ -0,0 +1,105 @@
+use chrono::NaiveDate;
+
+use super::{prestar_anotaciones, Cuadro, LibroDiario, LibroDiarioError};
+use super::moneda::Moneda;
+
+/// Un asiento recurrente (alquiler, nómina...) que se puede anotar tantas veces como haga falta
+/// con solo indicar la fecha
+#[derive(Debug, PartialEq, Clone)]
+pub struct PlantillaAsiento {
+    concepto: String,
+    debe: Vec<(String, Moneda)>,
+    haber: Vec<(String, Moneda)>,
+}
+
+impl PlantillaAsiento {
+
+    /// Crea una plantilla con su concepto y los pares de código de cuenta e importe de cada lado
+    pub fn new(concepto: &str, debe: Vec<(&str, Moneda)>, haber: Vec<(&str, Moneda)>) -> PlantillaAsiento {
+        let a_string = |anotaciones: Vec<(&str, Moneda)>| anotaciones
+            .into_iter()
+            .map(|(c, i)| (c.to_string(), i))
+            .collect();
+        PlantillaAsiento {
+            concepto: concepto.to_string(),
+            debe: a_string(debe),
+            haber: a_string(haber),
+        }
+    }
+
+    /// Cambia el importe de una cuenta de la plantilla, en el lado en el que aparezca.
+    /// Devuelve `false` si la cuenta no está en la plantilla
+    pub fn fijar_importe(&mut self, codigo_cuenta: &str, importe: Moneda) -> bool {
+        let mut encontrada = false;
+        for (codigo, i) in self.debe.iter_mut().chain(self.haber.iter_mut()) {
+            if codigo == codigo_cuenta {
+                *i = importe;
+                encontrada = true;
+            }
+        }
+        encontrada
+    }
+
+    /// Devuelve el concepto de la plantilla
+    pub fn concepto(&self) -> String {
+        self.concepto.clone()
+    }
+}
+
+impl LibroDiario {
+
+    /// Anota en el libro diario el asiento de una plantilla en la fecha indicada
+    pub fn aplicar_plantilla(&mut self, plantilla: &PlantillaAsiento, fecha: NaiveDate, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        self.insertar_asiento(
+            &plantilla.concepto,
+            Some(fecha),
+            prestar_anotaciones(&plantilla.debe),
+            prestar_anotaciones(&plantilla.haber),
+            cuadro,
+        )
+    }
+}
+
+#[cfg(test)]
+mod plantilla_tests {
+
+    use super::*;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Arrendamientos", "621", Masa::Gasto).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn aplicar_plantilla_anota_el_asiento_en_cada_fecha() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let alquiler = PlantillaAsiento::new("Alquiler", vec![("621", Moneda::from_euros(800.0))], vec![("572", Moneda::from_euros(800.0))]);
+
+        for mes in 1..=3 {
+            let fecha = NaiveDate::from_ymd_opt(2023, mes, 1).unwrap();
+            libro_diario.aplicar_plantilla(&alquiler, fecha, &mut cuadro).unwrap();
+        }
+
+        assert_eq!(libro_diario.asientos().count(), 3);
+        assert_eq!(cuadro.buscar_cuenta("621").unwrap().saldo(), Moneda::from_euros(2400.0));
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(-2400.0));
+    }
+
+    #[test]
+    fn fijar_importe_sobreescribe_el_importe_de_una_cuenta() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        let mut alquiler = PlantillaAsiento::new("Alquiler", vec![("621", Moneda::from_euros(800.0))], vec![("572", Moneda::from_euros(800.0))]);
+
+        assert!(alquiler.fijar_importe("621", Moneda::from_euros(850.0)));
+        assert!(alquiler.fijar_importe("572", Moneda::from_euros(850.0)));
+        assert!(!alquiler.fijar_importe("600", Moneda::from_euros(850.0)));
+
+        libro_diario.aplicar_plantilla(&alquiler, NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(), &mut cuadro).unwrap();
+        assert_eq!(cuadro.buscar_cuenta("621").unwrap().saldo(), Moneda::from_euros(850.0));
+    }
+}
@@ -0,0 +1,151 @@
+use std::fmt::Display;
+
+use super::Cuadro;
+use super::formato::{formato_importe, redondear};
+use super::masa::Masa;
+use super::moneda::Moneda;
+
+/// Ratios financieros básicos calculados sobre los saldos del cuadro.
+/// Los cocientes son `None` cuando su denominador es cero
+#[derive(Debug, PartialEq)]
+pub struct Ratios {
+    /// Activo corriente menos pasivo corriente
+    pub fondo_maniobra: Moneda,
+    /// Activo corriente entre pasivo corriente
+    pub liquidez: Option<f64>,
+    /// Pasivo (corriente y no corriente) entre la suma de pasivo y patrimonio neto
+    pub endeudamiento: Option<f64>,
+    /// Activo total entre pasivo total
+    pub solvencia: Option<f64>,
+}
+
+/// Cociente redondeado a céntimos, o `None` si el divisor es cero
+fn cociente(dividendo: Moneda, divisor: Moneda) -> Option<f64> {
+    if divisor == Moneda::CERO {
+        return None;
+    }
+    Some(redondear(dividendo.as_euros() / divisor.as_euros()))
+}
+
+impl Ratios {
+
+    /// Lectura del fondo de maniobra para quien no es contable
+    pub fn interpretacion_fondo_maniobra(&self) -> &'static str {
+        if self.fondo_maniobra > Moneda::CERO {
+            "el activo corriente cubre las deudas a corto plazo"
+        } else if self.fondo_maniobra < Moneda::CERO {
+            "las deudas a corto plazo superan al activo corriente: posibles tensiones de tesorería"
+        } else {
+            "el activo corriente cubre justo las deudas a corto plazo"
+        }
+    }
+
+    /// Lectura del ratio de liquidez
+    pub fn interpretacion_liquidez(&self) -> &'static str {
+        match self.liquidez {
+            None => "sin deudas a corto plazo",
+            Some(r) if r < 1.0 => "insuficiente: no se pueden atender todas las deudas a corto plazo",
+            Some(r) if r <= 2.0 => "adecuada",
+            Some(_) => "holgada: puede haber activos corrientes ociosos",
+        }
+    }
+
+    /// Lectura del ratio de endeudamiento
+    pub fn interpretacion_endeudamiento(&self) -> &'static str {
+        match self.endeudamiento {
+            None => "sin financiación",
+            Some(r) if r < 0.4 => "bajo: la financiación es mayoritariamente propia",
+            Some(r) if r <= 0.6 => "equilibrado",
+            Some(_) => "elevado: fuerte dependencia de la financiación ajena",
+        }
+    }
+
+    /// Lectura del ratio de solvencia
+    pub fn interpretacion_solvencia(&self) -> &'static str {
+        match self.solvencia {
+            None => "sin deudas",
+            Some(r) if r < 1.0 => "el activo no alcanza para pagar todas las deudas",
+            Some(r) if r < 1.5 => "ajustada",
+            Some(_) => "solvente",
+        }
+    }
+}
+
+impl Display for Ratios {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let valor = |ratio: Option<f64>| ratio.map_or("n/d".to_string(), |r| format!("{:.2}", r));
+
+        writeln!(f, "Fondo de maniobra: {} ({})", formato_importe(self.fondo_maniobra), self.interpretacion_fondo_maniobra())?;
+        writeln!(f, "Liquidez: {} ({})", valor(self.liquidez), self.interpretacion_liquidez())?;
+        writeln!(f, "Endeudamiento: {} ({})", valor(self.endeudamiento), self.interpretacion_endeudamiento())?;
+        writeln!(f, "Solvencia: {} ({})", valor(self.solvencia), self.interpretacion_solvencia())
+    }
+}
+
+impl Cuadro {
+
+    /// Calcula los ratios financieros básicos a partir de los totales por masa.
+    /// El patrimonio neto incluye el resultado provisional, como en el balance de situación
+    pub fn ratios(&self) -> Ratios {
+        let totales = self.totales_por_masa();
+        let total = |masa: Masa| totales.get(&masa).copied().unwrap_or(Moneda::CERO);
+
+        // El pasivo y el patrimonio tienen saldo acreedor, es decir, negativo
+        let activo_corriente = total(Masa::ActivoCorriente);
+        let activo = activo_corriente + total(Masa::ActivoNoCorriente);
+        let pasivo_corriente = -total(Masa::PasivoCorriente);
+        let pasivo = pasivo_corriente - total(Masa::PasivoNoCorriente);
+        let patrimonio_neto = -total(Masa::Patrimonio) + self.resultado_provisional();
+
+        Ratios {
+            fondo_maniobra: activo_corriente - pasivo_corriente,
+            liquidez: cociente(activo_corriente, pasivo_corriente),
+            endeudamiento: cociente(pasivo, pasivo + patrimonio_neto),
+            solvencia: cociente(activo, pasivo),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ratios_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Maquinaria", "213", Masa::ActivoNoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Proveedores", "400", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("Deudas a largo plazo", "170", Masa::PasivoNoCorriente).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn ratios_calcula_sobre_los_totales_por_masa() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(3000.0)), ("213", Moneda::from_euros(5000.0))], vec![("100", Moneda::from_euros(4000.0)), ("400", Moneda::from_euros(1500.0)), ("170", Moneda::from_euros(2500.0))], &mut cuadro).unwrap();
+
+        let ratios = cuadro.ratios();
+
+        assert_eq!(ratios, Ratios { fondo_maniobra: Moneda::from_euros(1500.0), liquidez: Some(2.0), endeudamiento: Some(0.5), solvencia: Some(2.0) });
+        assert_eq!(ratios.interpretacion_liquidez(), "adecuada");
+        assert_eq!(ratios.interpretacion_endeudamiento(), "equilibrado");
+        assert_eq!(ratios.interpretacion_solvencia(), "solvente");
+    }
+
+    #[test]
+    fn ratios_sin_deudas_no_divide_por_cero() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+
+        let ratios = cuadro.ratios();
+
+        assert_eq!(ratios, Ratios { fondo_maniobra: Moneda::from_euros(1000.0), liquidez: None, endeudamiento: Some(0.0), solvencia: None });
+        assert!(ratios.to_string().contains("Liquidez: n/d (sin deudas a corto plazo)"));
+        assert_eq!(Cuadro::new().ratios().endeudamiento, None);
+    }
+}
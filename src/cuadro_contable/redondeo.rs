@@ -0,0 +1,154 @@
+use super::{Cuadro, CuadroError, LibroDiario};
+use super::apunte::Lado;
+use super::masa::Masa;
+use super::moneda::Moneda;
+
+/// Cuenta a la que van las diferencias de redondeo a favor del haber (otros gastos financieros)
+pub const CUENTA_GASTO_REDONDEO: &str = "669";
+/// Cuenta a la que van las diferencias de redondeo a favor del debe (otros ingresos financieros)
+pub const CUENTA_INGRESO_REDONDEO: &str = "769";
+/// Umbral máximo que se puede configurar: por encima, la diferencia ya no es de redondeo
+pub const UMBRAL_MAXIMO: Moneda = Moneda::from_centimos(5);
+
+impl LibroDiario {
+
+    /// Activa el cuadre automático de los asientos que solo difieren en céntimos: si la diferencia entre
+    /// el debe y el haber no pasa de `umbral`, se lleva a la 669 (si falta debe) o a la 769 (si falta haber)
+    /// y el ajuste queda en el historial del cuadro. El umbral no puede pasar de [`UMBRAL_MAXIMO`],
+    /// para no ocultar descuadres de verdad; las diferencias mayores se siguen rechazando.
+    /// Crea en el cuadro la 669 y la 769 si aún no existen; si no se pueden crear, el ajuste no se activa
+    pub fn activar_ajuste_redondeo(&mut self, umbral: Moneda, cuadro: &mut Cuadro) -> Result<(), CuadroError> {
+        let cuentas = [
+            (CUENTA_GASTO_REDONDEO, "Otros gastos financieros", Masa::Gasto),
+            (CUENTA_INGRESO_REDONDEO, "Otros ingresos financieros", Masa::Ingreso),
+        ];
+        for (codigo, nombre, masa) in cuentas {
+            if cuadro.buscar_cuenta(codigo).is_none() {
+                cuadro.crear_cuenta(nombre, codigo, masa)?;
+            }
+        }
+        self.ajuste_redondeo = Some(umbral.clamp(Moneda::CERO, UMBRAL_MAXIMO));
+        Ok(())
+    }
+
+    /// Desactiva el cuadre automático: cualquier diferencia vuelve a rechazar el asiento
+    pub fn desactivar_ajuste_redondeo(&mut self) {
+        self.ajuste_redondeo = None;
+    }
+
+    /// Devuelve el umbral del cuadre automático, si está activado
+    pub fn ajuste_redondeo(&self) -> Option<Moneda> {
+        self.ajuste_redondeo
+    }
+}
+
+/// Calcula la línea que cuadra un asiento con una diferencia de céntimos dentro del umbral:
+/// el lado en el que falta importe, la cuenta de redondeo y la diferencia. `None` si el asiento
+/// ya cuadra o la diferencia supera el umbral
+pub(super) fn linea_ajuste(debe: &[(&str, Moneda)], haber: &[(&str, Moneda)], umbral: Moneda) -> Option<(Lado, &'static str, Moneda)> {
+
+    let total = |lineas: &[(&str, Moneda)]| lineas.iter().map(|(_, i)| *i).sum::<Moneda>();
+    let diferencia = total(debe) - total(haber);
+
+    if diferencia == Moneda::CERO || diferencia.abs() > umbral {
+        return None;
+    }
+
+    if diferencia > Moneda::CERO {
+        Some((Lado::Haber, CUENTA_INGRESO_REDONDEO, diferencia))
+    } else {
+        Some((Lado::Debe, CUENTA_GASTO_REDONDEO, -diferencia))
+    }
+}
+
+#[cfg(test)]
+mod redondeo_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiarioError;
+    use crate::cuadro_contable::auditoria::Operacion;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("IVA soportado", "472", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Proveedores", "400", Masa::PasivoCorriente).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn linea_ajuste_solo_cuadra_diferencias_dentro_del_umbral() {
+        assert_eq!(linea_ajuste(&[("600", Moneda::from_euros(10.01))], &[("400", Moneda::from_euros(10.0))], Moneda::from_euros(0.01)), Some((Lado::Haber, "769", Moneda::from_euros(0.01))));
+        assert_eq!(linea_ajuste(&[("600", Moneda::from_euros(33.33)), ("472", Moneda::from_euros(7.0))], &[("400", Moneda::from_euros(40.34))], Moneda::from_euros(0.01)), Some((Lado::Debe, "669", Moneda::from_euros(0.01))));
+        assert_eq!(linea_ajuste(&[("600", Moneda::from_euros(10.02))], &[("400", Moneda::from_euros(10.0))], Moneda::from_euros(0.01)), None);
+        assert_eq!(linea_ajuste(&[("600", Moneda::from_euros(10.0))], &[("400", Moneda::from_euros(10.0))], Moneda::from_euros(0.01)), None);
+    }
+
+    #[test]
+    fn insertar_asiento_con_ajuste_lleva_los_centimos_a_la_cuenta_de_redondeo() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.activar_ajuste_redondeo(Moneda::from_euros(0.01), &mut cuadro).unwrap();
+
+        libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(82.64)), ("472", Moneda::from_euros(17.35))], vec![("400", Moneda::from_euros(100.0))], &mut cuadro).unwrap();
+
+        let asiento = libro_diario.asientos().last().unwrap();
+        assert_eq!(asiento.debe().last().unwrap().codigo_cuenta(), "669");
+        assert_eq!(asiento.total_debe(), Moneda::from_euros(100.0));
+        assert_eq!(cuadro.buscar_cuenta("669").unwrap().saldo(), Moneda::from_euros(0.01));
+        assert!(cuadro.historial().iter().any(|e| e.operacion == Operacion::AjusteRedondeo { codigo: asiento.codigo(), diferencia: Moneda::from_euros(0.01) }));
+    }
+
+    #[test]
+    fn ajuste_redondeo_nunca_oculta_descuadres_grandes() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+
+        // Sin activar, cualquier diferencia se rechaza
+        assert!(matches!(
+            libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(10.01))], vec![("400", Moneda::from_euros(10.0))], &mut cuadro),
+            Err(LibroDiarioError::AsientoDesequilibrado(_))
+        ));
+
+        // El umbral no pasa del máximo y las diferencias mayores se siguen rechazando
+        libro_diario.activar_ajuste_redondeo(Moneda::from_euros(100.0), &mut cuadro).unwrap();
+        assert_eq!(libro_diario.ajuste_redondeo(), Some(UMBRAL_MAXIMO));
+        assert!(matches!(
+            libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(11.0))], vec![("400", Moneda::from_euros(10.0))], &mut cuadro),
+            Err(LibroDiarioError::AsientoDesequilibrado(_))
+        ));
+        assert!(libro_diario.asientos().next().is_none());
+        assert_eq!(cuadro.buscar_cuenta("769").unwrap().saldo(), Moneda::from_euros(0.0));
+    }
+
+    #[test]
+    fn activar_ajuste_redondeo_crea_las_cuentas_de_redondeo_si_faltan() {
+        let mut cuadro = setup_cuadro();
+        cuadro.crear_cuenta("Gastos por redondeo", "669", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+
+        libro_diario.activar_ajuste_redondeo(Moneda::from_euros(0.01), &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("669").unwrap().nombre(), "Gastos por redondeo");
+        assert_eq!(cuadro.buscar_cuenta("769").unwrap().masa(), Masa::Ingreso);
+    }
+
+    #[test]
+    fn ajuste_redondeo_senala_las_lineas_del_usuario_antes_que_la_de_ajuste() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.activar_ajuste_redondeo(Moneda::from_euros(0.01), &mut cuadro).unwrap();
+
+        assert_eq!(
+            libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(10.01))], vec![("4000", Moneda::from_euros(10.0))], &mut cuadro),
+            Err(LibroDiarioError::CuentaInexistenteEnLinea(Lado::Haber, 1, "4000".to_string()))
+        );
+
+        cuadro.desactivar_cuenta("769").unwrap();
+        assert_eq!(
+            libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(10.01))], vec![("400", Moneda::from_euros(10.0))], &mut cuadro),
+            Err(LibroDiarioError::CuentaInactiva(Lado::Haber, 2, "769".to_string()))
+        );
+        assert!(libro_diario.asientos().next().is_none());
+    }
+}
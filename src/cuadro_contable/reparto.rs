@@ -0,0 +1,49 @@
+use super::moneda::Moneda;
+
+/// Reparte un importe entre varias cuentas según sus porcentajes (sobre 100).
+/// Cada parte se redondea a céntimos y la última se calcula como lo que falta,
+/// de modo que la suma coincide exactamente con el importe original.
+/// Si los porcentajes no suman 100, la última cuenta se queda con la diferencia.
+pub fn repartir(importe: Moneda, porcentajes: &[(String, f64)]) -> Vec<(String, Moneda)> {
+
+    let mut partes: Vec<(String, Moneda)> = vec![];
+    let mut repartido = Moneda::CERO;
+
+    for (i, (codigo, porcentaje)) in porcentajes.iter().enumerate() {
+        let parte = if i + 1 == porcentajes.len() {
+            importe - repartido
+        } else {
+            Moneda::from_euros(importe.as_euros() * porcentaje / 100.0)
+        };
+        repartido += parte;
+        partes.push((codigo.clone(), parte));
+    }
+
+    partes
+}
+
+#[cfg(test)]
+mod reparto_tests {
+
+    use super::*;
+
+    #[test]
+    fn repartir_en_tres_partes_iguales_cuadra_con_el_ultimo() {
+        let tercio = 100.0 / 3.0;
+        let porcentajes = vec![("621".to_string(), tercio), ("628".to_string(), tercio), ("629".to_string(), tercio)];
+
+        let partes = repartir(Moneda::from_euros(100.0), &porcentajes);
+
+        assert_eq!(partes, vec![
+            ("621".to_string(), Moneda::from_euros(33.33)),
+            ("628".to_string(), Moneda::from_euros(33.33)),
+            ("629".to_string(), Moneda::from_euros(33.34)),
+        ]);
+        assert_eq!(partes.iter().map(|(_, i)| *i).sum::<Moneda>(), Moneda::from_euros(100.0));
+    }
+
+    #[test]
+    fn repartir_sin_porcentajes_no_devuelve_nada() {
+        assert!(repartir(Moneda::from_euros(100.0), &[]).is_empty());
+    }
+}
@@ -0,0 +1,258 @@
+use super::Cuadro;
+use super::formato::LineaInforme;
+use super::masa::Masa;
+use super::moneda::Moneda;
+
+/// Bloque de la cuenta de pérdidas y ganancias al que pertenece un epígrafe
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bloque {
+    Explotacion,
+    Financiero,
+    Impuestos,
+}
+
+/// Epígrafes del modelo de cuenta de pérdidas y ganancias del PGC, con los prefijos de las cuentas
+/// que recoge cada uno. Una cuenta va al epígrafe cuyo prefijo coincida con más cifras de su código,
+/// de modo que un subgrupo entero puede tener excepciones (p. ej. el 63 salvo el 630, el 633 y el 638).
+const EPIGRAFES_PYG: [(&str, Bloque, &[&str]); 18] = [
+    ("1. Importe neto de la cifra de negocios", Bloque::Explotacion, &["70"]),
+    ("2. Variación de existencias de productos terminados y en curso", Bloque::Explotacion, &["71", "6930", "7930"]),
+    ("3. Trabajos realizados por la empresa para su activo", Bloque::Explotacion, &["73"]),
+    ("4. Aprovisionamientos", Bloque::Explotacion, &["60", "61", "6931", "6932", "6933", "7931", "7932", "7933"]),
+    ("5. Otros ingresos de explotación", Bloque::Explotacion, &["74", "75"]),
+    ("6. Gastos de personal", Bloque::Explotacion, &["64"]),
+    ("7. Otros gastos de explotación", Bloque::Explotacion, &["62", "63", "65", "694", "695", "794", "7954"]),
+    ("8. Amortización del inmovilizado", Bloque::Explotacion, &["68"]),
+    ("9. Imputación de subvenciones de inmovilizado no financiero", Bloque::Explotacion, &["746"]),
+    ("10. Excesos de provisiones", Bloque::Explotacion, &["7951", "7952", "7955", "7956"]),
+    ("11. Deterioro y resultado por enajenaciones del inmovilizado", Bloque::Explotacion, &["67", "69", "77", "79"]),
+    ("12. Otros resultados", Bloque::Explotacion, &["678", "778"]),
+    ("13. Ingresos financieros", Bloque::Financiero, &["76"]),
+    ("14. Gastos financieros", Bloque::Financiero, &["66"]),
+    ("15. Variación de valor razonable en instrumentos financieros", Bloque::Financiero, &["663", "763"]),
+    ("16. Diferencias de cambio", Bloque::Financiero, &["668", "768"]),
+    ("17. Deterioro y resultado por enajenaciones de instrumentos financieros", Bloque::Financiero,
+        &["666", "667", "673", "675", "696", "697", "698", "699", "766", "773", "775", "796", "797", "798", "799"]),
+    ("18. Impuestos sobre beneficios", Bloque::Impuestos, &["630", "633", "638"]),
+];
+
+/// Epígrafe al que van las cuentas de gasto o ingreso que no encajan en ningún otro
+const EPIGRAFE_PYG_POR_DEFECTO: usize = 11;
+
+/// Devuelve la posición en `EPIGRAFES_PYG` del epígrafe que recoge la cuenta
+fn epigrafe_pyg(codigo: &str) -> usize {
+    EPIGRAFES_PYG
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, _, prefijos))| prefijos.iter().map(move |p| (i, p)))
+        .filter(|(_, prefijo)| codigo.starts_with(*prefijo))
+        .max_by_key(|(_, prefijo)| prefijo.len())
+        .map(|(i, _)| i)
+        .unwrap_or(EPIGRAFE_PYG_POR_DEFECTO)
+}
+
+impl Cuadro {
+
+    /// Devuelve el resultado del ejercicio hasta ahora (ingresos menos gastos) sin anotar ningún asiento.
+    /// Positivo si hay beneficio y negativo si hay pérdida
+    pub fn resultado_provisional(&self) -> Moneda {
+        let totales = self.totales_por_masa();
+        let total = |masa: Masa| totales.get(&masa).copied().unwrap_or(Moneda::CERO);
+
+        // Los ingresos tienen saldo acreedor, es decir, negativo
+        -(total(Masa::Ingreso) + total(Masa::Gasto))
+    }
+
+    /// Devuelve la cuenta de pérdidas y ganancias a fecha de hoy (o de cierre, si el ejercicio ya ha terminado): los ingresos y los gastos
+    /// con sus cuentas, ambos en positivo, y el resultado del ejercicio al pie
+    pub fn cuenta_resultados(&self) -> String {
+
+        let w = 80;
+
+        let totales = self.totales_por_masa();
+        let ingresos = -totales.get(&Masa::Ingreso).copied().unwrap_or(Moneda::CERO);
+        let gastos = totales.get(&Masa::Gasto).copied().unwrap_or(Moneda::CERO);
+
+        let mut lineas = vec![];
+        let seccion = |lineas: &mut Vec<LineaInforme>, titulo: &str, masa: Masa, total: Moneda| {
+            let signo = masa.naturaleza().signo();
+            lineas.push(LineaInforme::epigrafe(titulo, total));
+            for cuenta in self.cuentas.iter().filter(|c| c.masa() == masa && c.saldo() != Moneda::CERO) {
+                lineas.push(LineaInforme::cuenta(format!("({}) {}", cuenta.codigo(), cuenta.nombre()), signo * cuenta.saldo()));
+            }
+        };
+        seccion(&mut lineas, "INGRESOS", Masa::Ingreso, ingresos);
+        seccion(&mut lineas, "GASTOS", Masa::Gasto, gastos);
+
+        let mut salida = self.cabecera_resultados(w);
+        for linea in lineas {
+            salida.push_str(&linea.formatear(w));
+            salida.push('\n');
+        }
+        salida.push_str(&format!("{:-<w$}\n", ""));
+        salida.push_str(&LineaInforme::epigrafe("RESULTADO DEL EJERCICIO", self.resultado_provisional()).formatear(w));
+        salida.push('\n');
+
+        salida
+    }
+
+    /// Devuelve la cuenta de pérdidas y ganancias según el modelo del PGC: las cuentas de gasto
+    /// e ingreso agrupadas en sus epígrafes (cifra de negocios, aprovisionamientos, gastos de personal...)
+    /// con los márgenes intermedios de explotación, financiero y antes de impuestos.
+    /// Los ingresos aparecen en positivo y los gastos en negativo, como en el modelo oficial.
+    pub fn pyg_analitica(&self) -> String {
+
+        let w = 80;
+
+        let mut importes = [Moneda::CERO; EPIGRAFES_PYG.len()];
+        let mut cuentas: Vec<Vec<LineaInforme>> = EPIGRAFES_PYG.iter().map(|_| vec![]).collect();
+        for cuenta in self.cuentas.iter().filter(|c| matches!(c.masa(), Masa::Ingreso | Masa::Gasto) && c.saldo() != Moneda::CERO) {
+            let i = epigrafe_pyg(&cuenta.codigo());
+            importes[i] -= cuenta.saldo();
+            cuentas[i].push(LineaInforme::cuenta(format!("({}) {}", cuenta.codigo(), cuenta.nombre()), -cuenta.saldo()));
+        }
+
+        let total = |bloque: Bloque| EPIGRAFES_PYG.iter()
+            .zip(importes.iter())
+            .filter(|((_, b, _), _)| *b == bloque)
+            .map(|(_, importe)| *importe)
+            .sum::<Moneda>();
+        let explotacion = total(Bloque::Explotacion);
+        let financiero = total(Bloque::Financiero);
+        let antes_de_impuestos = explotacion + financiero;
+
+        let mut salida = self.cabecera_resultados(w);
+        let mut bloque = |salida: &mut String, b: Bloque, margen: &str, importe: Moneda| {
+            for (i, (titulo, _, _)) in EPIGRAFES_PYG.iter().enumerate().filter(|(_, (_, e, _))| *e == b) {
+                if cuentas[i].is_empty() {
+                    continue;
+                }
+                salida.push_str(&LineaInforme::epigrafe(titulo, importes[i]).formatear(w));
+                salida.push('\n');
+                for linea in cuentas[i].drain(..) {
+                    salida.push_str(&linea.formatear(w));
+                    salida.push('\n');
+                }
+            }
+            salida.push_str(&format!("{:-<w$}\n", ""));
+            salida.push_str(&LineaInforme::epigrafe(margen, importe).formatear(w));
+            salida.push('\n');
+        };
+        bloque(&mut salida, Bloque::Explotacion, "A.1) RESULTADO DE EXPLOTACIÓN", explotacion);
+        bloque(&mut salida, Bloque::Financiero, "A.2) RESULTADO FINANCIERO", financiero);
+        salida.push_str(&LineaInforme::epigrafe("A.3) RESULTADO ANTES DE IMPUESTOS", antes_de_impuestos).formatear(w));
+        salida.push('\n');
+        bloque(&mut salida, Bloque::Impuestos, "A.4) RESULTADO DEL EJERCICIO", antes_de_impuestos + total(Bloque::Impuestos));
+
+        salida
+    }
+
+    /// Título, fecha y ejercicio con los que empiezan las cuentas de pérdidas y ganancias
+    fn cabecera_resultados(&self, w: usize) -> String {
+        let mut salida = String::new();
+        salida.push_str(&format!("{:^w$}\n", "CUENTA DE PÉRDIDAS Y GANANCIAS"));
+        salida.push_str(&format!("{:^w$}\n", self.fecha_informe().format("%Y-%m-%d").to_string()));
+        if let Some(ejercicio) = self.ejercicio {
+            salida.push_str(&format!("{:^w$}\n", format!("Ejercicio {}", ejercicio)));
+        }
+        salida.push_str(&format!("{:-<w$}\n", ""));
+        salida
+    }
+}
+
+#[cfg(test)]
+mod resultados_tests {
+
+    use super::*;
+    use crate::cuadro_contable::LibroDiario;
+
+    #[test]
+    fn cuenta_resultados_muestra_ingresos_gastos_y_resultado() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Suministros", "628", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(1500.0))], vec![("700", Moneda::from_euros(1500.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(400.0))], vec![("572", Moneda::from_euros(400.0))], &mut cuadro).unwrap();
+
+        let pyg = cuadro.cuenta_resultados();
+        let linea = |inicio: &str| pyg.lines().find(|l| l.trim_start().starts_with(inicio)).unwrap().to_string();
+
+        assert!(linea("INGRESOS").ends_with("1.500,00 €"));
+        assert!(linea("(700) Ventas").ends_with("1.500,00 €"));
+        assert!(linea("GASTOS").ends_with("400,00 €"));
+        assert!(!pyg.contains("Suministros"));
+        assert!(!pyg.contains("Bancos"));
+        assert!(linea("RESULTADO DEL EJERCICIO").ends_with("1.100,00 €"));
+    }
+
+    #[test]
+    fn epigrafe_pyg_usa_el_prefijo_mas_largo() {
+        let titulo = |codigo: &str| EPIGRAFES_PYG[epigrafe_pyg(codigo)].0;
+
+        assert_eq!(titulo("700"), "1. Importe neto de la cifra de negocios");
+        assert_eq!(titulo("629"), "7. Otros gastos de explotación");
+        assert_eq!(titulo("6300"), "18. Impuestos sobre beneficios");
+        assert_eq!(titulo("768"), "16. Diferencias de cambio");
+        assert_eq!(titulo("769"), "13. Ingresos financieros");
+        assert_eq!(titulo("7952"), "10. Excesos de provisiones");
+        assert_eq!(titulo("690"), "11. Deterioro y resultado por enajenaciones del inmovilizado");
+        assert_eq!(titulo("720"), "12. Otros resultados");
+    }
+
+    #[test]
+    fn pyg_analitica_calcula_los_margenes_intermedios() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas de mercaderías", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras de mercaderías", "600", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Sueldos y salarios", "640", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Amortización del inmovilizado material", "681", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Intereses de deudas", "662", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Otros ingresos financieros", "769", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Impuesto corriente", "6300", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(1000.0))], vec![("700", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento(
+            "Gastos",
+            None,
+            vec![("600", Moneda::from_euros(300.0)), ("640", Moneda::from_euros(200.0)), ("681", Moneda::from_euros(50.0)), ("662", Moneda::from_euros(20.0)), ("6300", Moneda::from_euros(100.0))],
+            vec![("572", Moneda::from_euros(670.0))],
+            &mut cuadro,
+        ).unwrap();
+        libro_diario.insertar_asiento("Intereses", None, vec![("572", Moneda::from_euros(5.0))], vec![("769", Moneda::from_euros(5.0))], &mut cuadro).unwrap();
+
+        let pyg = cuadro.pyg_analitica();
+        let linea = |inicio: &str| pyg.lines().find(|l| l.trim_start().starts_with(inicio)).unwrap().to_string();
+
+        assert!(linea("1. Importe neto de la cifra de negocios").ends_with("1.000,00 €"));
+        assert!(linea("4. Aprovisionamientos").ends_with("-300,00 €"));
+        assert!(linea("6. Gastos de personal").ends_with("-200,00 €"));
+        assert!(linea("8. Amortización del inmovilizado").ends_with("-50,00 €"));
+        assert!(linea("A.1) RESULTADO DE EXPLOTACIÓN").ends_with("450,00 €"));
+        assert!(linea("A.2) RESULTADO FINANCIERO").ends_with("-15,00 €"));
+        assert!(linea("A.3) RESULTADO ANTES DE IMPUESTOS").ends_with("435,00 €"));
+        assert!(linea("18. Impuestos sobre beneficios").ends_with("-100,00 €"));
+        assert!(linea("A.4) RESULTADO DEL EJERCICIO").ends_with("335,00 €"));
+        assert_eq!(cuadro.resultado_provisional(), Moneda::from_euros(335.0));
+        assert!(!pyg.contains("2. Variación de existencias"));
+        assert!(!pyg.contains("Bancos"));
+    }
+
+    #[test]
+    fn resultado_provisional_no_anota_ningun_asiento() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(300.0))], vec![("700", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Compra", None, vec![("600", Moneda::from_euros(450.0))], vec![("572", Moneda::from_euros(450.0))], &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.resultado_provisional(), Moneda::from_euros(-150.0));
+        assert_eq!(cuadro.resumen(&libro_diario).asientos, 2);
+        assert_eq!(cuadro.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(-300.0));
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt::Display;
+
+use super::{Cuadro, LibroDiario};
+use super::apunte::Lado;
+use super::formato::formato_importe;
+use super::moneda::Moneda;
+
+/// Foto rápida del estado de un cuadro: su tamaño y si cuadra
+#[derive(Debug, PartialEq)]
+pub struct ResumenCuadro {
+    /// Número de cuentas del cuadro
+    pub cuentas: usize,
+    /// Cuentas con saldo distinto de cero
+    pub cuentas_con_saldo: usize,
+    /// Asientos del libro diario
+    pub asientos: usize,
+    /// Suma de los apuntes del debe de todas las cuentas
+    pub total_debe: Moneda,
+    /// Suma de los apuntes del haber de todas las cuentas
+    pub total_haber: Moneda,
+}
+
+impl ResumenCuadro {
+    /// Indica si el total del debe y el del haber coinciden
+    pub fn cuadra(&self) -> bool {
+        self.total_debe == self.total_haber
+    }
+}
+
+impl Display for ResumenCuadro {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Cuentas: {} ({} con saldo)", self.cuentas, self.cuentas_con_saldo)?;
+        writeln!(f, "Asientos: {}", self.asientos)?;
+        writeln!(f, "Total debe: {}", formato_importe(self.total_debe))?;
+        writeln!(f, "Total haber: {}", formato_importe(self.total_haber))?;
+        writeln!(f, "Cuadra: {}", if self.cuadra() { "sí" } else { "NO" })
+    }
+}
+
+impl Cuadro {
+
+    /// Resume el estado del cuadro: las cuentas y los totales salen de las cuentas del cuadro,
+    /// y el número de asientos, del libro diario. Los códigos de asiento de los apuntes no sirven
+    /// para contarlos, porque se repiten al consolidar cuadros de varios libros
+    pub fn resumen(&self, libro_diario: &LibroDiario) -> ResumenCuadro {
+
+        let mut total_debe = Moneda::CERO;
+        let mut total_haber = Moneda::CERO;
+
+        for apunte in self.cuentas.iter().flat_map(|c| c.apuntes()) {
+            match apunte.lado() {
+                Lado::Debe => total_debe += apunte.importe(),
+                Lado::Haber => total_haber += apunte.importe(),
+            }
+        }
+
+        ResumenCuadro {
+            cuentas: self.cuentas.len(),
+            cuentas_con_saldo: self.cuentas.iter().filter(|c| c.saldo() != Moneda::CERO).count(),
+            asientos: libro_diario.asientos().count(),
+            total_debe,
+            total_haber,
+        }
+    }
+}
+
+#[cfg(test)]
+mod resumen_tests {
+
+    use chrono::NaiveDate;
+
+    use super::*;
+    use crate::cuadro_contable::apunte::Apunte;
+    use crate::cuadro_contable::masa::Masa;
+
+    #[test]
+    fn resumen_cuenta_cuentas_asientos_y_totales() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro.crear_cuenta("Compras", "600", Masa::Gasto).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Apertura", None, vec![("572", Moneda::from_euros(1000.0))], vec![("100", Moneda::from_euros(1000.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Venta", None, vec![("572", Moneda::from_euros(250.0))], vec![("700", Moneda::from_euros(250.0))], &mut cuadro).unwrap();
+
+        let resumen = cuadro.resumen(&libro_diario);
+
+        assert_eq!(resumen, ResumenCuadro {
+            cuentas: 4,
+            cuentas_con_saldo: 3,
+            asientos: 2,
+            total_debe: Moneda::from_euros(1250.0),
+            total_haber: Moneda::from_euros(1250.0),
+        });
+        assert!(resumen.cuadra());
+        assert!(resumen.to_string().contains("Cuadra: sí"));
+    }
+
+    #[test]
+    fn resumen_detecta_un_cuadro_que_no_cuadra() {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        let fecha = NaiveDate::from_ymd_opt(2023, 8, 9).unwrap();
+        cuadro.buscar_cuenta("572").unwrap().anotar(Apunte::new(fecha, Moneda::from_euros(10.0), Lado::Debe, "202308090"));
+
+        assert!(!cuadro.resumen(&LibroDiario::new()).cuadra());
+    }
+
+    #[test]
+    fn resumen_cuenta_todos_los_asientos_de_un_cuadro_consolidado() {
+        let mut cuadros = vec![];
+        let mut libros = vec![];
+        for importe in [100.0, 200.0] {
+            let mut cuadro = Cuadro::new();
+            cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+            cuadro.crear_cuenta("Capital", "100", Masa::Patrimonio).unwrap();
+            let mut libro_diario = LibroDiario::new();
+            let fecha = NaiveDate::from_ymd_opt(2023, 8, 9);
+            libro_diario.insertar_asiento("Aportación", fecha, vec![("572", Moneda::from_euros(importe))], vec![("100", Moneda::from_euros(importe))], &mut cuadro).unwrap();
+            cuadros.push(cuadro);
+            libros.push(libro_diario);
+        }
+
+        let cuadro = Cuadro::consolidar(&cuadros.iter().collect::<Vec<&Cuadro>>());
+        let libro_diario = LibroDiario::consolidar(&libros.iter().collect::<Vec<&LibroDiario>>());
+        let resumen = cuadro.resumen(&libro_diario);
+
+        assert_eq!(resumen.asientos, 2);
+        assert_eq!(resumen.total_debe, Moneda::from_euros(300.0));
+        assert!(resumen.cuadra());
+    }
+}
@@ -0,0 +1,82 @@
+use chrono::NaiveDate;
+
+use super::{Cuadro, CuadroError, LibroDiario, LibroDiarioError};
+use super::moneda::Moneda;
+
+impl Cuadro {
+
+    /// Fija la cuenta de tesorería (normalmente bancos, 572) que hace de contrapartida
+    /// en las altas rápidas de gastos e ingresos. La cuenta ha de existir
+    pub fn fijar_cuenta_tesoreria(&mut self, codigo: &str) -> Result<(), CuadroError> {
+        if !self.cuentas.iter().any(|c| c.codigo() == codigo) {
+            return Err(CuadroError::CuentaInexistente(codigo.to_string()));
+        }
+        self.cuenta_tesoreria = Some(codigo.to_string());
+        Ok(())
+    }
+
+    /// Devuelve el código de la cuenta de tesorería, si se ha fijado
+    pub fn cuenta_tesoreria(&self) -> Option<&str> {
+        self.cuenta_tesoreria.as_deref()
+    }
+}
+
+impl LibroDiario {
+
+    /// Anota un gasto pagado con la cuenta de tesorería del cuadro: la cuenta de gasto al debe
+    /// y la de tesorería al haber
+    pub fn anotar_gasto(&mut self, concepto: &str, fecha: Option<NaiveDate>, codigo_gasto: &str, importe: Moneda, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        let tesoreria = cuadro.cuenta_tesoreria.clone().ok_or(LibroDiarioError::SinCuentaTesoreria)?;
+        self.insertar_asiento(concepto, fecha, vec![(codigo_gasto, importe)], vec![(&tesoreria, importe)], cuadro)
+    }
+
+    /// Anota un ingreso cobrado en la cuenta de tesorería del cuadro: la de tesorería al debe
+    /// y la cuenta de ingreso al haber
+    pub fn anotar_ingreso(&mut self, concepto: &str, fecha: Option<NaiveDate>, codigo_ingreso: &str, importe: Moneda, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+        let tesoreria = cuadro.cuenta_tesoreria.clone().ok_or(LibroDiarioError::SinCuentaTesoreria)?;
+        self.insertar_asiento(concepto, fecha, vec![(&tesoreria, importe)], vec![(codigo_ingreso, importe)], cuadro)
+    }
+}
+
+#[cfg(test)]
+mod tesoreria_tests {
+
+    use super::*;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup_cuadro() -> Cuadro {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Suministros", "628", Masa::Gasto).unwrap();
+        cuadro.crear_cuenta("Ventas", "700", Masa::Ingreso).unwrap();
+        cuadro
+    }
+
+    #[test]
+    fn fijar_cuenta_tesoreria_exige_que_exista() {
+        let mut cuadro = setup_cuadro();
+        assert_eq!(cuadro.cuenta_tesoreria(), None);
+
+        assert_eq!(cuadro.fijar_cuenta_tesoreria("570"), Err(CuadroError::CuentaInexistente("570".to_string())));
+        assert_eq!(cuadro.fijar_cuenta_tesoreria("572"), Ok(()));
+        assert_eq!(cuadro.cuenta_tesoreria(), Some("572"));
+    }
+
+    #[test]
+    fn anotar_gasto_e_ingreso_usan_la_cuenta_de_tesoreria() {
+        let mut cuadro = setup_cuadro();
+        let mut libro_diario = LibroDiario::new();
+        assert_eq!(
+            libro_diario.anotar_gasto("Luz", None, "628", Moneda::from_euros(45.0), &mut cuadro),
+            Err(LibroDiarioError::SinCuentaTesoreria)
+        );
+
+        cuadro.fijar_cuenta_tesoreria("572").unwrap();
+        libro_diario.anotar_ingreso("Venta", None, "700", Moneda::from_euros(200.0), &mut cuadro).unwrap();
+        libro_diario.anotar_gasto("Luz", None, "628", Moneda::from_euros(45.0), &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("572").unwrap().saldo(), Moneda::from_euros(155.0));
+        assert_eq!(cuadro.buscar_cuenta("628").unwrap().saldo(), Moneda::from_euros(45.0));
+        assert_eq!(cuadro.buscar_cuenta("700").unwrap().saldo(), Moneda::from_euros(-200.0));
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::NaiveDate;
+
+use super::{Cuadro, LibroDiario, LibroDiarioError};
+use super::masa::{self, Naturaleza};
+use super::moneda::Moneda;
+
+impl LibroDiario {
+
+    /// Anota un asiento de traspaso que mueve `importe` del saldo de la cuenta de origen a la de destino,
+    /// por ejemplo de deudores varios a clientes. Si el origen es de naturaleza deudora se carga el destino
+    /// y se abona el origen; si es acreedora, al revés. Ambas cuentas han de existir; si el origen no existe,
+    /// su lado se deduce del código, para que el error señale la línea en la que estaría.
+    pub fn traspasar_saldo(&mut self, origen: &str, destino: &str, importe: Moneda, fecha: Option<NaiveDate>, cuadro: &mut Cuadro) -> Result<(), LibroDiarioError> {
+
+        let naturaleza = cuadro.cuentas()
+            .find(|c| c.codigo() == origen)
+            .map(|c| c.naturaleza())
+            .or_else(|| masa::naturaleza_codigo(origen))
+            .unwrap_or(Naturaleza::Deudora);
+
+        let concepto = format!("Traspaso de saldo de la cuenta {} a la {}", origen, destino);
+        let (debe, haber) = match naturaleza {
+            Naturaleza::Deudora => (destino, origen),
+            Naturaleza::Acreedora => (origen, destino),
+        };
+
+        self.insertar_asiento(&concepto, fecha, vec![(debe, importe)], vec![(haber, importe)], cuadro)
+    }
+}
+
+#[cfg(test)]
+mod traspaso_tests {
+
+    use super::*;
+    use crate::cuadro_contable::apunte::Lado;
+    use crate::cuadro_contable::masa::Masa;
+
+    fn setup() -> (Cuadro, LibroDiario) {
+        let mut cuadro = Cuadro::new();
+        cuadro.crear_cuenta("Bancos", "572", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Clientes", "430", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Deudores varios", "440", Masa::ActivoCorriente).unwrap();
+        cuadro.crear_cuenta("Proveedores", "400", Masa::PasivoCorriente).unwrap();
+        cuadro.crear_cuenta("Acreedores por prestaciones de servicios", "410", Masa::PasivoCorriente).unwrap();
+        let mut libro_diario = LibroDiario::new();
+        libro_diario.insertar_asiento("Préstamo a un tercero", None, vec![("440", Moneda::from_euros(300.0))], vec![("572", Moneda::from_euros(300.0))], &mut cuadro).unwrap();
+        libro_diario.insertar_asiento("Factura", None, vec![("572", Moneda::from_euros(200.0))], vec![("400", Moneda::from_euros(200.0))], &mut cuadro).unwrap();
+        (cuadro, libro_diario)
+    }
+
+    #[test]
+    fn traspasar_saldo_deudor_carga_el_destino() {
+        let (mut cuadro, mut libro_diario) = setup();
+
+        libro_diario.traspasar_saldo("440", "430", Moneda::from_euros(300.0), None, &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("440").unwrap().saldo(), Moneda::from_euros(0.0));
+        assert_eq!(cuadro.buscar_cuenta("430").unwrap().saldo(), Moneda::from_euros(300.0));
+        assert_eq!(libro_diario.asientos().last().unwrap().concepto(), "Traspaso de saldo de la cuenta 440 a la 430");
+    }
+
+    #[test]
+    fn traspasar_saldo_acreedor_abona_el_destino() {
+        let (mut cuadro, mut libro_diario) = setup();
+
+        libro_diario.traspasar_saldo("400", "410", Moneda::from_euros(150.0), None, &mut cuadro).unwrap();
+
+        assert_eq!(cuadro.buscar_cuenta("400").unwrap().saldo(), Moneda::from_euros(-50.0));
+        assert_eq!(cuadro.buscar_cuenta("410").unwrap().saldo(), Moneda::from_euros(-150.0));
+    }
+
+    #[test]
+    fn traspasar_saldo_exige_que_existan_las_cuentas() {
+        let (mut cuadro, mut libro_diario) = setup();
+
+        assert_eq!(
+            libro_diario.traspasar_saldo("441", "430", Moneda::from_euros(10.0), None, &mut cuadro),
+            Err(LibroDiarioError::CuentaInexistenteEnLinea(Lado::Haber, 1, "441".to_string()))
+        );
+        assert_eq!(
+            libro_diario.traspasar_saldo("440", "431", Moneda::from_euros(10.0), None, &mut cuadro),
+            Err(LibroDiarioError::CuentaInexistenteEnLinea(Lado::Debe, 1, "431".to_string()))
+        );
+        assert_eq!(libro_diario.asientos().count(), 2);
+    }
+}
@@ -1 +1,2 @@
-pub mod cuadro_contable;
\ No newline at end of file
+pub mod carga;
+pub mod cuadro_contable;
@@ -1,230 +1,128 @@
-use std::fs::{self};
-use std::str::Split;
-use regex;
+use std::path::Path;
 
-use chrono::{NaiveDate, Utc};
-use presupuestos::cuadro_contable::Cuadro;
+use presupuestos::carga::{self, ResumenCarga};
+use presupuestos::cuadro_contable::{formato, Cuadro, CuadroError, LibroDiario};
 
-fn main() {
-/*
-    let mut args = std::env::args();
-
-    let mut path_diario = "diario".to_string();
-
-    if let Some(v) = args.nth(1) {
-        path_diario = v;
-    }
-
-    
-    let mut cuadro = Cuadro::new();
-
-    leer_balance_inicial(&mut cuadro);
-
-    cargar_cuadro(&mut cuadro);
-    cargar_diario(&mut cuadro, path_diario);
-
-    cuadro.print_libro_diario();
-    cuadro.print_libro_mayor();
-
-
-}
-
-/// Lee un archivo llamado 'cuadro.txt' para recuperar las cuentas, imprime error si no lo logra
-fn cargar_cuadro(cuadro: &mut Cuadro) {
-    let archivo = fs::read_to_string("cuadro.txt");
-
-    match archivo {
-        Ok(contenido) => {procesar_cadena(contenido, cuadro)},
-        Err(e) => println!("Ha habido un error al leer el archivo 'cuadro.txt'.: {e}")
-    }   
+/// Órdenes que acepta la herramienta desde la línea de comandos
+#[derive(Debug, PartialEq)]
+enum Comando {
+    /// presupuestos [diario]
+    Diario { path_diario: String },
+    /// presupuestos saldo <código> [diario]
+    Saldo { codigo_cuenta: String, path_diario: String },
+    /// presupuestos balance [diario]
+    Balance { path_diario: String },
+    /// presupuestos resultados [diario]
+    Resultados { path_diario: String },
 }
 
-/// Toma una serie leída y procesa cada línea escrita en formato <CÓDIGO> <NOMBRE> como una cuenta
-fn procesar_cadena(cadena: String, cuadro: &mut Cuadro) {
-
-    let cadena = cadena
-        .as_str();
+impl Comando {
 
-    let re_codigo: regex::Regex = regex::Regex::new(r"(?P<codigo>[0-9]+)\s(?P<nombre>.+)\n").unwrap();
+    /// Interpreta los argumentos recibidos, sin contar el nombre del programa.
+    /// Si el primero no es un subcomando conocido, se toma como la carpeta del diario
+    fn interpretar(args: &[String]) -> Result<Comando, String> {
 
-    let capturas = re_codigo.captures_iter(cadena);
+        let path_diario = |i: usize| args.get(i).cloned().unwrap_or("diario".to_string());
 
-    for c in capturas {
-        cuadro.crear_cuenta(&c["nombre"], &c["codigo"]).unwrap()
+        match args.first().map(|a| a.as_str()) {
+            Some("saldo") => match args.get(1) {
+                Some(codigo_cuenta) => Ok(Comando::Saldo { codigo_cuenta: codigo_cuenta.clone(), path_diario: path_diario(2) }),
+                None => Err("Uso: presupuestos saldo <código de cuenta> [carpeta del diario]".to_string()),
+            },
+            Some("balance") => Ok(Comando::Balance { path_diario: path_diario(1) }),
+            Some("resultados") => Ok(Comando::Resultados { path_diario: path_diario(1) }),
+            _ => Ok(Comando::Diario { path_diario: path_diario(0) }),
+        }
     }
 }
 
-/// Procesa una carpeta y procesa los posibles archivos de asientos, que deben tener formato <YYYYMMDD.data>
-fn cargar_diario(cuadro: &mut Cuadro, path: String) {
+fn main() {
 
-    let carpeta = fs::read_dir(path)
-        .expect("Imposible listar el directorio diario");
-/*
-    for ruta in carpeta {
-        if let Ok(archivo) = ruta {
-            let validado = validar_archivo(&archivo);
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-            if let Some(_fecha) = validado {
-                    leer_asientos(&archivo, cuadro);
-                }
+    match Comando::interpretar(&args) {
+        Ok(comando) => ejecutar(comando),
+        Err(uso) => {
+            println!("{uso}");
+            std::process::exit(1);
         }
-    }*/
-}
-/*
-/// Valida que la ruta y archivo son correctos. Devuelve una fecha si lo ha leído bien.
-fn validar_archivo(ruta: &fs::DirEntry) -> Option<NaiveDate> {
-
-    let mut respuesta = None;
-
-    match ruta.file_name().into_string() {
-        Ok(c) => {
-            let formato_archivo = regex::Regex::new(r"^(?P<fecha>[0-9]{8})(?:\d+)\.data$").unwrap();
-            let capturas = formato_archivo.captures(c.as_str());
-            if let Some(cap) = capturas {
-                let fecha = NaiveDate::parse_from_str(
-                    &cap["fecha"],
-                    "%Y%m%d"
-            );
-            if let Ok(f) = fecha {
-                respuesta = Some(f);
-            }
-            }
-        },
-        Err(_e) => {
-            println!("Error al procesar tu ruta, sigue siendo una cadena de sistema")
-        },
     }
-    respuesta
-}
-/*
-/// Lee todos los asientos de una ruta dada, y los guarda en el cuadro.
-fn leer_asientos(ruta: &fs::DirEntry, cuadro: &mut Cuadro) {
 
-    let mut fecha: Option<NaiveDate> = None;
-    let mut codigo: String = String::new();
-
-    let leido = fs::read_to_string(ruta.path())
-        .expect("Imposible leer el archivo");
-
-    let fecha_expr = regex::Regex::new(r"^(?P<fecha>[0-9]{8})[0-9]*").unwrap();
+}
 
-    for cap in fecha_expr.captures_iter(&ruta.file_name().into_string().unwrap()) {
-        fecha = Some(NaiveDate::parse_from_str(&cap["fecha"], "%Y%m%d").unwrap());
-        codigo = cap[0].to_string();
-    };
+/// Carga el cuadro y el diario y lleva a cabo la orden indicada
+fn ejecutar(comando: Comando) {
 
-    let concepto_expr = regex::Regex::new(r"^(?s)(?P<concepto>.+)\n\nDEBE\n(?P<debe>.+)\n\nHABER\n(?P<haber>.+)\n\n///").unwrap();
+    let mut cuadro = Cuadro::new();
+    let mut libro_diario = LibroDiario::new();
 
-    let captura = concepto_expr.captures(&leido);
+    match comando {
+        Comando::Diario { path_diario } => {
+            let resumen = cargar(&mut cuadro, &mut libro_diario, &path_diario);
 
-    if let Some(cap) = captura {
+            libro_diario.print_libro_diario(false);
 
-        // Concepto del asiento
-        let concepto = cap["concepto"].to_string();
-        /*
-        // Movimientos del debe
-        let debe: Vec<Movimiento> = cap["debe"]
-            .split('\n')
-            .map(|v| {
-                let movimiento:Vec<&str> = v.split(' ').collect();
-                let codigo_cuenta = movimiento[0].to_string();
-                let mut importe: f64 = 0.00;
+            print!("{resumen}");
 
-                if let Ok(v) = movimiento[1].trim().parse() {
-                    importe = v;
+            let futuros = libro_diario.asientos_futuros();
+            if !futuros.is_empty() {
+                println!("Asientos con fecha futura, revísalos: {}", futuros.len());
+                for asiento in futuros {
+                    println!("  N.º {} ({})", asiento.codigo(), asiento.fecha().format("%Y-%m-%d"));
+                }
+            }
+        },
+        Comando::Saldo { codigo_cuenta, path_diario } => {
+            cargar(&mut cuadro, &mut libro_diario, &path_diario);
+
+            match cuadro.buscar_cuenta(&codigo_cuenta) {
+                Some(cuenta) => println!("({}) {}: {}", cuenta.codigo(), cuenta.nombre(), formato::formato_importe(cuenta.saldo())),
+                None => {
+                    println!("{}", CuadroError::CuentaInexistente(codigo_cuenta));
+                    std::process::exit(1);
                 }
-
-                Movimiento::new(importe, codigo_cuenta)
-            })
-            .collect();
-
-        // Movimientos del haber
-        let haber: Vec<Movimiento> = cap["haber"]
-        .split('\n')
-        .map(|v| {
-            let movimiento:Vec<&str> = v.split(' ').collect();
-
-            let codigo_cuenta = movimiento[0].to_string();
-            let mut importe: f64 = 0.00;
-
-            if let Ok(v) = movimiento[1].trim().parse() {
-                importe = v;
             }
+        },
+        Comando::Balance { path_diario } => {
+            cargar(&mut cuadro, &mut libro_diario, &path_diario);
 
-            Movimiento::new(importe, codigo_cuenta)
-        })
-        .collect();  
-     */
+            print!("{}", cuadro.balance_situacion());
+        },
+        Comando::Resultados { path_diario } => {
+            cargar(&mut cuadro, &mut libro_diario, &path_diario);
 
-        cuadro.crear_asiento(concepto, fecha, debe, haber, codigo);  */
+            print!("{}", cuadro.cuenta_resultados());
+        },
     }
-}*/
-
-#[derive(Debug, PartialEq)]
-enum Masa {
-    Activo,
-    Pasivo,
-    Patrimonio,
-    Gasto,
-    Ingreso
 }
 
-fn leer_balance_inicial(cuadro: &mut Cuadro) {
-
-    let archivo = fs::read_to_string("balance_inicial.txt").unwrap();
-/*/
-    let mut vec_debe: Vec<Movimiento> = vec![];
-    let mut vec_haber: Vec<Movimiento> = vec![];
-
-    let iterador_archivo: Split<&str> = archivo.as_str().split("\n");
-
-    let mut grupo: Masa = Masa::Activo;
-
-    for linea in iterador_archivo {
-        let modo: Option<Masa> = match linea {
-            "ACTIVO" => Some(Masa::Activo),
-            "ACTIVO CORRIENTE" => Some(Masa::Activo),
-            "ACTIVO NO CORRIENTE" => Some(Masa::Activo),
-            "PASIVO" => Some(Masa::Pasivo),
-            "PASIVO CORRIENTE" => Some(Masa::Pasivo),
-            "PASIVO NO CORRIENTE" => Some(Masa:: Pasivo),
-            "PATRIMONIO NETO" => Some(Masa::Patrimonio),
-            _ => None
-        };
-
-        if let Some(mode) = modo {
-            grupo = mode;
-        } else {
-            let read: Vec<&str> = linea.split_whitespace().take(2).collect();
-
-            if let [codigo_cuenta, importe] = read[..] {
-                let importe_parsed: f64 = importe.parse::<f64>().unwrap();
-
-                let movimiento = Movimiento::new(importe_parsed, codigo_cuenta.to_string());
-
-                match grupo {
-                    Masa::Activo => vec_debe.push(movimiento),
-                    _ => vec_haber.push(movimiento),
-                }
-            }
-        };
+/// Carga los archivos del directorio de ejecución y el diario indicado, avisando de los pasos que hayan fallado
+fn cargar(cuadro: &mut Cuadro, libro_diario: &mut LibroDiario, path_diario: &str) -> ResumenCarga {
 
+    let resumen = carga::cargar(cuadro, libro_diario, Path::new("."), Path::new(path_diario));
+    for aviso in &resumen.avisos {
+        println!("{aviso}");
     }
-    cuadro.crear_asiento("Asiento de apertura".to_string(), None, vec_debe, vec_haber, generar_codigo(0));
-    cuadro.libro_diario()[0].guardar_asiento("segundo");
-
+    resumen
 }
 
- */
+#[cfg(test)]
+mod main_tests {
 
-fn generar_codigo(orden: usize) -> String {
+    use super::*;
 
-    let hoy = Utc::now().date_naive().format("%Y%m%d");
+    #[test]
+    fn interpretar_reconoce_subcomandos_y_ruta_del_diario() {
+        let args = |a: &[&str]| a.iter().map(|s| s.to_string()).collect::<Vec<String>>();
 
-    let s = format!("{}{}", hoy.to_string(), orden);
-
-    s
+        assert_eq!(Comando::interpretar(&args(&[])), Ok(Comando::Diario { path_diario: "diario".to_string() }));
+        assert_eq!(Comando::interpretar(&args(&["primero"])), Ok(Comando::Diario { path_diario: "primero".to_string() }));
+        assert_eq!(Comando::interpretar(&args(&["balance", "segundo"])), Ok(Comando::Balance { path_diario: "segundo".to_string() }));
+        assert_eq!(Comando::interpretar(&args(&["resultados"])), Ok(Comando::Resultados { path_diario: "diario".to_string() }));
+        assert_eq!(
+            Comando::interpretar(&args(&["saldo", "572"])),
+            Ok(Comando::Saldo { codigo_cuenta: "572".to_string(), path_diario: "diario".to_string() })
+        );
+        assert!(Comando::interpretar(&args(&["saldo"])).is_err());
+    }
 }
- */
-
-}
\ No newline at end of file
@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use presupuestos::carga::{cargar, ResumenCarga};
+use presupuestos::cuadro_contable::{Cuadro, LibroDiario};
+use presupuestos::cuadro_contable::asiento::TipoAsiento;
+use presupuestos::cuadro_contable::moneda::Moneda;
+
+/// Carpeta con los archivos de ejemplo de los tests de integración
+fn datos() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("data")
+}
+
+fn cargar_ejemplo() -> (Cuadro, LibroDiario, ResumenCarga) {
+    let mut cuadro = Cuadro::new();
+    let mut libro_diario = LibroDiario::new();
+    let resumen = cargar(&mut cuadro, &mut libro_diario, &datos(), &datos().join("diario"));
+    (cuadro, libro_diario, resumen)
+}
+
+#[test]
+fn cargar_lee_los_archivos_de_ejemplo() {
+    let (_, libro_diario, resumen) = cargar_ejemplo();
+
+    assert_eq!(resumen.archivos_leidos, 3);
+    assert_eq!(resumen.asientos_insertados, 3);
+    assert_eq!(resumen.total_debe, Moneda::from_euros(4840.0));
+    assert_eq!(resumen.total_haber, Moneda::from_euros(4840.0));
+    assert!(resumen.to_string().contains("Total debe: 4.840,00 €\nTotal haber: 4.840,00 €\n"));
+    assert_eq!(resumen.archivos_ignorados, vec![("notas.txt".to_string(), "la extensión no es '.data'".to_string())]);
+    assert_eq!(resumen.lineas_cuadro_ignoradas, vec![(5, "Bancos 572".to_string())]);
+    assert!(resumen.avisos.is_empty());
+
+    // El asiento de apertura, con la fecha del primer asiento del diario, más los tres del diario;
+    // la línea FECHA manda sobre el nombre del archivo
+    assert_eq!(libro_diario.asientos().count(), 4);
+    let asientos = libro_diario.asientos_ordenados();
+    assert_eq!(asientos[0].tipo(), TipoAsiento::Apertura);
+    let fechas: Vec<NaiveDate> = asientos.iter().map(|a| a.fecha()).collect();
+    assert_eq!(fechas, vec![
+        NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 1, 10).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 1, 15).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 2, 5).unwrap(),
+    ]);
+}
+
+fn saldo(cuadro: &mut Cuadro, codigo: &str) -> Moneda {
+    cuadro.buscar_cuenta(codigo).unwrap().saldo()
+}
+
+#[test]
+fn cargar_deja_los_saldos_finales_y_el_balance_cuadrado() {
+    let (mut cuadro, libro_diario, _) = cargar_ejemplo();
+
+    assert_eq!(cuadro.buscar_cuenta("5720").unwrap().nombre(), "Banco Uno, cuenta corriente");
+    // La cuenta propia manda sobre la del PGC con el mismo código, que no se carga
+    assert_eq!(cuadro.buscar_cuenta("570").unwrap().nombre(), "Caja de la tienda");
+    assert_eq!(cuadro.cuentas().filter(|c| c.codigo() == "570").count(), 1);
+    assert_eq!(saldo(&mut cuadro, "5720"), Moneda::from_euros(18790.0));
+    assert_eq!(saldo(&mut cuadro, "300"), Moneda::from_euros(5000.0));
+    assert_eq!(saldo(&mut cuadro, "100"), Moneda::from_euros(-25000.0));
+    assert_eq!(saldo(&mut cuadro, "4001"), Moneda::from_euros(0.0));
+    assert_eq!(saldo(&mut cuadro, "430"), Moneda::from_euros(2420.0));
+    assert_eq!(saldo(&mut cuadro, "472"), Moneda::from_euros(210.0));
+    assert_eq!(saldo(&mut cuadro, "477"), Moneda::from_euros(-420.0));
+
+    assert_eq!(cuadro.buscar_cuenta("5720").unwrap().saldo_apertura(), Moneda::from_euros(20000.0));
+    // El primer día del diario ya cuenta con los saldos de apertura
+    let primer_dia = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+    assert_eq!(cuadro.saldo_a_fecha("300", primer_dia), Some(Moneda::from_euros(5000.0)));
+    assert_eq!(cuadro.resultado_provisional(), Moneda::from_euros(1000.0));
+    assert_eq!(cuadro.verificar_ecuacion(), Ok(()));
+    assert!(cuadro.resumen(&libro_diario).cuadra());
+}